@@ -19,8 +19,7 @@ pub mod webserver;
 /// use sunweb::WEB_LOGGER;
 ///
 /// # fn main() -> Result<(), SetLoggerError> {
-/// log::set_logger(&WEB_LOGGER).unwrap();
-/// log::set_max_level(log::LevelFilter::Trace);
+/// WEB_LOGGER.init(log::LevelFilter::Trace)?;
 /// // Now logging will use the colored output
 /// log::info!("This is an info message");
 /// log::error!("This is an error message");
@@ -7,6 +7,7 @@
 //! # Features
 //! - HTTP/1.1 request parsing
 //! - Optional TLS support via `rustls`
+//! - HTTP/2 for TLS connections that negotiate `h2` via ALPN
 //! - Middleware support for request/response modification
 //! - Static file serving, custom routes, and reverse proxying
 //! - CORS and security headers application
@@ -29,68 +30,324 @@
 //! }
 //! ```
 use crate::webserver::Domain;
-use crate::webserver::files::get_static_file_content;
+use crate::webserver::files::{
+    StaticOptions, StaticTarget, directory_listing, open_static_file, resolve_static_path,
+    set_file_body,
+};
 use crate::webserver::http_packet::header::connection::ConnectionType;
 use crate::webserver::http_packet::header::content_types::ContentType;
+use crate::webserver::http_packet::header::content_types::multipart::MultipartSubType;
+use crate::webserver::http2;
+use crate::webserver::metrics::Metrics;
 use crate::webserver::middleware::{Middleware, MiddlewareFn};
-use crate::webserver::proxy::{Proxy, ProxySchema};
+use crate::webserver::proxy::{ConnectionPool, Proxy, ProxySchema};
 use crate::webserver::requests::HTTPRequest;
-use crate::webserver::responses::HTTPResponse;
+use crate::webserver::requests::client_certificate::ClientCertificate;
 use crate::webserver::responses::status_code::StatusCode;
-use crate::webserver::route::{Route, RouteType};
+use crate::webserver::responses::{BodyReader, HTTPResponse};
+use crate::webserver::route::{
+    BlockingPool, HTTPMethod, Handler, Route, RouteType, UpgradeHandler,
+};
+use crate::webserver::server_config::ContinueHook;
+use chrono::{DateTime, Utc};
 use log::{error, warn};
 use rustls::{ServerConfig, ServerConnection};
-use std::collections::HashMap;
-use std::io::{Read, Write};
-use std::net::TcpStream;
+use std::any::Any;
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
+use std::net::{SocketAddr, TcpStream};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
 use std::panic::{AssertUnwindSafe, catch_unwind};
 use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Size of the pieces a streamed response body is read and sent in.
+pub(crate) const STREAM_CHUNK_SIZE: usize = 16 * 1024;
+
+/// Most byte ranges one request may ask for before the whole file is sent instead.
+const MAX_BYTE_RANGES: usize = 16;
+
+/// Default limit of the request line, in bytes.
+pub(crate) const DEFAULT_MAX_REQUEST_LINE: usize = 8 * 1024;
+
+/// Default limit of the request line and headers together, in bytes.
+pub(crate) const DEFAULT_MAX_HEADER_SIZE: usize = 64 * 1024;
+
+/// Default limit of a decompressed request body, in bytes.
+pub(crate) const DEFAULT_MAX_BODY_SIZE: usize = 10 * 1024 * 1024;
+
+/// A bidirectional byte stream a client connection can be served over.
+///
+/// Implemented for [`TcpStream`] and, on Unix, for `UnixStream`. Other transports
+/// (in-memory pipes, custom sockets) only need `Read + Write`; the socket knobs
+/// default to no-ops and the peer address to `None`.
+pub trait ClientStream: Read + Write + Send {
+    /// Returns the address of the remote peer, if the transport has one.
+    fn peer_addr(&self) -> Option<SocketAddr> {
+        None
+    }
+
+    /// Sets the timeout for blocking reads (`None` blocks indefinitely).
+    fn set_read_timeout(&self, _timeout: Option<Duration>) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Sets the timeout for blocking writes (`None` blocks indefinitely).
+    fn set_write_timeout(&self, _timeout: Option<Duration>) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Switches the stream between blocking and non-blocking mode.
+    fn set_nonblocking(&self, _nonblocking: bool) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Enables or disables Nagle's algorithm (`TCP_NODELAY`).
+    fn set_nodelay(&self, _nodelay: bool) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl ClientStream for TcpStream {
+    fn peer_addr(&self) -> Option<SocketAddr> {
+        TcpStream::peer_addr(self).ok()
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        TcpStream::set_read_timeout(self, timeout)
+    }
+
+    fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        TcpStream::set_write_timeout(self, timeout)
+    }
+
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        TcpStream::set_nonblocking(self, nonblocking)
+    }
+
+    fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
+        TcpStream::set_nodelay(self, nodelay)
+    }
+}
+
+#[cfg(unix)]
+impl ClientStream for UnixStream {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        UnixStream::set_read_timeout(self, timeout)
+    }
+
+    fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        UnixStream::set_write_timeout(self, timeout)
+    }
+
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        UnixStream::set_nonblocking(self, nonblocking)
+    }
+}
+
+/// The connection handed to the handler of an upgrade route.
+///
+/// Reads and writes go to the client directly, through TLS if the connection
+/// is encrypted. See [`WebServer::add_upgrade_route`](crate::webserver::WebServer::add_upgrade_route).
+pub trait UpgradedStream: Read + Write {}
+
+impl<T: Read + Write + ?Sized> UpgradedStream for T {}
+
+/// An in-memory [`ClientStream`] replaying a fixed request and capturing the
+/// bytes written back, used to run requests through a `Client` without a socket.
+pub(crate) struct MemoryStream {
+    /// Bytes the client will read.
+    input: Cursor<Vec<u8>>,
+    /// Bytes the client wrote.
+    pub(crate) output: Vec<u8>,
+    /// Peer address reported to the request.
+    peer: Option<SocketAddr>,
+}
+
+impl MemoryStream {
+    /// Creates a stream that yields `input` and reports `peer` as its remote address.
+    pub(crate) fn new(input: &[u8], peer: Option<SocketAddr>) -> Self {
+        Self {
+            input: Cursor::new(input.to_vec()),
+            output: Vec::new(),
+            peer,
+        }
+    }
+}
+
+impl Read for MemoryStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.input.read(buf)
+    }
+}
+
+impl Write for MemoryStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.output.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl ClientStream for MemoryStream {
+    fn peer_addr(&self) -> Option<SocketAddr> {
+        self.peer
+    }
+}
+
+/// Why no request could be read from a connection.
+#[derive(Debug)]
+enum ReadError {
+    /// The client closed the connection after sending `received` bytes of a request.
+    Closed { received: usize },
+    /// The read timeout expired after `received` bytes of request headers.
+    Timeout { received: usize },
+    /// The continue hook answered with a final response before the body was read.
+    Rejected(Box<HTTPResponse>),
+    /// The request line or header block exceeded its limit; answered with this status.
+    TooLarge(StatusCode),
+    /// The connection failed.
+    Io(io::Error),
+}
+
+/// Called with every `5xx` response before it is sent.
+pub(crate) type ErrorReporter = Arc<dyn Fn(&HTTPRequest, &HTTPResponse) + Send + Sync>;
 
 /// Represents a client connected to the webserver.
 ///
-/// The `Client` struct handles reading from the TCP stream (or TLS stream if configured),
+/// The `Client` struct handles reading from the underlying stream (or TLS stream if configured),
 /// parsing HTTP requests, applying middleware, routing to the appropriate handler,
 /// and sending HTTP responses.
-pub(crate) struct Client {
-    /// The TCP stream connected to the client.
-    stream: TcpStream,
+pub(crate) struct Client<S: ClientStream> {
+    /// The stream connected to the client (TCP or Unix socket).
+    stream: S,
     /// Map of domains to their route configurations.
     domains: Arc<Mutex<HashMap<Domain, Arc<Mutex<Vec<Route>>>>>>,
     /// Default domain used when no matching domain is found.
     default_domain: Domain,
     /// Middleware to apply for requests and responses.
     middleware: Arc<Vec<Middleware>>,
+    /// Request metrics shared with the server.
+    metrics: Arc<Metrics>,
     /// Optional TLS configuration.
     tls_config: Option<Arc<ServerConfig>>,
+    /// `Strict-Transport-Security` value added to responses sent over TLS.
+    hsts: Option<String>,
     /// Optional active TLS connection.
     tls_connection: Option<ServerConnection>,
+    /// Protocol negotiated via ALPN during the TLS handshake.
+    alpn_protocol: Option<String>,
+    /// Certificate the client presented during the TLS handshake.
+    client_certificate: Option<ClientCertificate>,
+    /// Number of requests served before the connection is closed; `None` for no limit.
+    pub(crate) max_requests: Option<u32>,
+    /// How long a read may block before the request is considered complete or abandoned.
+    pub(crate) read_timeout: Duration,
+    /// How long a write may block; `None` blocks indefinitely.
+    pub(crate) write_timeout: Option<Duration>,
+    /// Shared pool of idle proxy upstream connections; `None` opens one per request.
+    pub(crate) proxy_pool: Option<Arc<ConnectionPool>>,
+    /// How long a custom handler may run before `504` is sent; `None` waits indefinitely.
+    pub(crate) handler_timeout: Option<Duration>,
+    /// Decides whether an `Expect: 100-continue` request may send its body.
+    pub(crate) continue_hook: Option<ContinueHook>,
+    /// Reports `5xx` responses before they are sent.
+    pub(crate) error_reporter: Option<ErrorReporter>,
+    /// Rejects malformed header lines instead of accepting them.
+    pub(crate) strict_parsing: bool,
+    /// Longest request line accepted before `414 URI Too Long` is sent.
+    pub(crate) max_request_line: usize,
+    /// Largest header block accepted before `431 Request Header Fields Too Large` is sent.
+    pub(crate) max_header_size: usize,
+    /// Largest decompressed body accepted before `413 Content Too Large` is sent.
+    pub(crate) max_body_size: usize,
+    /// Sends small writes immediately instead of coalescing them (`TCP_NODELAY`).
+    pub(crate) tcp_nodelay: bool,
+    /// Upgrade handler chosen by routing, run once the request has been handled.
+    pending_upgrade: Option<(UpgradeHandler, HTTPRequest)>,
 }
 
-impl Client {
+impl<S: ClientStream> Client<S> {
     /// Creates a new `Client` instance.
     ///
     /// # Arguments
-    /// * `stream` - The TCP stream for this client.
+    /// * `stream` - The stream for this client.
     /// * `domains` - Shared map of domain routes.
     /// * `default_domain` - Default domain for unmatched requests.
     /// * `middleware` - Middleware to apply.
+    /// * `metrics` - Shared metrics every handled request is recorded in.
     /// * `tls_config` - Optional TLS server configuration.
+    /// * `hsts` - Optional `Strict-Transport-Security` value for TLS responses.
     pub(crate) fn new(
-        stream: TcpStream,
+        stream: S,
         domains: Arc<Mutex<HashMap<Domain, Arc<Mutex<Vec<Route>>>>>>,
         default_domain: Domain,
         middleware: Arc<Vec<Middleware>>,
+        metrics: Arc<Metrics>,
         tls_config: Option<Arc<ServerConfig>>,
+        hsts: Option<String>,
     ) -> Self {
         Self {
             stream,
             domains,
             default_domain,
             middleware,
+            metrics,
             tls_config,
+            hsts,
             tls_connection: None,
+            alpn_protocol: None,
+            client_certificate: None,
+            max_requests: None,
+            read_timeout: Duration::from_millis(500),
+            write_timeout: None,
+            proxy_pool: None,
+            handler_timeout: None,
+            continue_hook: None,
+            error_reporter: None,
+            strict_parsing: false,
+            max_request_line: DEFAULT_MAX_REQUEST_LINE,
+            max_header_size: DEFAULT_MAX_HEADER_SIZE,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            tcp_nodelay: true,
+            pending_upgrade: None,
+        }
+    }
+
+    /// Drops the TLS configuration, so the stream is served as plain HTTP.
+    pub(crate) fn without_tls(mut self) -> Self {
+        self.tls_config = None;
+        self.hsts = None;
+        self
+    }
+
+    /// Consumes the client, returning the underlying stream.
+    pub(crate) fn into_stream(self) -> S {
+        self.stream
+    }
+
+    /// Serves requests on this connection until the client closes it or stops
+    /// asking for keep-alive.
+    pub(crate) fn serve(&mut self) {
+        let _ = self.stream.set_nodelay(self.tcp_nodelay);
+        let _ = self.stream.set_write_timeout(self.write_timeout);
+        let mut i = 0;
+        while let Some(connection_type) = self.handle(i) {
+            match connection_type {
+                ConnectionType::KeepAlive => i += 1,
+                _ => {
+                    error!("Connection closed: {connection_type}");
+                    break;
+                }
+            }
         }
     }
 
@@ -105,62 +362,184 @@ impl Client {
     /// Returns `Some(ConnectionType)` to indicate whether the connection should
     /// be kept alive, or `None` if the connection closed or an error occurred.
     pub(crate) fn handle(&mut self, i: u32) -> Option<ConnectionType> {
-        let raw_request = if self.tls_config.is_some() && i == 0 {
-            self.handle_tls_connection()?
-        } else {
-            self.read_http_request()?
+        if self.tls_config.is_some() && i == 0 && !self.start_tls() {
+            return None;
+        }
+
+        let raw_request = match self.read_http_request() {
+            Ok(raw_request) => raw_request,
+            // The client closed the connection or left it idle between requests.
+            Err(ReadError::Closed { received: 0 } | ReadError::Timeout { received: 0 }) => {
+                return None;
+            }
+            Err(ReadError::Closed { received }) => {
+                warn!("Connection closed after {received} bytes of an incomplete request");
+                return None;
+            }
+            Err(ReadError::Timeout { received }) => {
+                warn!("Timed out after {received} bytes of an incomplete request");
+                let mut response = HTTPResponse::request_timeout();
+                response.message.headers.connection = ConnectionType::Close;
+                self.send_response(response);
+                return None;
+            }
+            Err(ReadError::Rejected(mut response)) => {
+                response.message.headers.connection = ConnectionType::Close;
+                self.send_response(*response);
+                return None;
+            }
+            Err(ReadError::TooLarge(status_code)) => {
+                warn!("Rejected request: {status_code}");
+                let mut response = HTTPResponse::new(status_code);
+                response.message.headers.connection = ConnectionType::Close;
+                self.send_response(response);
+                return None;
+            }
+            Err(ReadError::Io(e)) => {
+                warn!("Socket read error: {e}");
+                return None;
+            }
         };
 
-        let request = match HTTPRequest::parse(raw_request.as_ref()) {
+        let request = match HTTPRequest::parse_complete(
+            &raw_request,
+            self.strict_parsing,
+            self.max_body_size,
+        ) {
             Ok(req) => req,
-            Err(_) => {
-                error!("Failed to parse HTTP request");
+            Err(e) => {
+                error!("Failed to parse HTTP request: {e}");
+                let mut response = HTTPResponse::new(e.status_code());
+                response.message.headers.connection = ConnectionType::Close;
+                self.send_response(response);
                 return None;
             }
         };
 
-        let connection = request.headers().connection.clone();
-        let modified_request = self.apply_request_middleware(request.clone());
-        let response = self.handle_routing(modified_request);
-        let final_response = self.apply_response_middleware(request, response);
+        let started = Instant::now();
+        let mut connection = match request.keep_alive() {
+            true => ConnectionType::KeepAlive,
+            false => ConnectionType::Close,
+        };
+        let http_1_0 = request.message.http_version == "HTTP/1.0";
+        let mut final_response = self.respond(request);
+        // Response middleware may have answered in place of the upgrade, e.g.
+        // to turn away an unauthenticated client; that response is sent instead.
+        if let Some((upgrade, request)) = self.pending_upgrade.take()
+            && final_response.status_code == StatusCode::SwitchingProtocols
+        {
+            self.metrics
+                .record(StatusCode::SwitchingProtocols, started.elapsed());
+            self.run_upgrade(&upgrade, request);
+            return None;
+        }
+        // HTTP/1.0 has no chunked encoding; closing the connection ends the body.
+        if http_1_0
+            && final_response
+                .body_reader
+                .as_ref()
+                .is_some_and(|body| body.len().is_none())
+        {
+            final_response.unframed = true;
+            connection = ConnectionType::Close;
+        }
+        if connection == ConnectionType::Close || self.max_requests.is_some_and(|max| i + 1 >= max)
+        {
+            connection = ConnectionType::Close;
+            final_response.message.headers.connection = ConnectionType::Close;
+        }
 
-        self.send_response(final_response);
+        let status_code = final_response.status_code;
+        if !self.send_response(final_response) {
+            connection = ConnectionType::Close;
+        }
+        self.metrics.record(status_code, started.elapsed());
 
         Some(connection)
     }
 
-    /// Reads an HTTP request from the TCP stream.
+    /// Produces the response to a parsed request.
+    ///
+    /// Attaches the connection details to the request, applies middleware and
+    /// routing, and adds the `Strict-Transport-Security` header on TLS connections.
+    /// `5xx` responses are passed to the error reporter, if one is set.
+    fn respond(&mut self, mut request: HTTPRequest) -> HTTPResponse {
+        request.peer_addr = self.stream.peer_addr();
+        request.alpn_protocol = self.alpn_protocol.clone();
+        request.client_certificate = self.client_certificate.clone();
+
+        let reported_request = self.error_reporter.as_ref().map(|_| request.clone());
+        let modified_request = self.apply_request_middleware(&self.middleware, request.clone());
+        let response = self.handle_routing(modified_request);
+        let mut final_response =
+            self.apply_response_middleware(&self.middleware, request, response);
+        if let (Some(reporter), Some(request)) = (&self.error_reporter, reported_request)
+            && final_response.status_code.as_u16() >= 500
+        {
+            reporter(&request, &final_response);
+        }
+        if let (Some(hsts), Some(_)) = (&self.hsts, &self.tls_connection)
+            && final_response
+                .message
+                .headers
+                .get_header("Strict-Transport-Security")
+                .is_none()
+        {
+            final_response
+                .message
+                .headers
+                .add_header("Strict-Transport-Security", hsts);
+        }
+        final_response
+    }
+
+    /// Reads an HTTP request from the client stream, through TLS if established.
     ///
-    /// Handles reading headers and body based on `Content-Length`.
-    fn read_http_request(&mut self) -> Option<String> {
-        let _ = self
-            .stream
-            .set_read_timeout(Some(Duration::from_millis(500)));
+    /// Handles reading headers and body based on `Content-Length`. If the client
+    /// sent `Expect: 100-continue` and the body has not arrived yet, an interim
+    /// `100 Continue` response is written before reading it, unless the
+    /// continue hook rejects the request. A body that does
+    /// not arrive completely is returned as far as it was received.
+    fn read_http_request(&mut self) -> Result<Vec<u8>, ReadError> {
+        let _ = self.stream.set_read_timeout(Some(self.read_timeout));
 
         let mut buffer = Vec::with_capacity(2048);
         let mut chunk = [0u8; 1024];
-        let mut headers_end_pos = 0;
+        let headers_end_pos;
 
         loop {
-            match self.stream.read(&mut chunk) {
-                Ok(0) => return None,
+            match self.read_some(&mut chunk) {
+                Ok(0) => {
+                    return Err(ReadError::Closed {
+                        received: buffer.len(),
+                    });
+                }
                 Ok(n) => {
                     buffer.extend_from_slice(&chunk[..n]);
-                    if let Some(pos) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+                    let line_end = buffer.iter().position(|&b| b == b'\n');
+                    if line_end.unwrap_or(buffer.len()) > self.max_request_line {
+                        return Err(ReadError::TooLarge(StatusCode::UriTooLong));
+                    }
+                    let end = buffer.windows(4).position(|w| w == b"\r\n\r\n");
+                    if end.map_or(buffer.len(), |pos| pos + 4) > self.max_header_size {
+                        return Err(ReadError::TooLarge(StatusCode::RequestHeaderFieldsTooLarge));
+                    }
+                    if let Some(pos) = end {
                         headers_end_pos = pos + 4;
                         break;
                     }
                 }
-                Err(e)
-                    if e.kind() == std::io::ErrorKind::WouldBlock
-                        || e.kind() == std::io::ErrorKind::TimedOut =>
-                {
-                    break;
+                Err(e) if is_timeout(&e) => {
+                    return Err(ReadError::Timeout {
+                        received: buffer.len(),
+                    });
                 }
-                Err(e) => {
-                    warn!("Socket read error: {e}");
-                    return None;
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                    return Err(ReadError::Closed {
+                        received: buffer.len(),
+                    });
                 }
+                Err(e) => return Err(ReadError::Io(e)),
             }
         }
 
@@ -172,16 +551,36 @@ impl Client {
             .and_then(|v| v.trim().parse().ok())
             .unwrap_or(0);
 
+        let expects_continue = headers_str.lines().any(|l| {
+            l.split_once(':').is_some_and(|(name, value)| {
+                name.trim().eq_ignore_ascii_case("expect")
+                    && value.trim().eq_ignore_ascii_case("100-continue")
+            })
+        });
+        if expects_continue && buffer.len() < headers_end_pos + content_length {
+            if let Some(hook) = &self.continue_hook
+                && let Ok(request) = HTTPRequest::parse_head(
+                    &buffer[..headers_end_pos],
+                    self.strict_parsing,
+                    self.max_body_size,
+                )
+                && let Some(response) = hook(&request)
+            {
+                return Err(ReadError::Rejected(Box::new(response)));
+            }
+            let interim = format!(
+                "HTTP/1.1 {} {}\r\n\r\n",
+                StatusCode::Continue.as_u16(),
+                StatusCode::Continue
+            );
+            let _ = self.write_all(interim.as_bytes());
+        }
+
         while buffer.len() < headers_end_pos + content_length {
-            match self.stream.read(&mut chunk) {
+            match self.read_some(&mut chunk) {
                 Ok(0) => break,
                 Ok(n) => buffer.extend_from_slice(&chunk[..n]),
-                Err(e)
-                    if e.kind() == std::io::ErrorKind::WouldBlock
-                        || e.kind() == std::io::ErrorKind::TimedOut =>
-                {
-                    break;
-                }
+                Err(e) if is_timeout(&e) => break,
                 Err(e) => {
                     warn!("Failed to read body: {e}");
                     break;
@@ -189,68 +588,136 @@ impl Client {
             }
         }
 
-        Some(String::from_utf8_lossy(&buffer).into())
+        Ok(buffer)
     }
 
-    /// Handles TLS connections, performing handshake and reading initial request.
-    fn handle_tls_connection(&mut self) -> Option<String> {
-        let tls_cfg = self.tls_config.as_ref()?.clone();
-        let mut conn = self.perform_tls_handshake(tls_cfg)?;
-        let buffer = self.read_tls_data(&mut conn)?;
-        self.tls_connection = Some(conn);
-        Some(String::from_utf8_lossy(&buffer).to_string())
+    /// Reads from the client, through TLS if established.
+    fn read_some(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.tls_stream() {
+            Some(mut io) => io.read(buf),
+            None => self.stream.read(buf),
+        }
     }
 
-    /// Performs a TLS handshake and returns a `ServerConnection`.
-    fn perform_tls_handshake(&mut self, tls_config: Arc<ServerConfig>) -> Option<ServerConnection> {
-        let mut conn = ServerConnection::new(tls_config).ok()?;
-        while conn.is_handshaking() {
-            if conn.complete_io(&mut self.stream).is_err() {
-                return None;
-            }
+    /// Performs the TLS handshake and records what it negotiated.
+    ///
+    /// Connections that negotiate `h2` are served entirely over HTTP/2 here.
+    /// Returns `false` if the connection is finished, either because the
+    /// handshake failed or because the HTTP/2 connection ended.
+    fn start_tls(&mut self) -> bool {
+        let Some(tls_cfg) = self.tls_config.clone() else {
+            return false;
+        };
+        let Some(conn) = self.perform_tls_handshake(tls_cfg) else {
+            return false;
+        };
+        self.alpn_protocol = conn
+            .alpn_protocol()
+            .map(|p| String::from_utf8_lossy(p).into_owned());
+        self.client_certificate = conn
+            .peer_certificates()
+            .and_then(|certs| certs.first())
+            .and_then(|cert| ClientCertificate::from_der(cert));
+        self.tls_connection = Some(conn);
+        if self.alpn_protocol.as_deref() == Some("h2") {
+            self.serve_http2();
+            return false;
         }
-        Some(conn)
+        true
     }
 
-    /// Reads plaintext data from an established TLS connection.
-    fn read_tls_data(&mut self, conn: &mut ServerConnection) -> Option<Vec<u8>> {
-        let _ = self.stream.set_nonblocking(true);
-        let mut buffer = Vec::with_capacity(2048);
-        let mut chunk = [0u8; 2048];
+    /// Serves an HTTP/2 connection until the client closes it or sends GOAWAY.
+    ///
+    /// Requests are read and answered one at a time.
+    fn serve_http2(&mut self) {
+        let _ = self.stream.set_read_timeout(Some(self.read_timeout));
+        let mut h2 = http2::Connection::new(self.max_header_size, self.max_body_size);
+        if let Err(e) = self.tls_stream().map_or(Ok(()), |mut io| h2.start(&mut io)) {
+            warn!("HTTP/2 handshake failed: {e}");
+            return;
+        }
 
         loop {
-            if conn.complete_io(&mut self.stream).is_err() {
-                return None;
-            }
+            let (stream_id, raw_request) =
+                match self.tls_stream().map(|mut io| h2.read_request(&mut io)) {
+                    Some(Ok(Some(request))) => request,
+                    Some(Err(e)) => {
+                        warn!("HTTP/2 connection error: {e}");
+                        return;
+                    }
+                    _ => return,
+                };
 
-            match conn.reader().read(&mut chunk) {
-                Ok(0) => break,
-                Ok(n) => buffer.extend_from_slice(&chunk[..n]),
-                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
-                Err(_) => return None,
-            }
+            let started = Instant::now();
+            let response = match HTTPRequest::parse_complete(
+                &raw_request,
+                self.strict_parsing,
+                self.max_body_size,
+            ) {
+                Ok(request) => {
+                    let response = self.respond(request);
+                    // HTTP/2 streams cannot be taken over like an HTTP/1.1 connection.
+                    match self.pending_upgrade.take() {
+                        Some(_) => HTTPResponse::new(StatusCode::NotImplemented),
+                        None => response,
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to parse HTTP/2 request: {e}");
+                    HTTPResponse::new(e.status_code())
+                }
+            };
 
-            if buffer.windows(4).any(|w| w == b"\r\n\r\n") {
-                break;
+            let status_code = response.status_code;
+            if let Some(Err(e)) = self
+                .tls_stream()
+                .map(|mut io| h2.send_response(&mut io, stream_id, &response))
+            {
+                warn!("Error writing HTTP/2 response: {e}");
+                return;
             }
+            self.metrics.record(status_code, started.elapsed());
         }
+    }
 
-        if buffer.is_empty() {
-            None
-        } else {
-            Some(buffer)
+    /// Hands the connection to an upgrade handler, which owns it until it returns.
+    fn run_upgrade(&mut self, upgrade: &UpgradeHandler, request: HTTPRequest) {
+        // The handler decides itself how long to wait for the client.
+        let _ = self.stream.set_read_timeout(None);
+        let result = catch_unwind(AssertUnwindSafe(|| match self.tls_stream() {
+            Some(mut io) => upgrade(request, &mut io),
+            None => upgrade(request, &mut self.stream),
+        }));
+        if result.is_err() {
+            error!("Upgrade handler panicked");
         }
     }
 
-    /// Applies request middleware in order for this request.
-    fn apply_request_middleware(&self, mut request: HTTPRequest) -> HTTPRequest {
-        for middleware in self.middleware.iter() {
-            if middleware.route.as_str() != request.path && middleware.route.as_str() != "*" {
-                continue;
+    /// Returns a plaintext stream over the active TLS connection.
+    fn tls_stream(&mut self) -> Option<rustls::Stream<'_, ServerConnection, S>> {
+        let conn = self.tls_connection.as_mut()?;
+        Some(rustls::Stream::new(conn, &mut self.stream))
+    }
+
+    /// Performs a TLS handshake and returns a `ServerConnection`.
+    fn perform_tls_handshake(&mut self, tls_config: Arc<ServerConfig>) -> Option<ServerConnection> {
+        let mut conn = ServerConnection::new(tls_config).ok()?;
+        while conn.is_handshaking() {
+            if conn.complete_io(&mut self.stream).is_err() {
+                return None;
             }
-            if middleware.domain.as_str() != "*"
-                && middleware.domain.as_str() != request.host().unwrap_or_default()
-            {
+        }
+        Some(conn)
+    }
+
+    /// Applies request middleware in order for this request.
+    fn apply_request_middleware(
+        &self,
+        middleware: &[Middleware],
+        mut request: HTTPRequest,
+    ) -> HTTPRequest {
+        for middleware in middleware {
+            if !self.middleware_applies(middleware, &request) {
                 continue;
             }
 
@@ -266,10 +733,15 @@ impl Client {
     /// Applies response middleware in order for this response.
     fn apply_response_middleware(
         &self,
+        middleware: &[Middleware],
         mut original_request: HTTPRequest,
         mut response: HTTPResponse,
     ) -> HTTPResponse {
-        for middleware in self.middleware.iter() {
+        for middleware in middleware {
+            if !self.middleware_applies(middleware, &original_request) {
+                continue;
+            }
+
             match &middleware.f {
                 MiddlewareFn::HTTPResponse(func) => func(&mut response),
                 MiddlewareFn::BothHTTPResponse(func) => {
@@ -277,18 +749,12 @@ impl Client {
                 }
                 MiddlewareFn::Both(_, res_func) => response = res_func(response),
                 MiddlewareFn::HTTPResponseBothWithRoutes(func) => {
-                    response = func(
-                        &mut original_request,
-                        response,
-                        &*self
-                            .domains
-                            .lock()
-                            .unwrap()
-                            .get(&self.default_domain)
-                            .unwrap()
-                            .lock()
-                            .unwrap(),
-                    )
+                    response = match self.domain_routes(&original_request) {
+                        Some(routes) => {
+                            func(&mut original_request, response, &routes.lock().unwrap())
+                        }
+                        None => func(&mut original_request, response, &[]),
+                    }
                 }
                 _ => {}
             }
@@ -296,9 +762,67 @@ impl Client {
         response
     }
 
+    /// Whether `middleware` is scoped to the request's path and domain.
+    fn middleware_applies(&self, middleware: &Middleware, request: &HTTPRequest) -> bool {
+        middleware.matches_path(&request.decoded_path())
+            && (middleware.domain.as_str() == "*"
+                || middleware
+                    .domain
+                    .matches(&self.request_domain(request).name))
+            && !(middleware.access_log && self.skips_access_log(request))
+    }
+
+    /// Whether the request goes to a route kept out of the access log.
+    fn skips_access_log(&self, request: &HTTPRequest) -> bool {
+        let path = request.decoded_path();
+        self.domain_routes(request).is_some_and(|routes| {
+            routes
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|route| !route.access_log && route.route == path)
+        })
+    }
+
+    /// The domain named by the request's `Host` header, or the default domain
+    /// for HTTP/1.0 requests without one.
+    fn request_domain(&self, request: &HTTPRequest) -> Domain {
+        request
+            .host()
+            .map(|host| Domain::new(&host))
+            .unwrap_or_else(|| self.default_domain.clone())
+    }
+
+    /// Returns the routes of the domain the request is addressed to, falling
+    /// back to the most specific matching wildcard domain and then to the
+    /// default domain when its host has no router.
+    fn domain_routes(&self, request: &HTTPRequest) -> Option<Arc<Mutex<Vec<Route>>>> {
+        let domain = self.request_domain(request);
+        let guard = self.domains.lock().unwrap();
+        guard
+            .get(&domain)
+            .or_else(|| {
+                guard
+                    .iter()
+                    .filter(|(candidate, _)| {
+                        candidate.is_wildcard() && candidate.matches(&domain.name)
+                    })
+                    .max_by_key(|(candidate, _)| candidate.name.len())
+                    .map(|(_, routes)| routes)
+            })
+            .or_else(|| guard.get(&self.default_domain))
+            .cloned()
+    }
+
     /// Sends an HTTP response to the client, over TLS if applicable.
-    fn send_response(&mut self, response: HTTPResponse) {
+    ///
+    /// Returns `false` if a streamed body could not be sent completely, in
+    /// which case the connection must not be reused.
+    fn send_response(&mut self, response: HTTPResponse) -> bool {
         let response_bytes = response.to_bytes();
+        let forbids_body = response.forbids_body();
+        let unframed = response.unframed;
+        let body_reader = response.body_reader.filter(|_| !forbids_body);
 
         if let Some(conn) = &mut self.tls_connection {
             let chunk_size = 4096;
@@ -312,11 +836,11 @@ impl Client {
                     .is_err()
                 {
                     warn!("Error writing to TLS stream");
-                    return;
+                    return false;
                 }
                 if conn.complete_io(&mut self.stream).is_err() {
                     warn!("Error completing TLS write");
-                    return;
+                    return false;
                 }
                 offset = end;
             }
@@ -330,68 +854,194 @@ impl Client {
             let _ = self.stream.write_all(&response_bytes);
             let _ = self.stream.flush();
         }
+
+        match body_reader {
+            Some(body) => match self.stream_body(&body, unframed) {
+                Ok(()) => true,
+                Err(e) => {
+                    warn!("Error streaming response body: {e}");
+                    false
+                }
+            },
+            None => true,
+        }
+    }
+
+    /// Copies a streamed body to the client, chunk-encoded if its length is
+    /// unknown and the response is not `unframed`.
+    fn stream_body(&mut self, body: &BodyReader, unframed: bool) -> io::Result<()> {
+        let Some(mut reader) = body.take() else {
+            return Err(io::Error::other("Response body was already sent"));
+        };
+        let chunked = body.len().is_none() && !unframed;
+        let mut buffer = vec![0; STREAM_CHUNK_SIZE];
+        let mut sent = 0;
+
+        loop {
+            let n = match reader.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            sent += n as u64;
+            if chunked {
+                let mut chunk = format!("{n:X}\r\n").into_bytes();
+                chunk.extend_from_slice(&buffer[..n]);
+                chunk.extend_from_slice(b"\r\n");
+                self.write_all(&chunk)?;
+            } else {
+                self.write_all(&buffer[..n])?;
+            }
+        }
+
+        if chunked {
+            self.write_all(b"0\r\n\r\n")?;
+        } else if body.len().is_some_and(|len| sent < len) {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Response body shorter than Content-Length",
+            ));
+        }
+
+        match self.tls_stream() {
+            Some(mut io) => io.flush(),
+            None => self.stream.flush(),
+        }
+    }
+
+    /// Writes bytes to the client, over TLS if applicable.
+    fn write_all(&mut self, bytes: &[u8]) -> io::Result<()> {
+        match self.tls_stream() {
+            Some(mut io) => io.write_all(bytes),
+            None => self.stream.write_all(bytes),
+        }
     }
 
     /// Routes the HTTP request to the appropriate handler.
     ///
     /// Handles static files, custom routes, proxy routes, and error routes.
-    fn handle_routing(&mut self, request: HTTPRequest) -> HTTPResponse {
-        let host = request.host().unwrap_or_default();
-        let current_domain = Domain::new(&host);
-
-        let guard = self.domains.lock().unwrap();
-        let routes_mutex = guard
-            .get(&current_domain)
-            .or_else(|| guard.get(&self.default_domain));
-
-        let Some(routes_mutex) = routes_mutex else {
+    fn handle_routing(&mut self, mut request: HTTPRequest) -> HTTPResponse {
+        let Some(routes_mutex) = self.domain_routes(&request) else {
             return HTTPResponse::not_found();
         };
 
         let routes = routes_mutex.lock().unwrap();
 
-        // Longest prefix match
-        let matched_prefix = routes
+        // Routes registered for exactly this path take precedence over the
+        // longest prefix match.
+        let path = request.decoded_path();
+        let has_exact = routes.iter().any(|r| r.route == path);
+        let matched = routes
             .iter()
-            .filter(|r| request.path.starts_with(&r.route) && r.method == request.method)
+            .filter(|r| r.method == request.method)
+            .filter(|r| match has_exact {
+                true => r.route == path,
+                false => path.starts_with(&r.route),
+            })
             .max_by_key(|r| r.route.len());
 
-        let route = match matched_prefix {
-            Some(r) => r,
-            None => return HTTPResponse::not_found(),
+        let Some(exact) = matched else {
+            let allowed = allowed_methods(&routes, &path);
+            if request.method == HTTPMethod::OPTIONS && !allowed.is_empty() {
+                let mut response = HTTPResponse::no_content();
+                response.add_header("Allow", &allow_header(&allowed));
+                return response;
+            }
+            if !has_exact {
+                return HTTPResponse::not_found();
+            }
+            let mut response = HTTPResponse::method_not_allowed();
+            response.add_header("Allow", &allow_header(&allowed));
+            return response;
         };
-
-        let exact = routes
-            .iter()
-            .find(|r| r.route == request.path)
-            .unwrap_or(route);
-
-        if exact.method != request.method {
-            return HTTPResponse::method_not_allowed();
-        }
+        // Release the routes so slow handlers don't hold up other requests.
+        let exact = exact.clone();
+        drop(routes);
+        request.matched_route = Some(exact.route.clone());
 
         match exact.route_type {
             RouteType::Static => {
-                if let Some(folder) = &exact.folder {
-                    return get_static_file_response(folder, &request);
+                if let (Some(folder), Some(options)) = (&exact.folder, &exact.static_options) {
+                    return get_static_file_response(folder, options, &request);
                 }
             }
             RouteType::File => {
                 if let Some(content) = &exact.content {
                     let mut response = HTTPResponse::new(exact.status_code);
                     response.set_body_string(content.to_string());
+                    if let Some(content_type) = &exact.content_type {
+                        response.set_content_type(content_type.clone());
+                    }
+                    if let Some(cache_control) = &exact.cache_control {
+                        response
+                            .message
+                            .headers
+                            .set_cache_control(&cache_control.as_str());
+                    }
+                    return response;
+                }
+            }
+            RouteType::Bytes => {
+                if let Some(bytes) = &exact.bytes {
+                    let mut response = HTTPResponse::new(exact.status_code);
+                    response.set_body(bytes.to_vec());
+                    if let Some(content_type) = &exact.content_type {
+                        response.set_content_type(content_type.clone());
+                    }
                     return response;
                 }
             }
             RouteType::Custom => {
                 if let Some(f) = &exact.f {
-                    return catch_unwind(AssertUnwindSafe(|| f(request, &exact.domain)))
-                        .unwrap_or_else(|_| HTTPResponse::internal_error());
+                    // Handlers see the host that was requested, not the router they were registered on.
+                    let domain = request
+                        .host()
+                        .map(|host| Domain::new(&host))
+                        .unwrap_or_else(|| exact.domain.clone());
+                    if exact.middleware.is_empty() {
+                        return call_handler(
+                            f,
+                            request,
+                            domain,
+                            self.handler_timeout,
+                            exact.blocking_pool.as_deref(),
+                        );
+                    }
+                    // Route middleware wraps the handler, inside the server-wide middleware.
+                    let modified =
+                        self.apply_request_middleware(&exact.middleware, request.clone());
+                    let response = call_handler(
+                        f,
+                        modified,
+                        domain,
+                        self.handler_timeout,
+                        exact.blocking_pool.as_deref(),
+                    );
+                    return self.apply_response_middleware(&exact.middleware, request, response);
+                }
+            }
+            RouteType::Upgrade => {
+                if let Some(upgrade) = &exact.upgrade {
+                    // The handler writes its own response once the connection is handed over.
+                    self.pending_upgrade = Some((Arc::clone(upgrade), request));
+                    return HTTPResponse::new(StatusCode::SwitchingProtocols);
                 }
             }
             RouteType::Proxy => {
                 if let Some(external) = &exact.external {
-                    return get_proxy_route(&exact.route, external, &request);
+                    if let Some(breaker) = &exact.circuit_breaker
+                        && let Err(remaining) = breaker.try_acquire()
+                    {
+                        let mut response = HTTPResponse::new(StatusCode::ServiceUnavailable);
+                        response.set_retry_after_secs(remaining.as_secs_f64().ceil() as u64);
+                        return response;
+                    }
+                    let response = get_proxy_route(external, &request, self.proxy_pool.as_deref());
+                    if let Some(breaker) = &exact.circuit_breaker {
+                        breaker.record(response.status_code != StatusCode::BadGateway);
+                    }
+                    return response;
                 }
             }
             RouteType::Error => {
@@ -407,31 +1057,153 @@ impl Client {
     }
 }
 
-/// Helper: Handles proxy routes.
-fn get_proxy_route(prefix: &str, external: &String, request: &HTTPRequest) -> HTTPResponse {
-    let path = format!(
-        "{}/{}",
-        prefix.trim_end_matches('/'),
-        request.path.strip_prefix(prefix).unwrap_or("")
-    );
-    let joined = if external.ends_with('/') {
-        format!("{}{}", external.trim_end_matches('/'), path)
-    } else {
-        format!("{}{}", external, path)
+/// `true` if a read failed because the read timeout expired.
+fn is_timeout(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
+
+/// Helper: The methods registered for `path`.
+///
+/// These are the methods of the routes for exactly this path or, if there are
+/// none, of the longest matching route prefix, in registration order.
+fn allowed_methods(routes: &[Route], path: &str) -> Vec<HTTPMethod> {
+    let has_exact = routes.iter().any(|r| r.route == path);
+    let longest = routes
+        .iter()
+        .filter(|r| path.starts_with(&r.route))
+        .map(|r| r.route.len())
+        .max();
+    let mut methods: Vec<HTTPMethod> = Vec::new();
+    for route in routes {
+        let matches = match has_exact {
+            true => route.route == path,
+            false => path.starts_with(&route.route) && Some(route.route.len()) == longest,
+        };
+        if matches && !methods.contains(&route.method) {
+            methods.push(route.method.clone());
+        }
+    }
+    methods
+}
+
+/// Helper: Formats an `Allow` header value, which always includes `OPTIONS`.
+fn allow_header(methods: &[HTTPMethod]) -> String {
+    let mut names: Vec<String> = methods.iter().map(HTTPMethod::to_string).collect();
+    if !methods.contains(&HTTPMethod::OPTIONS) {
+        names.push(HTTPMethod::OPTIONS.to_string());
+    }
+    names.join(", ")
+}
+
+/// Most timed handler threads that may run at once, including ones whose
+/// request already timed out.
+const MAX_TIMED_HANDLERS: usize = 256;
+
+/// Number of timed handler threads currently running.
+static TIMED_HANDLERS: AtomicUsize = AtomicUsize::new(0);
+
+/// Helper: Runs a custom handler, answering `500` if it panics.
+///
+/// The panic message is kept on the response for the error reporter.
+///
+/// Handlers of blocking routes are queued on their `pool`, which caps how many
+/// run at once; the calling connection thread still blocks until the response
+/// arrives. Otherwise, with a timeout the handler runs on its own thread. Either way
+/// `504` is returned once the timeout passes; the handler is left to finish
+/// in the background. Once [`MAX_TIMED_HANDLERS`] threads are running, `503`
+/// is returned without starting the handler.
+fn call_handler(
+    f: &Handler,
+    request: HTTPRequest,
+    domain: Domain,
+    timeout: Option<Duration>,
+    pool: Option<&BlockingPool>,
+) -> HTTPResponse {
+    if pool.is_none() && timeout.is_none() {
+        return catch_unwind(AssertUnwindSafe(|| f(request, &domain)))
+            .unwrap_or_else(|payload| panic_response(payload.as_ref()));
+    }
+
+    let f = Arc::clone(f);
+    let path = request.path.clone();
+    let (sender, receiver) = mpsc::channel();
+    let job = move || {
+        let response = catch_unwind(AssertUnwindSafe(|| f(request, &domain)))
+            .unwrap_or_else(|payload| panic_response(payload.as_ref()));
+        let _ = sender.send(response);
     };
+    match pool {
+        Some(pool) => pool.execute(job),
+        None => {
+            if TIMED_HANDLERS.fetch_add(1, Ordering::SeqCst) >= MAX_TIMED_HANDLERS {
+                TIMED_HANDLERS.fetch_sub(1, Ordering::SeqCst);
+                warn!("Too many timed handlers still running, rejecting {path}");
+                return HTTPResponse::service_unavailable();
+            }
+            thread::spawn(move || {
+                job();
+                TIMED_HANDLERS.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+    }
+
+    let Some(timeout) = timeout else {
+        return receiver
+            .recv()
+            .unwrap_or_else(|_| HTTPResponse::internal_error());
+    };
+    match receiver.recv_timeout(timeout) {
+        Ok(response) => response,
+        Err(RecvTimeoutError::Timeout) => {
+            warn!("Handler for {path} did not finish within {timeout:?}");
+            HTTPResponse::gateway_timeout()
+        }
+        Err(RecvTimeoutError::Disconnected) => HTTPResponse::internal_error(),
+    }
+}
+
+/// Helper: Builds the `500` response for a handler that panicked with `payload`.
+fn panic_response(payload: &(dyn Any + Send)) -> HTTPResponse {
+    let message = payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| String::from("Box<dyn Any>"));
+    let mut response = HTTPResponse::internal_error();
+    response.panic_message = Some(message);
+    response
+}
+
+/// Helper: Handles proxy routes, reusing pooled upstream connections when a pool is given.
+///
+/// The request path, including its query string, is appended to the path of
+/// the `external` URL.
+fn get_proxy_route(
+    external: &str,
+    request: &HTTPRequest,
+    pool: Option<&ConnectionPool>,
+) -> HTTPResponse {
+    let joined = format!("{}{}", external.trim_end_matches('/'), request.path);
     let mut proxy = Proxy::new(joined);
 
     if proxy.parse_url().is_none() {
         return HTTPResponse::bad_gateway();
     }
 
-    let Some(mut stream) = Proxy::connect_to_server(&proxy.host, proxy.port) else {
-        return HTTPResponse::bad_gateway();
-    };
+    let response_data = if let Some(pool) = pool {
+        proxy.send_pooled_request(pool)
+    } else {
+        let Some(mut stream) = Proxy::connect_to_server(&proxy.host, proxy.port) else {
+            return HTTPResponse::bad_gateway();
+        };
 
-    let response_data = match proxy.scheme {
-        ProxySchema::HTTP => Proxy::send_http_request(&mut stream, &proxy.path, &proxy.host),
-        ProxySchema::HTTPS => Proxy::send_https_request(&mut stream, &proxy.path, &proxy.host),
+        match proxy.scheme {
+            ProxySchema::HTTP => Proxy::send_http_request(&mut stream, &proxy.path, &proxy.host),
+            ProxySchema::HTTPS => Proxy::send_https_request(&mut stream, &proxy.path, &proxy.host),
+        }
     };
 
     if let Some(raw_response) = response_data {
@@ -450,15 +1222,316 @@ fn get_proxy_route(prefix: &str, external: &String, request: &HTTPRequest) -> HT
 }
 
 /// Helper: Handles static file routes.
-fn get_static_file_response(folder: &String, request: &HTTPRequest) -> HTTPResponse {
-    let (content, content_type) = get_static_file_content(&request.path, folder);
-
-    if content.is_empty() {
+fn get_static_file_response(
+    folder: &str,
+    options: &StaticOptions,
+    request: &HTTPRequest,
+) -> HTTPResponse {
+    let file_path = match resolve_static_path(&request.decoded_path(), folder, options) {
+        Some(StaticTarget::File(file_path)) => file_path,
+        Some(StaticTarget::Directory(dir)) => {
+            let mut response = HTTPResponse::ok();
+            response.set_body_string(directory_listing(&dir, &request.path, options));
+            return response;
+        }
+        None => return HTTPResponse::not_found(),
+    };
+    let Some((file, len)) = open_static_file(&file_path) else {
         return HTTPResponse::not_found();
-    }
+    };
 
     let mut response = HTTPResponse::ok();
-    response.set_body_string(content.to_string());
-    response.message.headers.content_type = content_type;
+    response.message.headers.content_type = options.content_type_for(&file_path);
     response
+        .message
+        .headers
+        .add_header("Accept-Ranges", "bytes");
+    if let Some(cache_control) = &options.cache_control {
+        response
+            .message
+            .headers
+            .set_cache_control(&cache_control.as_str());
+    }
+
+    // Validators let clients resume downloads with `If-Range`.
+    let modified = file.metadata().and_then(|m| m.modified()).ok();
+    let validators = modified.map(|modified| {
+        let nanos = modified
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        (
+            format!("{len:x}-{nanos:x}"),
+            DateTime::<Utc>::from(modified),
+        )
+    });
+    if let Some((etag, last_modified)) = &validators {
+        response.message.headers.set_etag(etag);
+        response.message.headers.set_last_modified(*last_modified);
+    }
+
+    let conditional = request.conditional_headers();
+    let range = conditional
+        .range
+        .filter(|_| match (&conditional.if_range, &validators) {
+            (None, _) => true,
+            (Some(if_range), Some((etag, last_modified))) => if_range.matches(etag, *last_modified),
+            (Some(_), None) => false,
+        });
+    let result = match range {
+        Some(range) => match coalesce_ranges(range.resolve(len), len) {
+            Some(ranges) => apply_byte_ranges(&mut response, file, len, &ranges),
+            None => set_file_body(&mut response, file, 0, len),
+        },
+        None => set_file_body(&mut response, file, 0, len),
+    };
+    if let Err(e) = result {
+        error!("Failed to read static file {}: {e}", file_path.display());
+        return HTTPResponse::internal_error();
+    }
+    response
+}
+
+/// Helper: Sorts resolved byte ranges and merges the ones that overlap or touch.
+///
+/// Returns `None` when the ranges should be ignored in favour of the whole
+/// file: more than [`MAX_BYTE_RANGES`] were requested, or together they ask
+/// for more bytes than the file has, as repeated or overlapping ranges do.
+fn coalesce_ranges(mut ranges: Vec<(u64, u64)>, len: u64) -> Option<Vec<(u64, u64)>> {
+    if ranges.len() > MAX_BYTE_RANGES {
+        return None;
+    }
+    let requested = ranges.iter().fold(0u64, |total, (start, end)| {
+        total.saturating_add(end - start + 1)
+    });
+    if requested > len {
+        return None;
+    }
+
+    ranges.sort_unstable();
+    let mut merged: Vec<(u64, u64)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1.saturating_add(1) => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    Some(merged)
+}
+
+/// Helper: Turns a full static file response into a `206 Partial Content` (or
+/// `416 Range Not Satisfiable`) response for the requested byte ranges.
+///
+/// A single range is sent as-is with a `Content-Range` header, streamed like a
+/// whole file; multiple ranges are streamed as a `multipart/byteranges` body.
+fn apply_byte_ranges(
+    response: &mut HTTPResponse,
+    file: File,
+    len: u64,
+    ranges: &[(u64, u64)],
+) -> io::Result<()> {
+    match ranges {
+        [] => {
+            response.status_code = StatusCode::RangeNotSatisfiable;
+            response
+                .message
+                .headers
+                .add_header("Content-Range", &format!("bytes */{len}"));
+            response.set_body(Vec::new());
+        }
+        [(start, end)] => {
+            response.status_code = StatusCode::PartialContent;
+            response
+                .message
+                .headers
+                .add_header("Content-Range", &format!("bytes {start}-{end}/{len}"));
+            set_file_body(response, file, *start, end - start + 1)?;
+        }
+        _ => {
+            let nanos = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_nanos())
+                .unwrap_or_default();
+            let boundary = format!("sunweb-{nanos:x}");
+            let part_type = response.content_type().to_string();
+
+            let mut segments = VecDeque::new();
+            for (i, (start, end)) in ranges.iter().enumerate() {
+                let separator = if i == 0 { "" } else { "\r\n" };
+                segments.push_back(RangeSegment::Bytes(Cursor::new(
+                    format!(
+                        "{separator}--{boundary}\r\nContent-Type: {part_type}\r\nContent-Range: bytes {start}-{end}/{len}\r\n\r\n"
+                    )
+                    .into_bytes(),
+                )));
+                segments.push_back(RangeSegment::File {
+                    offset: *start,
+                    remaining: end - start + 1,
+                });
+            }
+            segments.push_back(RangeSegment::Bytes(Cursor::new(
+                format!("\r\n--{boundary}--\r\n").into_bytes(),
+            )));
+
+            let body = ByteRangesReader { file, segments };
+            let body_len = body.len();
+            response.status_code = StatusCode::PartialContent;
+            response.message.headers.content_type =
+                ContentType::Multipart(MultipartSubType::ByteRanges);
+            response.message.headers.content_type_boundary = Some(boundary);
+            response.set_body_reader(body, Some(body_len));
+        }
+    }
+    Ok(())
+}
+
+/// A piece of a `multipart/byteranges` body.
+enum RangeSegment {
+    /// Part headers or delimiters.
+    Bytes(Cursor<Vec<u8>>),
+    /// A slice of the file still to be sent.
+    File { offset: u64, remaining: u64 },
+}
+
+/// Streams a `multipart/byteranges` body, reading each range from the file
+/// only when it is sent.
+struct ByteRangesReader {
+    file: File,
+    segments: VecDeque<RangeSegment>,
+}
+
+impl ByteRangesReader {
+    /// The number of bytes left to read.
+    fn len(&self) -> u64 {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                RangeSegment::Bytes(bytes) => bytes.get_ref().len() as u64 - bytes.position(),
+                RangeSegment::File { remaining, .. } => *remaining,
+            })
+            .sum()
+    }
+}
+
+impl Read for ByteRangesReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while let Some(segment) = self.segments.front_mut() {
+            let n = match segment {
+                RangeSegment::Bytes(bytes) => bytes.read(buf)?,
+                RangeSegment::File { offset, remaining } => {
+                    let max = buf
+                        .len()
+                        .min(usize::try_from(*remaining).unwrap_or(usize::MAX));
+                    self.file.seek(SeekFrom::Start(*offset))?;
+                    let n = self.file.read(&mut buf[..max])?;
+                    if n == 0 && max > 0 {
+                        return Err(io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            "File shorter than the requested range",
+                        ));
+                    }
+                    *offset += n as u64;
+                    *remaining -= n as u64;
+                    n
+                }
+            };
+            if n > 0 || buf.is_empty() {
+                return Ok(n);
+            }
+            self.segments.pop_front();
+        }
+        Ok(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::webserver::WebServer;
+
+    /// A server whose domain map lost the default domain still answers `404`
+    /// instead of panicking in the error-page middleware.
+    #[test]
+    fn missing_default_domain_is_not_found() {
+        let error_page = Middleware::new_response_both_w_routes(None, None, WebServer::error_page);
+        let mut client = Client::new(
+            MemoryStream::new(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n", None),
+            Arc::new(Mutex::new(HashMap::new())),
+            Domain::new("localhost"),
+            Arc::new(vec![error_page]),
+            Arc::new(Metrics::default()),
+            None,
+            None,
+        );
+
+        client.handle(0);
+
+        let output = client.into_stream().output;
+        assert!(output.starts_with(b"HTTP/1.1 404 Not Found\r\n"));
+    }
+
+    /// Routes are matched against the path without its query string.
+    #[test]
+    fn query_string_is_ignored_when_matching_routes() {
+        let mut server = WebServer::new(crate::webserver::ServerConfig::new([127, 0, 0, 1], 8080));
+        server.add_custom_route(
+            "/search",
+            HTTPMethod::GET,
+            |request, _domain| {
+                let query = request.query_param("q").unwrap_or_default();
+                HTTPResponse::with_body(StatusCode::Ok, format!("results for {query}"))
+            },
+            StatusCode::Ok,
+            None,
+        );
+
+        let peer = "127.0.0.1:50000".parse().unwrap();
+        let response = server.handle_request(
+            b"GET /search?q=rust HTTP/1.1\r\nHost: localhost\r\n\r\n",
+            peer,
+        );
+        assert!(response.starts_with(b"HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with(b"\r\n\r\nresults for rust"));
+    }
+
+    /// Health routes skip the access logger but not other middleware.
+    #[test]
+    fn health_route_skips_access_log() {
+        let mut server = WebServer::new(crate::webserver::ServerConfig::new([127, 0, 0, 1], 8080));
+        server.add_health_route("/healthz");
+        let client = server.client(MemoryStream::new(b"", None));
+        let request = |path: &str| {
+            let raw = format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n");
+            HTTPRequest::parse(raw.as_bytes()).unwrap()
+        };
+
+        let logger = client.middleware.iter().find(|m| m.access_log).unwrap();
+        assert!(!client.middleware_applies(logger, &request("/healthz")));
+        assert!(client.middleware_applies(logger, &request("/other")));
+        let other = client.middleware.iter().find(|m| !m.access_log).unwrap();
+        assert!(client.middleware_applies(other, &request("/healthz")));
+    }
+
+    /// Once too many timed handlers are still running, new ones are not
+    /// started and the client receives `503`.
+    #[test]
+    fn timed_handlers_are_capped() {
+        let handler: Handler = Arc::new(|_request, _domain| HTTPResponse::ok());
+        let request = HTTPRequest::parse(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        let timeout = Some(Duration::from_secs(1));
+
+        TIMED_HANDLERS.fetch_add(MAX_TIMED_HANDLERS, Ordering::SeqCst);
+        let response = call_handler(
+            &handler,
+            request.clone(),
+            Domain::new("localhost"),
+            timeout,
+            None,
+        );
+        TIMED_HANDLERS.fetch_sub(MAX_TIMED_HANDLERS, Ordering::SeqCst);
+        assert_eq!(response.status_code, StatusCode::ServiceUnavailable);
+
+        let response = call_handler(&handler, request, Domain::new("localhost"), timeout, None);
+        assert_eq!(response.status_code, StatusCode::Ok);
+    }
 }
@@ -1,29 +1,320 @@
 use crate::webserver::http_packet::header::content_types::ContentType;
+use crate::webserver::http_packet::header::content_types::application::ApplicationSubType;
+use crate::webserver::http_packet::header::headers::cache_control::CacheControl;
+use crate::webserver::responses::HTTPResponse;
+use std::collections::HashMap;
 use std::str::FromStr;
 use std::{
     fs,
     fs::File,
-    io::{BufReader, Read},
-    path::Path,
+    io::{self, BufReader, Read, Seek, SeekFrom},
+    path::{Component, Path, PathBuf},
     sync::Arc,
 };
 
-/// Retrieves the content and MIME type of a static file based on a route and base folder.
+/// Options for serving a folder with
+/// [`WebServer::add_static_route_with_options`](crate::webserver::WebServer::add_static_route_with_options).
 ///
-/// This function maps a given route to a file path relative to a specified folder,
-/// reads the file's contents, and infers its MIME type from the file extension.
-/// If the file does not exist, it returns an empty string with `text/plain` as the MIME type.
+/// The defaults match [`add_static_route`](crate::webserver::WebServer::add_static_route):
+/// no index file, no directory listings, no `Cache-Control` header, and hidden
+/// files are served. Paths containing `..` are never served.
 ///
-/// # Arguments
+/// # Examples
 ///
-/// * `route` - The route path to the file, e.g., `/static/css/style.css`.
-/// * `folder` - The base folder where static files are located.
+/// ```rust
+/// use sunweb::webserver::StaticOptions;
+/// use sunweb::webserver::http_packet::header::headers::cache_control::CacheControl;
 ///
-/// # Returns
+/// let options = StaticOptions::new()
+///     .index_file("index.html")
+///     .directory_listing(true)
+///     .cache_control(CacheControl::MaxAge(3600))
+///     .deny_hidden_files();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct StaticOptions {
+    /// File served for requests addressing a directory, e.g. `index.html`.
+    pub(crate) index_file: Option<String>,
+    /// Whether directories without an index file are answered with a listing.
+    pub(crate) directory_listing: bool,
+    /// `Cache-Control` directive sent with every file.
+    pub(crate) cache_control: Option<CacheControl>,
+    /// Whether files and directories starting with `.` are hidden.
+    pub(crate) deny_hidden: bool,
+    /// `Content-Type` for files with an unknown extension; `None` means
+    /// `application/octet-stream`.
+    pub(crate) fallback_content_type: Option<ContentType>,
+    /// `Content-Type` of specific files, keyed by their path below the folder.
+    pub(crate) path_content_types: HashMap<PathBuf, ContentType>,
+    /// `Content-Type` of files by extension, taking precedence over the built-in mapping.
+    pub(crate) extension_content_types: HashMap<String, ContentType>,
+}
+
+impl StaticOptions {
+    /// Creates the default options.
+    pub fn new() -> StaticOptions {
+        Self::default()
+    }
+
+    /// Serves `name` from a directory when the request addresses the directory itself.
+    pub fn index_file(mut self, name: &str) -> Self {
+        self.index_file = Some(name.to_string());
+        self
+    }
+
+    /// Answers requests for directories without an index file with an HTML listing.
+    pub fn directory_listing(mut self, enabled: bool) -> Self {
+        self.directory_listing = enabled;
+        self
+    }
+
+    /// Sends `directive` as the `Cache-Control` header with every file.
+    pub fn cache_control(mut self, directive: CacheControl) -> Self {
+        self.cache_control = Some(directive);
+        self
+    }
+
+    /// Answers `404 Not Found` for files and directories whose name starts with `.`.
+    pub fn deny_hidden_files(mut self) -> Self {
+        self.deny_hidden = true;
+        self
+    }
+
+    /// Serves files whose extension is not recognised as `content_type`
+    /// instead of `application/octet-stream`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::fs;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, StaticOptions, WebServer};
+    /// use tempfile::tempdir;
+    ///
+    /// let dir = tempdir().unwrap();
+    /// fs::write(dir.path().join("firmware.bin"), [0x7f, 0xff, 0x00, 0x80]).unwrap();
+    /// fs::write(dir.path().join("NOTES"), "plain words").unwrap();
+    /// let folder = dir.path().to_str().unwrap();
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_static_route_with_options(
+    ///     "/files", HTTPMethod::GET, folder, StatusCode::Ok, StaticOptions::new(), None,
+    /// );
+    /// server.add_static_route_with_options(
+    ///     "/docs",
+    ///     HTTPMethod::GET,
+    ///     folder,
+    ///     StatusCode::Ok,
+    ///     StaticOptions::new().fallback_content_type("text/plain".parse().unwrap()),
+    ///     None,
+    /// );
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    ///
+    /// let response = server.handle_request(b"GET /files/firmware.bin HTTP/1.1\r\nHost: localhost\r\n\r\n", peer);
+    /// let head = String::from_utf8_lossy(&response);
+    /// assert!(head.starts_with("HTTP/1.1 200 OK"));
+    /// assert!(head.contains("Content-Type: application/octet-stream\r\n"));
+    /// assert!(response.ends_with(&[0x7f, 0xff, 0x00, 0x80]));
+    ///
+    /// let response = server.handle_request(b"GET /docs/NOTES HTTP/1.1\r\nHost: localhost\r\n\r\n", peer);
+    /// assert!(String::from_utf8_lossy(&response).contains("Content-Type: text/plain; charset=utf-8\r\n"));
+    /// ```
+    pub fn fallback_content_type(mut self, content_type: ContentType) -> Self {
+        self.fallback_content_type = Some(content_type);
+        self
+    }
+
+    /// Serves the file at `path` as `content_type`, whatever its extension.
+    ///
+    /// `path` is a file name or a path relative to the served folder, e.g.
+    /// `LICENSE` or `docs/LICENSE`; a bare file name applies in every
+    /// subdirectory. The longest matching path wins, and paths take
+    /// precedence over [`extension_content_type`](Self::extension_content_type).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::fs;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, StaticOptions, WebServer};
+    /// use tempfile::tempdir;
+    ///
+    /// let dir = tempdir().unwrap();
+    /// fs::write(dir.path().join("LICENSE"), "MIT License").unwrap();
+    /// fs::write(dir.path().join("app.wasm"), [0x00, 0x61, 0x73, 0x6d]).unwrap();
+    /// fs::write(dir.path().join("build.log"), "ok").unwrap();
+    ///
+    /// let options = StaticOptions::new()
+    ///     .path_content_type("LICENSE", "text/plain".parse().unwrap())
+    ///     .extension_content_type("wasm", "application/wasm".parse().unwrap())
+    ///     .extension_content_type("log", "text/plain".parse().unwrap());
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_static_route_with_options(
+    ///     "/files", HTTPMethod::GET, dir.path().to_str().unwrap(), StatusCode::Ok, options, None,
+    /// );
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let content_type = |path: &str| {
+    ///     let raw = format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n");
+    ///     let response = String::from_utf8_lossy(&server.handle_request(raw.as_bytes(), peer)).into_owned();
+    ///     response
+    ///         .lines()
+    ///         .find_map(|line| line.strip_prefix("Content-Type: "))
+    ///         .unwrap()
+    ///         .to_string()
+    /// };
+    ///
+    /// assert_eq!(content_type("/files/LICENSE"), "text/plain; charset=utf-8");
+    /// assert_eq!(content_type("/files/app.wasm"), "application/wasm");
+    /// assert_eq!(content_type("/files/build.log"), "text/plain; charset=utf-8");
+    /// ```
+    pub fn path_content_type(mut self, path: &str, content_type: ContentType) -> Self {
+        self.path_content_types
+            .insert(PathBuf::from(path.trim_start_matches('/')), content_type);
+        self
+    }
+
+    /// Serves files ending in `.{extension}` as `content_type`, replacing the
+    /// built-in mapping for that extension. `extension` is matched without
+    /// the leading dot and ignoring case.
+    pub fn extension_content_type(mut self, extension: &str, content_type: ContentType) -> Self {
+        self.extension_content_types.insert(
+            extension.trim_start_matches('.').to_ascii_lowercase(),
+            content_type,
+        );
+        self
+    }
+
+    /// The `Content-Type` served for a file: an override for its path or
+    /// extension, else guessed from its extension.
+    pub(crate) fn content_type_for(&self, file_path: &Path) -> ContentType {
+        let by_path = self
+            .path_content_types
+            .iter()
+            .filter(|(path, _)| file_path.ends_with(path))
+            .max_by_key(|(path, _)| path.components().count())
+            .map(|(_, content_type)| content_type);
+        let by_extension = file_path
+            .extension()
+            .and_then(|e| e.to_str())
+            .and_then(|e| self.extension_content_types.get(&e.to_ascii_lowercase()));
+        if let Some(content_type) = by_path.or(by_extension) {
+            return content_type.clone();
+        }
+
+        content_type_for(file_path).unwrap_or_else(|| {
+            self.fallback_content_type
+                .clone()
+                .unwrap_or(ContentType::Application(ApplicationSubType::OctetStream))
+        })
+    }
+}
+
+/// What a request to a static folder resolves to.
+pub(crate) enum StaticTarget {
+    /// A file to serve.
+    File(PathBuf),
+    /// A directory to list.
+    Directory(PathBuf),
+}
+
+/// Maps a request path below a static route to a file or directory in `folder`.
+///
+/// `route` is the percent-decoded request path. The first path segment (the
+/// route prefix) is stripped and the rest is joined onto `folder`. Returns
+/// `None` if the path tries to leave the folder, contains an encoded slash
+/// (`%2F`), which no file name can, is hidden by the options, or addresses a
+/// directory that has neither an index file nor a listing enabled.
+pub(crate) fn resolve_static_path(
+    route: &str,
+    folder: &str,
+    options: &StaticOptions,
+) -> Option<StaticTarget> {
+    if route.contains("%2F") {
+        return None;
+    }
+    let parts: Vec<&str> = route.trim_start_matches('/').splitn(2, '/').collect();
+    let relative_path = Path::new(if parts.len() > 1 { parts[1] } else { "" });
+
+    for component in relative_path.components() {
+        match component {
+            Component::Normal(name) => {
+                if options.deny_hidden && name.to_string_lossy().starts_with('.') {
+                    return None;
+                }
+            }
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+
+    let file_path = Path::new(folder).join(relative_path);
+    log::debug!("Resolved static path: {}", file_path.display());
+
+    if !file_path.is_dir() {
+        return Some(StaticTarget::File(file_path));
+    }
+    if let Some(index) = &options.index_file {
+        let index_path = file_path.join(index);
+        if index_path.is_file() {
+            return Some(StaticTarget::File(index_path));
+        }
+    }
+    options
+        .directory_listing
+        .then_some(StaticTarget::Directory(file_path))
+}
+
+/// Files larger than this are streamed from disk instead of read into memory.
+pub(crate) const STREAM_THRESHOLD: u64 = 1024 * 1024;
+
+/// Opens a static file for serving.
+///
+/// Returns the file and its length in bytes, or `None` if it cannot be opened
+/// or is empty.
 ///
-/// A tuple containing:
-/// * `Arc<String>` — the file's content.
-/// * `String` — the inferred MIME type of the file.
+/// # Arguments
+///
+/// * `file_path` - The file to open, usually resolved by [`resolve_static_path`].
+pub(crate) fn open_static_file(file_path: &Path) -> Option<(File, u64)> {
+    let opened = File::open(file_path).and_then(|file| {
+        let len = file.metadata()?.len();
+        Ok((file, len))
+    });
+    match opened {
+        Ok((file, len)) if len > 0 => Some((file, len)),
+        Ok(_) => None,
+        Err(e) => {
+            log::warn!("Static file not found: {} ({})", file_path.display(), e);
+            None
+        }
+    }
+}
+
+/// Sets `len` bytes of `file`, starting at `start`, as the response body.
+///
+/// Bodies above [`STREAM_THRESHOLD`] are streamed from the file with a
+/// `Content-Length` of `len`; smaller ones are read into memory.
+pub(crate) fn set_file_body(
+    response: &mut HTTPResponse,
+    mut file: File,
+    start: u64,
+    len: u64,
+) -> io::Result<()> {
+    file.seek(SeekFrom::Start(start))?;
+    if len > STREAM_THRESHOLD {
+        response.set_body_reader(file, Some(len));
+    } else {
+        let mut body = Vec::with_capacity(len as usize);
+        file.take(len).read_to_end(&mut body)?;
+        response.set_body(body);
+    }
+    Ok(())
+}
+
+/// The `Content-Type` for a file with a known extension.
 ///
 /// # MIME Type Mapping
 ///
@@ -33,60 +324,86 @@ use std::{
 /// | js        | application/javascript     |
 /// | html      | text/html                  |
 /// | json      | application/json           |
+/// | txt       | text/plain                 |
 /// | png       | image/png                  |
 /// | jpg/jpeg  | image/jpeg                 |
 /// | svg       | image/svg+xml              |
-/// | other     | text/plain                 |
-///
-/// # Examples
-///
-/// ```rust
-/// use std::fs;
-/// use std::sync::Arc;
-/// use tempfile::tempdir;
-/// use crate::webserver::files::get_static_file_content;
-///
-/// let dir = tempdir().unwrap();
-/// let folder = dir.path().to_str().unwrap().to_string();
-/// let file_path = format!("{}/style.css", folder);
-/// fs::write(&file_path, "body { color: red; }").unwrap();
-///
-/// let (content, mime_type) = get_static_file_content("/static/css/style.css", &folder);
-///
-/// assert_eq!(mime_type, "text/css");
-/// assert!(content.contains("color: red"));
-/// ```
-pub(crate) fn get_static_file_content(route: &str, folder: &String) -> (Arc<String>, ContentType) {
-    let parts: Vec<&str> = route.trim_start_matches('/').splitn(2, '/').collect();
-    let relative_path = if parts.len() > 1 { parts[1] } else { "" };
-    let file_path = Path::new(folder).join(relative_path);
-
-    log::debug!("Resolved static path: {}", file_path.display());
-
+pub(crate) fn content_type_for(file_path: &Path) -> Option<ContentType> {
     let content_type = match file_path.extension().and_then(|e| e.to_str()) {
         Some("css") => "text/css",
         Some("js") => "application/javascript",
         Some("html") => "text/html",
         Some("json") => "application/json",
+        Some("txt") => "text/plain",
         Some("png") => "image/png",
         Some("jpg") | Some("jpeg") => "image/jpeg",
         Some("svg") => "image/svg+xml",
-        _ => "text/plain",
+        _ => return None,
     };
+    Some(ContentType::from_str(content_type).expect("Could not parse ContentType!"))
+}
 
-    match fs::read_to_string(&file_path) {
-        Ok(content) => (
-            Arc::new(content),
-            ContentType::from_str(content_type).expect("Could not parse ContentType!"),
-        ),
-        Err(e) => {
-            log::warn!("Static file not found: {} ({})", file_path.display(), e);
-            (
-                Arc::new(String::new()),
-                ContentType::from_str("text/plain").expect("Could not parse ContentType!"),
-            )
+/// Lists the files below `folder`, recursing into subdirectories.
+///
+/// Paths are relative to `folder` and sorted.
+pub(crate) fn walk_files(folder: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![PathBuf::new()];
+    while let Some(relative) = pending.pop() {
+        for entry in fs::read_dir(folder.join(&relative))? {
+            let entry = entry?;
+            let path = relative.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                pending.push(path);
+            } else {
+                files.push(path);
+            }
         }
     }
+    files.sort();
+    Ok(files)
+}
+
+/// Renders an HTML listing of `dir`, linking each entry below `request_path`.
+///
+/// Directories are listed first with a trailing `/`; names are HTML-escaped.
+pub(crate) fn directory_listing(dir: &Path, request_path: &str, options: &StaticOptions) -> String {
+    let mut entries: Vec<(bool, String)> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| {
+                    let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+                    (!is_dir, entry.file_name().to_string_lossy().into_owned())
+                })
+                .filter(|(_, name)| !(options.deny_hidden && name.starts_with('.')))
+                .collect()
+        })
+        .unwrap_or_default();
+    entries.sort();
+
+    let base = escape_html(request_path.trim_end_matches('/'));
+    let title = format!("{base}/");
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html><head><title>Index of {title}</title></head><body>\n<h1>Index of {title}</h1>\n<ul>\n"
+    );
+    for (is_file, name) in entries {
+        let suffix = if is_file { "" } else { "/" };
+        let name = escape_html(&name);
+        html.push_str(&format!(
+            "<li><a href=\"{base}/{name}{suffix}\">{name}{suffix}</a></li>\n"
+        ));
+    }
+    html.push_str("</ul>\n</body></html>\n");
+    html
+}
+
+/// Escapes the characters that are significant in HTML text and attributes.
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
 }
 
 /// Reads the entire content of a file into an `Arc<String>`.
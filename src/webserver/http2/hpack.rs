@@ -0,0 +1,552 @@
+//! HPACK header compression (RFC 7541).
+//!
+//! The decoder supports the full format, including the dynamic table and
+//! Huffman-coded strings. The encoder only emits literals without indexing,
+//! which every peer must accept and which keeps no state between header blocks.
+
+use std::collections::VecDeque;
+
+/// Dynamic table size the decoder allows, the protocol default for
+/// `SETTINGS_HEADER_TABLE_SIZE`.
+const MAX_TABLE_SIZE: usize = 4096;
+
+/// Per-entry overhead counted towards the dynamic table size.
+const ENTRY_OVERHEAD: usize = 32;
+
+/// The static table, indexed from 1.
+const STATIC_TABLE: [(&str, &str); 61] = [
+    (":authority", ""),
+    (":method", "GET"),
+    (":method", "POST"),
+    (":path", "/"),
+    (":path", "/index.html"),
+    (":scheme", "http"),
+    (":scheme", "https"),
+    (":status", "200"),
+    (":status", "204"),
+    (":status", "206"),
+    (":status", "304"),
+    (":status", "400"),
+    (":status", "404"),
+    (":status", "500"),
+    ("accept-charset", ""),
+    ("accept-encoding", "gzip, deflate"),
+    ("accept-language", ""),
+    ("accept-ranges", ""),
+    ("accept", ""),
+    ("access-control-allow-origin", ""),
+    ("age", ""),
+    ("allow", ""),
+    ("authorization", ""),
+    ("cache-control", ""),
+    ("content-disposition", ""),
+    ("content-encoding", ""),
+    ("content-language", ""),
+    ("content-length", ""),
+    ("content-location", ""),
+    ("content-range", ""),
+    ("content-type", ""),
+    ("cookie", ""),
+    ("date", ""),
+    ("etag", ""),
+    ("expect", ""),
+    ("expires", ""),
+    ("from", ""),
+    ("host", ""),
+    ("if-match", ""),
+    ("if-modified-since", ""),
+    ("if-none-match", ""),
+    ("if-range", ""),
+    ("if-unmodified-since", ""),
+    ("last-modified", ""),
+    ("link", ""),
+    ("location", ""),
+    ("max-forwards", ""),
+    ("proxy-authenticate", ""),
+    ("proxy-authorization", ""),
+    ("range", ""),
+    ("referer", ""),
+    ("refresh", ""),
+    ("retry-after", ""),
+    ("server", ""),
+    ("set-cookie", ""),
+    ("strict-transport-security", ""),
+    ("transfer-encoding", ""),
+    ("user-agent", ""),
+    ("vary", ""),
+    ("via", ""),
+    ("www-authenticate", ""),
+];
+
+/// Huffman code and bit length of every byte value, followed by EOS (256).
+const HUFFMAN_CODES: [(u32, u8); 257] = [
+    (0x1ff8, 13),
+    (0x7fffd8, 23),
+    (0xfffffe2, 28),
+    (0xfffffe3, 28),
+    (0xfffffe4, 28),
+    (0xfffffe5, 28),
+    (0xfffffe6, 28),
+    (0xfffffe7, 28),
+    (0xfffffe8, 28),
+    (0xffffea, 24),
+    (0x3ffffffc, 30),
+    (0xfffffe9, 28),
+    (0xfffffea, 28),
+    (0x3ffffffd, 30),
+    (0xfffffeb, 28),
+    (0xfffffec, 28),
+    (0xfffffed, 28),
+    (0xfffffee, 28),
+    (0xfffffef, 28),
+    (0xffffff0, 28),
+    (0xffffff1, 28),
+    (0xffffff2, 28),
+    (0x3ffffffe, 30),
+    (0xffffff3, 28),
+    (0xffffff4, 28),
+    (0xffffff5, 28),
+    (0xffffff6, 28),
+    (0xffffff7, 28),
+    (0xffffff8, 28),
+    (0xffffff9, 28),
+    (0xffffffa, 28),
+    (0xffffffb, 28),
+    (0x14, 6),
+    (0x3f8, 10),
+    (0x3f9, 10),
+    (0xffa, 12),
+    (0x1ff9, 13),
+    (0x15, 6),
+    (0xf8, 8),
+    (0x7fa, 11),
+    (0x3fa, 10),
+    (0x3fb, 10),
+    (0xf9, 8),
+    (0x7fb, 11),
+    (0xfa, 8),
+    (0x16, 6),
+    (0x17, 6),
+    (0x18, 6),
+    (0x0, 5),
+    (0x1, 5),
+    (0x2, 5),
+    (0x19, 6),
+    (0x1a, 6),
+    (0x1b, 6),
+    (0x1c, 6),
+    (0x1d, 6),
+    (0x1e, 6),
+    (0x1f, 6),
+    (0x5c, 7),
+    (0xfb, 8),
+    (0x7ffc, 15),
+    (0x20, 6),
+    (0xffb, 12),
+    (0x3fc, 10),
+    (0x1ffa, 13),
+    (0x21, 6),
+    (0x5d, 7),
+    (0x5e, 7),
+    (0x5f, 7),
+    (0x60, 7),
+    (0x61, 7),
+    (0x62, 7),
+    (0x63, 7),
+    (0x64, 7),
+    (0x65, 7),
+    (0x66, 7),
+    (0x67, 7),
+    (0x68, 7),
+    (0x69, 7),
+    (0x6a, 7),
+    (0x6b, 7),
+    (0x6c, 7),
+    (0x6d, 7),
+    (0x6e, 7),
+    (0x6f, 7),
+    (0x70, 7),
+    (0x71, 7),
+    (0x72, 7),
+    (0xfc, 8),
+    (0x73, 7),
+    (0xfd, 8),
+    (0x1ffb, 13),
+    (0x7fff0, 19),
+    (0x1ffc, 13),
+    (0x3ffc, 14),
+    (0x22, 6),
+    (0x7ffd, 15),
+    (0x3, 5),
+    (0x23, 6),
+    (0x4, 5),
+    (0x24, 6),
+    (0x5, 5),
+    (0x25, 6),
+    (0x26, 6),
+    (0x27, 6),
+    (0x6, 5),
+    (0x74, 7),
+    (0x75, 7),
+    (0x28, 6),
+    (0x29, 6),
+    (0x2a, 6),
+    (0x7, 5),
+    (0x2b, 6),
+    (0x76, 7),
+    (0x2c, 6),
+    (0x8, 5),
+    (0x9, 5),
+    (0x2d, 6),
+    (0x77, 7),
+    (0x78, 7),
+    (0x79, 7),
+    (0x7a, 7),
+    (0x7b, 7),
+    (0x7ffe, 15),
+    (0x7fc, 11),
+    (0x3ffd, 14),
+    (0x1ffd, 13),
+    (0xffffffc, 28),
+    (0xfffe6, 20),
+    (0x3fffd2, 22),
+    (0xfffe7, 20),
+    (0xfffe8, 20),
+    (0x3fffd3, 22),
+    (0x3fffd4, 22),
+    (0x3fffd5, 22),
+    (0x7fffd9, 23),
+    (0x3fffd6, 22),
+    (0x7fffda, 23),
+    (0x7fffdb, 23),
+    (0x7fffdc, 23),
+    (0x7fffdd, 23),
+    (0x7fffde, 23),
+    (0xffffeb, 24),
+    (0x7fffdf, 23),
+    (0xffffec, 24),
+    (0xffffed, 24),
+    (0x3fffd7, 22),
+    (0x7fffe0, 23),
+    (0xffffee, 24),
+    (0x7fffe1, 23),
+    (0x7fffe2, 23),
+    (0x7fffe3, 23),
+    (0x7fffe4, 23),
+    (0x1fffdc, 21),
+    (0x3fffd8, 22),
+    (0x7fffe5, 23),
+    (0x3fffd9, 22),
+    (0x7fffe6, 23),
+    (0x7fffe7, 23),
+    (0xffffef, 24),
+    (0x3fffda, 22),
+    (0x1fffdd, 21),
+    (0xfffe9, 20),
+    (0x3fffdb, 22),
+    (0x3fffdc, 22),
+    (0x7fffe8, 23),
+    (0x7fffe9, 23),
+    (0x1fffde, 21),
+    (0x7fffea, 23),
+    (0x3fffdd, 22),
+    (0x3fffde, 22),
+    (0xfffff0, 24),
+    (0x1fffdf, 21),
+    (0x3fffdf, 22),
+    (0x7fffeb, 23),
+    (0x7fffec, 23),
+    (0x1fffe0, 21),
+    (0x1fffe1, 21),
+    (0x3fffe0, 22),
+    (0x1fffe2, 21),
+    (0x7fffed, 23),
+    (0x3fffe1, 22),
+    (0x7fffee, 23),
+    (0x7fffef, 23),
+    (0xfffea, 20),
+    (0x3fffe2, 22),
+    (0x3fffe3, 22),
+    (0x3fffe4, 22),
+    (0x7ffff0, 23),
+    (0x3fffe5, 22),
+    (0x3fffe6, 22),
+    (0x7ffff1, 23),
+    (0x3ffffe0, 26),
+    (0x3ffffe1, 26),
+    (0xfffeb, 20),
+    (0x7fff1, 19),
+    (0x3fffe7, 22),
+    (0x7ffff2, 23),
+    (0x3fffe8, 22),
+    (0x1ffffec, 25),
+    (0x3ffffe2, 26),
+    (0x3ffffe3, 26),
+    (0x3ffffe4, 26),
+    (0x7ffffde, 27),
+    (0x7ffffdf, 27),
+    (0x3ffffe5, 26),
+    (0xfffff1, 24),
+    (0x1ffffed, 25),
+    (0x7fff2, 19),
+    (0x1fffe3, 21),
+    (0x3ffffe6, 26),
+    (0x7ffffe0, 27),
+    (0x7ffffe1, 27),
+    (0x3ffffe7, 26),
+    (0x7ffffe2, 27),
+    (0xfffff2, 24),
+    (0x1fffe4, 21),
+    (0x1fffe5, 21),
+    (0x3ffffe8, 26),
+    (0x3ffffe9, 26),
+    (0xffffffd, 28),
+    (0x7ffffe3, 27),
+    (0x7ffffe4, 27),
+    (0x7ffffe5, 27),
+    (0xfffec, 20),
+    (0xfffff3, 24),
+    (0xfffed, 20),
+    (0x1fffe6, 21),
+    (0x3fffe9, 22),
+    (0x1fffe7, 21),
+    (0x1fffe8, 21),
+    (0x7ffff3, 23),
+    (0x3fffea, 22),
+    (0x3fffeb, 22),
+    (0x1ffffee, 25),
+    (0x1ffffef, 25),
+    (0xfffff4, 24),
+    (0xfffff5, 24),
+    (0x3ffffea, 26),
+    (0x7ffff4, 23),
+    (0x3ffffeb, 26),
+    (0x7ffffe6, 27),
+    (0x3ffffec, 26),
+    (0x3ffffed, 26),
+    (0x7ffffe7, 27),
+    (0x7ffffe8, 27),
+    (0x7ffffe9, 27),
+    (0x7ffffea, 27),
+    (0x7ffffeb, 27),
+    (0xffffffe, 28),
+    (0x7ffffec, 27),
+    (0x7ffffed, 27),
+    (0x7ffffee, 27),
+    (0x7ffffef, 27),
+    (0x7fffff0, 27),
+    (0x3ffffee, 26),
+    (0x3fffffff, 30),
+];
+
+/// Decodes header blocks, keeping the dynamic table between blocks of a connection.
+pub(crate) struct Decoder {
+    /// Dynamic table entries, newest first.
+    table: VecDeque<(String, String)>,
+    /// Current size of the dynamic table as defined by RFC 7541 §4.1.
+    size: usize,
+    /// Size limit set by the last dynamic table size update.
+    max_size: usize,
+}
+
+impl Decoder {
+    /// Creates a decoder with an empty dynamic table.
+    pub(crate) fn new() -> Self {
+        Self {
+            table: VecDeque::new(),
+            size: 0,
+            max_size: MAX_TABLE_SIZE,
+        }
+    }
+
+    /// Decodes a complete header block into `(name, value)` pairs.
+    pub(crate) fn decode(&mut self, block: &[u8]) -> Result<Vec<(String, String)>, String> {
+        let mut headers = Vec::new();
+        let mut pos = 0;
+
+        while let Some(&byte) = block.get(pos) {
+            if byte & 0x80 != 0 {
+                let index = decode_int(block, &mut pos, 7)?;
+                headers.push(self.entry(index)?);
+            } else if byte & 0xC0 == 0x40 {
+                let header = self.decode_literal(block, &mut pos, 6)?;
+                self.insert(header.clone());
+                headers.push(header);
+            } else if byte & 0xE0 == 0x20 {
+                let max_size = decode_int(block, &mut pos, 5)?;
+                if max_size > MAX_TABLE_SIZE {
+                    return Err("Dynamic table size update exceeds the limit".to_string());
+                }
+                self.max_size = max_size;
+                self.evict(0);
+            } else {
+                headers.push(self.decode_literal(block, &mut pos, 4)?);
+            }
+        }
+
+        Ok(headers)
+    }
+
+    /// Decodes a literal header field whose name index uses `prefix` bits.
+    fn decode_literal(
+        &self,
+        block: &[u8],
+        pos: &mut usize,
+        prefix: u8,
+    ) -> Result<(String, String), String> {
+        let name = match decode_int(block, pos, prefix)? {
+            0 => decode_string(block, pos)?,
+            index => self.entry(index)?.0,
+        };
+        let value = decode_string(block, pos)?;
+        Ok((name, value))
+    }
+
+    /// Looks up an entry of the static or dynamic table.
+    fn entry(&self, index: usize) -> Result<(String, String), String> {
+        match index {
+            0 => Err("Header table index 0".to_string()),
+            1..=61 => {
+                let (name, value) = STATIC_TABLE[index - 1];
+                Ok((name.to_string(), value.to_string()))
+            }
+            _ => self
+                .table
+                .get(index - 62)
+                .cloned()
+                .ok_or_else(|| format!("Header table index {index} out of range")),
+        }
+    }
+
+    /// Adds an entry to the dynamic table, evicting old entries as needed.
+    fn insert(&mut self, header: (String, String)) {
+        let size = header.0.len() + header.1.len() + ENTRY_OVERHEAD;
+        if size > self.max_size {
+            self.table.clear();
+            self.size = 0;
+            return;
+        }
+        self.evict(size);
+        self.size += size;
+        self.table.push_front(header);
+    }
+
+    /// Evicts the oldest entries until `additional` more bytes fit.
+    fn evict(&mut self, additional: usize) {
+        while self.size + additional > self.max_size {
+            let Some((name, value)) = self.table.pop_back() else {
+                break;
+            };
+            self.size -= name.len() + value.len() + ENTRY_OVERHEAD;
+        }
+    }
+}
+
+/// Encodes headers as literals without indexing and without Huffman coding.
+pub(crate) fn encode(headers: &[(String, String)]) -> Vec<u8> {
+    let mut block = Vec::new();
+    for (name, value) in headers {
+        block.push(0x00);
+        encode_string(name, &mut block);
+        encode_string(value, &mut block);
+    }
+    block
+}
+
+/// Decodes an integer with a `prefix`-bit prefix (RFC 7541 §5.1).
+fn decode_int(block: &[u8], pos: &mut usize, prefix: u8) -> Result<usize, String> {
+    let truncated = || "Truncated header block".to_string();
+    let mask = (1usize << prefix) - 1;
+    let mut value = usize::from(*block.get(*pos).ok_or_else(truncated)?) & mask;
+    *pos += 1;
+    if value < mask {
+        return Ok(value);
+    }
+
+    let mut shift = 0;
+    loop {
+        let byte = *block.get(*pos).ok_or_else(truncated)?;
+        *pos += 1;
+        if shift > 28 {
+            return Err("Header integer overflow".to_string());
+        }
+        value += usize::from(byte & 0x7F) << shift;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+}
+
+/// Decodes a string literal, Huffman-coded or raw (RFC 7541 §5.2).
+fn decode_string(block: &[u8], pos: &mut usize) -> Result<String, String> {
+    let huffman = block.get(*pos).is_some_and(|byte| byte & 0x80 != 0);
+    let len = decode_int(block, pos, 7)?;
+    let bytes = block
+        .get(*pos..*pos + len)
+        .ok_or_else(|| "Truncated header block".to_string())?;
+    *pos += len;
+
+    let bytes = if huffman {
+        huffman_decode(bytes)?
+    } else {
+        bytes.to_vec()
+    };
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Decodes a Huffman-coded string.
+fn huffman_decode(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let mut decoded = Vec::with_capacity(bytes.len() * 2);
+    let mut code = 0u32;
+    let mut len = 0u8;
+
+    for byte in bytes {
+        for shift in (0..8).rev() {
+            code = (code << 1) | u32::from((byte >> shift) & 1);
+            len += 1;
+            if let Some(symbol) = HUFFMAN_CODES
+                .iter()
+                .position(|&(c, l)| l == len && c == code)
+            {
+                if symbol == 256 {
+                    return Err("EOS in Huffman-coded string".to_string());
+                }
+                decoded.push(symbol as u8);
+                code = 0;
+                len = 0;
+            } else if len >= 30 {
+                return Err("Invalid Huffman code".to_string());
+            }
+        }
+    }
+
+    // Padding must be shorter than a byte and consist of the EOS prefix (all ones).
+    if len > 7 || code != (1 << len) - 1 {
+        return Err("Invalid Huffman padding".to_string());
+    }
+    Ok(decoded)
+}
+
+/// Encodes an integer with a `prefix`-bit prefix, or-ing `flags` into the first byte.
+fn encode_int(value: usize, prefix: u8, flags: u8, block: &mut Vec<u8>) {
+    let mask = (1usize << prefix) - 1;
+    if value < mask {
+        block.push(flags | value as u8);
+        return;
+    }
+
+    block.push(flags | mask as u8);
+    let mut rest = value - mask;
+    while rest >= 0x80 {
+        block.push((rest & 0x7F) as u8 | 0x80);
+        rest >>= 7;
+    }
+    block.push(rest as u8);
+}
+
+/// Encodes a raw (not Huffman-coded) string literal.
+fn encode_string(value: &str, block: &mut Vec<u8>) {
+    encode_int(value.len(), 7, 0x00, block);
+    block.extend_from_slice(value.as_bytes());
+}
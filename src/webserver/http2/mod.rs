@@ -0,0 +1,622 @@
+//! HTTP/2 Module
+//!
+//! A minimal HTTP/2 (RFC 9113) implementation used for TLS connections that
+//! negotiate `h2` via ALPN. Requests are served one at a time: frames are read
+//! until a stream carries a complete request, which is handed to the regular
+//! routing as an [`HTTPRequest`](crate::webserver::requests::HTTPRequest), and
+//! the response is written back on the same stream.
+//!
+//! # Supported
+//! - Connection preface and SETTINGS exchange
+//! - HEADERS (with padding, priority and CONTINUATION) and DATA frames
+//! - HPACK, including the dynamic table and Huffman-coded strings
+//! - Flow control for request and response bodies, PING, RST_STREAM and GOAWAY
+//! - Limits on header blocks, request bodies and concurrent streams
+//!
+//! Server push and stream prioritisation are not supported.
+
+mod hpack;
+
+use crate::webserver::client_handling::STREAM_CHUNK_SIZE;
+use crate::webserver::responses::HTTPResponse;
+use crate::webserver::responses::status_code::StatusCode;
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Read, Write};
+
+/// The connection preface every HTTP/2 client sends first.
+const PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+const DATA: u8 = 0x0;
+const HEADERS: u8 = 0x1;
+const RST_STREAM: u8 = 0x3;
+const SETTINGS: u8 = 0x4;
+const PING: u8 = 0x6;
+const GOAWAY: u8 = 0x7;
+const WINDOW_UPDATE: u8 = 0x8;
+const CONTINUATION: u8 = 0x9;
+
+const FLAG_END_STREAM: u8 = 0x1;
+const FLAG_ACK: u8 = 0x1;
+const FLAG_END_HEADERS: u8 = 0x4;
+const FLAG_PADDED: u8 = 0x8;
+const FLAG_PRIORITY: u8 = 0x20;
+
+const SETTINGS_MAX_CONCURRENT_STREAMS: u16 = 0x3;
+const SETTINGS_INITIAL_WINDOW_SIZE: u16 = 0x4;
+const SETTINGS_MAX_FRAME_SIZE: u16 = 0x5;
+
+const NO_ERROR: u32 = 0x0;
+const PROTOCOL_ERROR: u32 = 0x1;
+const REFUSED_STREAM: u32 = 0x7;
+const ENHANCE_YOUR_CALM: u32 = 0xb;
+
+/// Flow-control window every connection and stream starts with.
+const DEFAULT_WINDOW_SIZE: i64 = 65_535;
+/// Largest frame payload either side may send until told otherwise.
+const DEFAULT_MAX_FRAME_SIZE: usize = 16_384;
+/// Largest frame payload a peer may announce with SETTINGS_MAX_FRAME_SIZE.
+const MAX_ALLOWED_FRAME_SIZE: usize = 16_777_215;
+/// Streams a client may have open at once, announced in the server's SETTINGS.
+const MAX_CONCURRENT_STREAMS: u32 = 100;
+
+/// Headers that only apply to a single HTTP/1.1 connection and must not be sent over HTTP/2.
+const CONNECTION_HEADERS: [&str; 5] = [
+    "connection",
+    "keep-alive",
+    "proxy-connection",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// A single HTTP/2 frame.
+struct Frame {
+    kind: u8,
+    flags: u8,
+    stream_id: u32,
+    payload: Vec<u8>,
+}
+
+/// A stream whose request has not been fully received yet.
+struct PendingStream {
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    /// Bytes the client may still send on the stream before it needs a WINDOW_UPDATE.
+    window: i64,
+}
+
+/// The state of one HTTP/2 connection.
+pub(crate) struct Connection {
+    /// HPACK decoder shared by all header blocks of the connection.
+    decoder: hpack::Decoder,
+    /// Streams still receiving their request.
+    streams: HashMap<u32, PendingStream>,
+    /// Complete requests waiting to be served, as HTTP/1.1-style bytes.
+    ready: VecDeque<(u32, Vec<u8>)>,
+    /// A header block waiting for CONTINUATION frames: stream, HEADERS flags and fragment.
+    continuation: Option<(u32, u8, Vec<u8>)>,
+    /// Highest stream identifier opened by the client.
+    last_stream_id: u32,
+    /// Connection-level send window.
+    send_window: i64,
+    /// Send windows of streams that have not been answered yet.
+    stream_windows: HashMap<u32, i64>,
+    /// Initial stream window announced by the client.
+    initial_window: i64,
+    /// Largest frame payload the client accepts.
+    max_frame_size: usize,
+    /// Largest header block accepted, in bytes.
+    max_header_size: usize,
+    /// Largest request body buffered for one stream, in bytes.
+    max_body_size: usize,
+}
+
+impl Connection {
+    /// Creates the state for a freshly negotiated connection.
+    ///
+    /// Header blocks larger than `max_header_size` close the connection, and
+    /// streams whose body grows past `max_body_size` are answered with
+    /// `413 Content Too Large`.
+    pub(crate) fn new(max_header_size: usize, max_body_size: usize) -> Self {
+        Self {
+            decoder: hpack::Decoder::new(),
+            streams: HashMap::new(),
+            ready: VecDeque::new(),
+            continuation: None,
+            last_stream_id: 0,
+            send_window: DEFAULT_WINDOW_SIZE,
+            stream_windows: HashMap::new(),
+            initial_window: DEFAULT_WINDOW_SIZE,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            max_header_size,
+            max_body_size,
+        }
+    }
+
+    /// Reads the client preface and sends the server's SETTINGS frame.
+    pub(crate) fn start<T: Read + Write>(&mut self, io: &mut T) -> io::Result<()> {
+        let mut preface = [0u8; PREFACE.len()];
+        io.read_exact(&mut preface)?;
+        if preface != PREFACE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Invalid HTTP/2 connection preface",
+            ));
+        }
+
+        let mut settings = SETTINGS_MAX_CONCURRENT_STREAMS.to_be_bytes().to_vec();
+        settings.extend_from_slice(&MAX_CONCURRENT_STREAMS.to_be_bytes());
+        write_frame(io, SETTINGS, 0, 0, &settings)?;
+        io.flush()
+    }
+
+    /// Reads frames until a request is complete.
+    ///
+    /// Returns the stream identifier and the request converted to HTTP/1.1-style
+    /// bytes, or `None` once the client sent GOAWAY.
+    pub(crate) fn read_request<T: Read + Write>(
+        &mut self,
+        io: &mut T,
+    ) -> io::Result<Option<(u32, Vec<u8>)>> {
+        loop {
+            if let Some(request) = self.ready.pop_front() {
+                return Ok(Some(request));
+            }
+            let frame = read_frame(io)?;
+            if !self.handle_frame(io, frame)? {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Sends a response on `stream_id` as a HEADERS frame followed by DATA frames.
+    ///
+    /// Waits for WINDOW_UPDATE frames when the body exceeds the flow-control
+    /// window. A streamed body is read and sent one frame at a time. Stops
+    /// early if the client resets the stream.
+    pub(crate) fn send_response<T: Read + Write>(
+        &mut self,
+        io: &mut T,
+        stream_id: u32,
+        response: &HTTPResponse,
+    ) -> io::Result<()> {
+        let (body, body_reader) = if response.forbids_body() {
+            (&[][..], None)
+        } else {
+            (
+                response.body().unwrap_or_default(),
+                response.body_reader.as_ref(),
+            )
+        };
+        let block = hpack::encode(&response_headers(response));
+
+        let mut fragments = block.chunks(self.max_frame_size).peekable();
+        let mut kind = HEADERS;
+        while let Some(fragment) = fragments.next() {
+            let mut flags = 0;
+            if kind == HEADERS && body.is_empty() && body_reader.is_none() {
+                flags |= FLAG_END_STREAM;
+            }
+            if fragments.peek().is_none() {
+                flags |= FLAG_END_HEADERS;
+            }
+            write_frame(io, kind, flags, stream_id, fragment)?;
+            kind = CONTINUATION;
+        }
+
+        if let Some(body_reader) = body_reader {
+            let Some(mut reader) = body_reader.take() else {
+                return Err(io::Error::other("Response body was already sent"));
+            };
+            let mut buffer = vec![0; STREAM_CHUNK_SIZE.min(self.max_frame_size)];
+            loop {
+                let n = match reader.read(&mut buffer) {
+                    Ok(n) => n,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(e),
+                };
+                if !self.send_data(io, stream_id, &buffer[..n], n == 0)? || n == 0 {
+                    break;
+                }
+            }
+        } else if !body.is_empty() {
+            self.send_data(io, stream_id, body, true)?;
+        }
+
+        self.stream_windows.remove(&stream_id);
+        io.flush()
+    }
+
+    /// Sends `data` on `stream_id` as DATA frames within the flow-control windows.
+    ///
+    /// Returns `false` if the stream was reset or the client went away before
+    /// everything was sent.
+    fn send_data<T: Read + Write>(
+        &mut self,
+        io: &mut T,
+        stream_id: u32,
+        data: &[u8],
+        end_stream: bool,
+    ) -> io::Result<bool> {
+        if data.is_empty() && end_stream {
+            write_frame(io, DATA, FLAG_END_STREAM, stream_id, &[])?;
+            return Ok(true);
+        }
+
+        let mut offset = 0;
+        while offset < data.len() {
+            let Some(&stream_window) = self.stream_windows.get(&stream_id) else {
+                return Ok(false);
+            };
+            let window = self.send_window.min(stream_window);
+            if window <= 0 {
+                io.flush()?;
+                let frame = read_frame(io)?;
+                if !self.handle_frame(io, frame)? {
+                    return Ok(false);
+                }
+                continue;
+            }
+
+            let len = (data.len() - offset)
+                .min(self.max_frame_size)
+                .min(window as usize);
+            let end = offset + len;
+            let flags = if end_stream && end == data.len() {
+                FLAG_END_STREAM
+            } else {
+                0
+            };
+            write_frame(io, DATA, flags, stream_id, &data[offset..end])?;
+            self.send_window -= len as i64;
+            if let Some(window) = self.stream_windows.get_mut(&stream_id) {
+                *window -= len as i64;
+            }
+            offset = end;
+        }
+        Ok(true)
+    }
+
+    /// Processes a single frame from the client.
+    ///
+    /// Returns `false` once the client sent GOAWAY.
+    fn handle_frame<T: Write>(&mut self, io: &mut T, frame: Frame) -> io::Result<bool> {
+        if self.continuation.is_some() && frame.kind != CONTINUATION {
+            return Err(protocol_error("Expected CONTINUATION frame"));
+        }
+
+        match frame.kind {
+            SETTINGS if frame.flags & FLAG_ACK == 0 => {
+                for setting in frame.payload.chunks_exact(6) {
+                    let id = u16::from_be_bytes([setting[0], setting[1]]);
+                    let value =
+                        u32::from_be_bytes([setting[2], setting[3], setting[4], setting[5]]);
+                    match id {
+                        SETTINGS_INITIAL_WINDOW_SIZE => {
+                            let delta = i64::from(value) - self.initial_window;
+                            self.initial_window = i64::from(value);
+                            for window in self.stream_windows.values_mut() {
+                                *window += delta;
+                            }
+                        }
+                        SETTINGS_MAX_FRAME_SIZE => {
+                            let size = value as usize;
+                            if !(DEFAULT_MAX_FRAME_SIZE..=MAX_ALLOWED_FRAME_SIZE).contains(&size) {
+                                return Err(self.go_away(
+                                    io,
+                                    PROTOCOL_ERROR,
+                                    "Invalid SETTINGS_MAX_FRAME_SIZE",
+                                ));
+                            }
+                            self.max_frame_size = size;
+                        }
+                        _ => {}
+                    }
+                }
+                write_frame(io, SETTINGS, FLAG_ACK, 0, &[])?;
+                io.flush()?;
+            }
+            PING if frame.flags & FLAG_ACK == 0 => {
+                write_frame(io, PING, FLAG_ACK, 0, &frame.payload)?;
+                io.flush()?;
+            }
+            WINDOW_UPDATE => {
+                let Some(bytes) = frame.payload.get(..4) else {
+                    return Err(protocol_error("Invalid WINDOW_UPDATE frame"));
+                };
+                let increment = i64::from(
+                    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) & 0x7FFF_FFFF,
+                );
+                if frame.stream_id == 0 {
+                    self.send_window += increment;
+                } else if let Some(window) = self.stream_windows.get_mut(&frame.stream_id) {
+                    *window += increment;
+                }
+            }
+            GOAWAY => return Ok(false),
+            RST_STREAM => {
+                self.streams.remove(&frame.stream_id);
+                self.stream_windows.remove(&frame.stream_id);
+            }
+            HEADERS => {
+                let fragment = frame_data(&frame)?.to_vec();
+                if fragment.len() > self.max_header_size {
+                    return Err(self.header_block_too_large(io));
+                }
+                if frame.flags & FLAG_END_HEADERS == 0 {
+                    self.continuation = Some((frame.stream_id, frame.flags, fragment));
+                } else {
+                    self.headers_complete(io, frame.stream_id, frame.flags, &fragment)?;
+                }
+            }
+            CONTINUATION => {
+                let Some((stream_id, flags, mut block)) = self.continuation.take() else {
+                    return Err(protocol_error("Unexpected CONTINUATION frame"));
+                };
+                if frame.stream_id != stream_id {
+                    return Err(protocol_error("CONTINUATION frame on the wrong stream"));
+                }
+                if block.len() + frame.payload.len() > self.max_header_size {
+                    return Err(self.header_block_too_large(io));
+                }
+                block.extend_from_slice(&frame.payload);
+                if frame.flags & FLAG_END_HEADERS == 0 {
+                    self.continuation = Some((stream_id, flags, block));
+                } else {
+                    self.headers_complete(io, stream_id, flags, &block)?;
+                }
+            }
+            DATA => {
+                let end_stream = frame.flags & FLAG_END_STREAM != 0;
+                let data = frame_data(&frame)?;
+                if !frame.payload.is_empty() {
+                    // The bytes are buffered or dropped below, so the connection
+                    // window is returned; each stream is bounded by its own window.
+                    let increment = (frame.payload.len() as u32).to_be_bytes();
+                    write_frame(io, WINDOW_UPDATE, 0, 0, &increment)?;
+                }
+
+                if let Some(stream) = self.streams.get_mut(&frame.stream_id) {
+                    stream.window -= frame.payload.len() as i64;
+                    if stream.body.len() + data.len() > self.max_body_size {
+                        self.reject_body(io, frame.stream_id)?;
+                    } else {
+                        stream.body.extend_from_slice(data);
+                        if end_stream {
+                            self.finish_stream(frame.stream_id);
+                        } else {
+                            // Only reopen the window as far as the body limit still allows.
+                            let remaining = i64::try_from(self.max_body_size - stream.body.len())
+                                .unwrap_or(i64::MAX);
+                            let increment = (DEFAULT_WINDOW_SIZE - stream.window)
+                                .min(remaining - stream.window);
+                            if increment > 0 {
+                                stream.window += increment;
+                                let increment = (increment as u32).to_be_bytes();
+                                write_frame(io, WINDOW_UPDATE, 0, frame.stream_id, &increment)?;
+                            }
+                        }
+                    }
+                }
+                io.flush()?;
+            }
+            _ => {}
+        }
+
+        Ok(true)
+    }
+
+    /// Handles a complete header block, opening a new stream or ending one with trailers.
+    ///
+    /// A new stream beyond [`MAX_CONCURRENT_STREAMS`] is refused with RST_STREAM.
+    fn headers_complete<T: Write>(
+        &mut self,
+        io: &mut T,
+        stream_id: u32,
+        flags: u8,
+        block: &[u8],
+    ) -> io::Result<()> {
+        // Every block must be decoded to keep the dynamic table in sync.
+        let headers = self.decoder.decode(block).map_err(protocol_error)?;
+
+        if !self.streams.contains_key(&stream_id) {
+            if stream_id <= self.last_stream_id || stream_id.is_multiple_of(2) {
+                return Err(protocol_error("Invalid stream identifier"));
+            }
+            self.last_stream_id = stream_id;
+            // Streams stay open until their response is sent or they are reset.
+            if self.stream_windows.len() >= MAX_CONCURRENT_STREAMS as usize {
+                write_frame(io, RST_STREAM, 0, stream_id, &REFUSED_STREAM.to_be_bytes())?;
+                return io.flush();
+            }
+            self.streams.insert(
+                stream_id,
+                PendingStream {
+                    headers,
+                    body: Vec::new(),
+                    window: DEFAULT_WINDOW_SIZE,
+                },
+            );
+            self.stream_windows.insert(stream_id, self.initial_window);
+        }
+
+        if flags & FLAG_END_STREAM != 0 {
+            self.finish_stream(stream_id);
+        }
+        Ok(())
+    }
+
+    /// Answers a stream whose body exceeds the limit with `413 Content Too Large`.
+    ///
+    /// The stream is then reset so the client stops sending the rest of the body.
+    fn reject_body<T: Write>(&mut self, io: &mut T, stream_id: u32) -> io::Result<()> {
+        self.streams.remove(&stream_id);
+        self.stream_windows.remove(&stream_id);
+
+        let status = StatusCode::ContentTooLarge.as_u16().to_string();
+        let block = hpack::encode(&[(":status".to_string(), status)]);
+        write_frame(
+            io,
+            HEADERS,
+            FLAG_END_STREAM | FLAG_END_HEADERS,
+            stream_id,
+            &block,
+        )?;
+        write_frame(io, RST_STREAM, 0, stream_id, &NO_ERROR.to_be_bytes())
+    }
+
+    /// Closes the connection because a header block exceeds the limit.
+    fn header_block_too_large<T: Write>(&self, io: &mut T) -> io::Error {
+        self.go_away(
+            io,
+            ENHANCE_YOUR_CALM,
+            "Header block exceeds the maximum header size",
+        )
+    }
+
+    /// Sends GOAWAY with `code` and returns the error that ends the connection.
+    fn go_away<T: Write>(&self, io: &mut T, code: u32, message: &str) -> io::Error {
+        let mut payload = self.last_stream_id.to_be_bytes().to_vec();
+        payload.extend_from_slice(&code.to_be_bytes());
+        // The connection is closed either way, so a failed write changes nothing.
+        let _ = write_frame(io, GOAWAY, 0, 0, &payload).and_then(|()| io.flush());
+        protocol_error(message)
+    }
+
+    /// Queues the request of a stream whose client side has ended.
+    fn finish_stream(&mut self, stream_id: u32) {
+        if let Some(stream) = self.streams.remove(&stream_id) {
+            self.ready
+                .push_back((stream_id, http1_request(&stream.headers, &stream.body)));
+        }
+    }
+}
+
+/// Reads one frame, rejecting payloads larger than the default maximum frame size.
+fn read_frame<T: Read>(io: &mut T) -> io::Result<Frame> {
+    let mut header = [0u8; 9];
+    io.read_exact(&mut header)?;
+
+    let len = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+    if len > DEFAULT_MAX_FRAME_SIZE {
+        return Err(protocol_error("Frame exceeds the maximum frame size"));
+    }
+    let mut payload = vec![0u8; len];
+    io.read_exact(&mut payload)?;
+
+    Ok(Frame {
+        kind: header[3],
+        flags: header[4],
+        stream_id: u32::from_be_bytes([header[5], header[6], header[7], header[8]]) & 0x7FFF_FFFF,
+        payload,
+    })
+}
+
+/// Writes one frame.
+fn write_frame<T: Write>(
+    io: &mut T,
+    kind: u8,
+    flags: u8,
+    stream_id: u32,
+    payload: &[u8],
+) -> io::Result<()> {
+    let mut frame = Vec::with_capacity(9 + payload.len());
+    frame.extend_from_slice(&(payload.len() as u32).to_be_bytes()[1..]);
+    frame.push(kind);
+    frame.push(flags);
+    frame.extend_from_slice(&stream_id.to_be_bytes());
+    frame.extend_from_slice(payload);
+    io.write_all(&frame)
+}
+
+/// Returns the payload of a DATA or HEADERS frame without padding and priority fields.
+fn frame_data(frame: &Frame) -> io::Result<&[u8]> {
+    let invalid = || protocol_error("Invalid frame padding");
+    let mut data = frame.payload.as_slice();
+    let mut padding = 0;
+
+    if frame.flags & FLAG_PADDED != 0 {
+        let (&len, rest) = data.split_first().ok_or_else(invalid)?;
+        padding = usize::from(len);
+        data = rest;
+    }
+    if frame.kind == HEADERS && frame.flags & FLAG_PRIORITY != 0 {
+        data = data.get(5..).ok_or_else(invalid)?;
+    }
+
+    let len = data.len().checked_sub(padding).ok_or_else(invalid)?;
+    Ok(&data[..len])
+}
+
+/// Converts decoded request headers and body into HTTP/1.1-style request bytes.
+///
+/// `:authority` becomes the `Host` header, `cookie` fields are joined as
+/// required by RFC 9113 §8.2.3, and `Content-Length` reflects the received body.
+fn http1_request(headers: &[(String, String)], body: &[u8]) -> Vec<u8> {
+    let pseudo = |name: &str| {
+        headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+            .unwrap_or_default()
+    };
+
+    let mut request = format!("{} {} HTTP/2\r\n", pseudo(":method"), pseudo(":path"));
+    if !headers.iter().any(|(name, _)| name == "host") && !pseudo(":authority").is_empty() {
+        request.push_str(&format!("host: {}\r\n", pseudo(":authority")));
+    }
+
+    let cookies: Vec<&str> = headers
+        .iter()
+        .filter(|(name, _)| name == "cookie")
+        .map(|(_, value)| value.as_str())
+        .collect();
+    if !cookies.is_empty() {
+        request.push_str(&format!("cookie: {}\r\n", cookies.join("; ")));
+    }
+
+    for (name, value) in headers {
+        if name.starts_with(':') || name == "cookie" || name == "content-length" {
+            continue;
+        }
+        request.push_str(&format!("{name}: {value}\r\n"));
+    }
+    if !body.is_empty() {
+        request.push_str(&format!("content-length: {}\r\n", body.len()));
+    }
+    request.push_str("\r\n");
+
+    let mut bytes = request.into_bytes();
+    bytes.extend_from_slice(body);
+    bytes
+}
+
+/// Collects the response headers to send, with lowercase names and the `:status` pseudo-header first.
+fn response_headers(response: &HTTPResponse) -> Vec<(String, String)> {
+    let headers = &response.message.headers;
+    let mut fields = vec![(
+        ":status".to_string(),
+        response.status_code.as_u16().to_string(),
+    )];
+
+    fields.push(("content-type".to_string(), headers.content_type_value()));
+    if let Some(len) = response.content_length()
+        && !response.forbids_body()
+    {
+        fields.push(("content-length".to_string(), len.to_string()));
+    }
+
+    for line in headers.as_str().lines() {
+        if let Some((name, value)) = line.split_once(':') {
+            let name = name.trim().to_lowercase();
+            if !CONNECTION_HEADERS.contains(&name.as_str()) {
+                fields.push((name, value.trim().to_string()));
+            }
+        }
+    }
+    fields
+}
+
+/// Creates the error used for any violation of the HTTP/2 protocol.
+fn protocol_error(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
@@ -60,6 +60,104 @@ pub enum ContentType {
     Unknown(String, String),
 }
 
+impl ContentType {
+    /// `application/octet-stream`, the type for arbitrary binary data and the
+    /// fallback when a request declares no content type.
+    ///
+    /// ```
+    /// use sunweb::webserver::http_packet::header::content_types::ContentType;
+    ///
+    /// assert_eq!(ContentType::octet_stream().to_string(), "application/octet-stream");
+    /// ```
+    pub fn octet_stream() -> ContentType {
+        ContentType::Application(ApplicationSubType::OctetStream)
+    }
+
+    /// `true` for textual types that carry a `charset` parameter: `text/*`,
+    /// JSON, XML, JavaScript and SVG images.
+    ///
+    /// ```
+    /// use sunweb::webserver::http_packet::header::content_types::ContentType;
+    /// use sunweb::webserver::http_packet::header::content_types::application::ApplicationSubType;
+    /// use sunweb::webserver::http_packet::header::content_types::image::ImageSubType;
+    /// use sunweb::webserver::http_packet::header::content_types::text::TextSubType;
+    /// use std::str::FromStr;
+    ///
+    /// assert!(ContentType::Text(TextSubType::Css).is_textual());
+    /// assert!(ContentType::Application(ApplicationSubType::Json).is_textual());
+    /// assert!(!ContentType::Image(ImageSubType::Png).is_textual());
+    ///
+    /// let expected = [
+    ///     ("text/html", true),
+    ///     ("text/event-stream", true),
+    ///     ("application/json", true),
+    ///     ("application/xml", true),
+    ///     ("application/javascript", true),
+    ///     ("image/svg+xml", true),
+    ///     ("image/png", false),
+    ///     ("application/wasm", false),
+    ///     ("application/octet-stream", false),
+    ///     ("font/woff2", false),
+    /// ];
+    /// for (mime, text) in expected {
+    ///     assert_eq!(ContentType::from_str(mime).unwrap().is_textual(), text, "{mime}");
+    /// }
+    /// ```
+    pub fn is_textual(&self) -> bool {
+        matches!(
+            self,
+            ContentType::Text(_)
+                | ContentType::Application(
+                    ApplicationSubType::Json
+                        | ApplicationSubType::Xml
+                        | ApplicationSubType::Javascript
+                )
+                | ContentType::Image(ImageSubType::SvgXml)
+        )
+    }
+
+    /// `true` for types worth compressing before sending.
+    ///
+    /// These are the [textual](Self::is_textual) types except
+    /// `text/event-stream`, whose events must reach the client as soon as they
+    /// are written, plus uncompressed binary formats: form data, WebAssembly,
+    /// BMP images and OpenType/TrueType fonts. Formats that are compressed
+    /// already, such as PNG, WOFF or ZIP, are not.
+    ///
+    /// ```
+    /// use sunweb::webserver::http_packet::header::content_types::ContentType;
+    /// use std::str::FromStr;
+    ///
+    /// let expected = [
+    ///     ("text/css", true),
+    ///     ("text/event-stream", false),
+    ///     ("application/json", true),
+    ///     ("image/svg+xml", true),
+    ///     ("application/wasm", true),
+    ///     ("image/bmp", true),
+    ///     ("font/ttf", true),
+    ///     ("font/woff2", false),
+    ///     ("image/jpeg", false),
+    ///     ("application/gzip", false),
+    ///     ("video/mp4", false),
+    /// ];
+    /// for (mime, compressible) in expected {
+    ///     assert_eq!(ContentType::from_str(mime).unwrap().is_compressible(), compressible, "{mime}");
+    /// }
+    /// ```
+    pub fn is_compressible(&self) -> bool {
+        match self {
+            ContentType::Text(TextSubType::EventStream) => false,
+            ContentType::Application(
+                ApplicationSubType::XWwwFormUrlEncoded | ApplicationSubType::Wasm,
+            )
+            | ContentType::Image(ImageSubType::Bmp)
+            | ContentType::Font(FontSubType::Otf | FontSubType::Ttf) => true,
+            _ => self.is_textual(),
+        }
+    }
+}
+
 impl fmt::Display for ContentType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let (main, sub) = match self {
@@ -101,13 +199,17 @@ impl FromStr for ContentType {
 }
 
 /// ---------- Display impls for all subtypes ----------
+///
+/// Variants are rendered as their lowercased name; multi-word variants list
+/// their wire name explicitly.
 macro_rules! impl_display {
-    ($($t:ty),*) => {
+    ($($t:ty $({ $($variant:ident => $name:literal),* })?),*) => {
         $(
             impl fmt::Display for $t {
                 fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
                     let s = match self {
                         Self::Other(v) => v.clone(),
+                        $($(Self::$variant => $name.to_string(),)*)?
                         _ => format!("{:?}", self).to_lowercase(),
                     };
                     write!(f, "{}", s.replace('_', "-"))
@@ -118,11 +220,14 @@ macro_rules! impl_display {
 }
 
 impl_display!(
-    TextSubType,
-    ApplicationSubType,
-    ImageSubType,
+    TextSubType { EventStream => "event-stream" },
+    ApplicationSubType {
+        OctetStream => "octet-stream",
+        XWwwFormUrlEncoded => "x-www-form-urlencoded"
+    },
+    ImageSubType { SvgXml => "svg+xml" },
     AudioSubType,
     VideoSubType,
     FontSubType,
-    MultipartSubType
+    MultipartSubType { FormData => "form-data" }
 );
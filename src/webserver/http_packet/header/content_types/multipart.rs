@@ -27,6 +27,8 @@ pub enum MultipartSubType {
     Alternative,
     /// `multipart/related`
     Related,
+    /// `multipart/byteranges`, used for responses to multi-range requests
+    ByteRanges,
     /// Anything else; stored verbatim.
     Other(String),
 }
@@ -40,6 +42,7 @@ impl FromStr for MultipartSubType {
             "mixed" => Self::Mixed,
             "alternative" => Self::Alternative,
             "related" => Self::Related,
+            "byteranges" => Self::ByteRanges,
             other => Self::Other(other.into()),
         })
     }
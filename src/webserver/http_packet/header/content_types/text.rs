@@ -33,6 +33,8 @@ pub enum TextSubType {
     Xml,
     /// `text/markdown`
     Markdown,
+    /// `text/event-stream`, used for server-sent events
+    EventStream,
     /// Anything else; stored verbatim.
     Other(String),
 }
@@ -49,6 +51,7 @@ impl FromStr for TextSubType {
             "csv" => Self::Csv,
             "xml" => Self::Xml,
             "markdown" => Self::Markdown,
+            "event-stream" => Self::EventStream,
             other => Self::Other(other.into()),
         })
     }
@@ -22,6 +22,7 @@ pub enum CacheControl {
 }
 
 impl CacheControl {
+    /// Renders the directive(s) as a `Cache-Control` header value.
     pub fn as_str(&self) -> String {
         match self {
             CacheControl::NoCache => "no-cache".to_string(),
@@ -27,14 +27,16 @@ pub struct Cookie {
     max_age: Option<u64>,
     /// The path for which the cookie is valid.
     path: String,
-    /// The domain for which the cookie is valid.
-    domain: Domain,
+    /// The domain for which the cookie is valid; `None` for a host-only cookie.
+    domain: Option<Domain>,
     /// The SameSite policy for the cookie.
     same_site: SameSite,
     /// Whether the cookie should only be sent over secure (HTTPS) connections.
     secure: bool,
     /// Whether the cookie should be accessible only through the HTTP protocol.
     is_http_only: bool,
+    /// Whether the cookie tells the browser to delete it; see [`delete`](Self::delete).
+    deleted: bool,
 }
 
 impl Cookie {
@@ -65,38 +67,68 @@ impl Cookie {
             value: value.to_string(),
             max_age: None,
             path: "/".to_string(),
-            domain: domain.clone(),
+            domain: Some(domain.clone()),
             same_site: SameSite::Lax, // sensible default
             secure: false,
             is_http_only: false,
+            deleted: false,
         }
     }
 
-    /// Converts the cookie to its string representation.
+    /// Serializes the cookie as the value of a `Set-Cookie` header.
     ///
-    /// This method formats all cookie attributes into a single string that can be used in an HTTP `Set-Cookie` header.
+    /// All attributes are included in a fixed order: `Max-Age` and `Expires` (if set),
+    /// `Path`, `Domain` (unless [host-only](Self::host_only)), `SameSite`, and the
+    /// `Secure` and `HttpOnly` flags.
+    ///
+    /// Browsers reject cookies whose name prefix is not backed by the matching
+    /// attributes, so these are corrected here: a `__Secure-` cookie is always
+    /// `Secure`, and a `__Host-` cookie is always `Secure`, host-only and
+    /// scoped to `Path=/`.
     ///
     /// # Returns
     ///
-    /// A formatted string representing the cookie with all its attributes.
+    /// The cookie and its attributes, separated by `"; "`.
     ///
     /// # Examples
     ///
     /// ```
-    /// use webserver::Domain;
-    /// use webserver::cookie::{Cookie, SameSite};
+    /// use sunweb::webserver::Domain;
+    /// use sunweb::webserver::http_packet::header::headers::cookie::{Cookie, SameSite};
     ///
     /// let domain = Domain::new("example.com");
     /// let cookie = Cookie::new("session_id", "abc123", &domain)
-    ///     .expires(Some(3600))
+    ///     .same_site(SameSite::Strict)
     ///     .secure()
     ///     .http_only();
     ///
-    /// assert_eq!(cookie.as_string(), "session_id=abc123; Max-Age=3600; Expires=...; Path=/; Domain=example.com; SameSite=Lax; Secure; HttpOnly");
+    /// assert_eq!(
+    ///     cookie.to_set_cookie_string(),
+    ///     "session_id=abc123; Path=/; Domain=example.com; SameSite=Strict; Secure; HttpOnly"
+    /// );
+    ///
+    /// let session = Cookie::new("__Host-session", "abc123", &domain).path("/admin").http_only();
+    /// assert_eq!(
+    ///     session.to_set_cookie_string(),
+    ///     "__Host-session=abc123; Path=/; SameSite=Lax; Secure; HttpOnly"
+    /// );
+    ///
+    /// let token = Cookie::new("__Secure-token", "xyz", &domain);
+    /// assert_eq!(
+    ///     token.to_set_cookie_string(),
+    ///     "__Secure-token=xyz; Path=/; Domain=example.com; SameSite=Lax; Secure"
+    /// );
     /// ```
-    pub(crate) fn as_string(&self) -> String {
+    pub fn to_set_cookie_string(&self) -> String {
+        let host_prefix = has_prefix(&self.key, "__Host-");
+        let secure = self.secure || host_prefix || has_prefix(&self.key, "__Secure-");
+        let path = if host_prefix { "/" } else { &self.path };
+        let domain = self.domain.as_ref().filter(|_| !host_prefix);
+
         let mut base = format!("{}={}; ", self.key, self.value);
-        if let Some(seconds) = self.max_age {
+        if self.deleted {
+            base.push_str("Max-Age=0; Expires=Thu, 01 Jan 1970 00:00:00 GMT; ");
+        } else if let Some(seconds) = self.max_age {
             base.push_str(&format!("Max-Age={}; ", seconds));
             let expires = Utc::now() + Duration::seconds(seconds as i64);
             base.push_str(&format!(
@@ -104,21 +136,23 @@ impl Cookie {
                 expires.format("%a, %d %b %Y %H:%M:%S GMT")
             ));
         }
-        base.push_str(&format!("Path={}; ", self.path));
-        base.push_str(&format!("Domain={}; ", &self.domain.name));
+        base.push_str(&format!("Path={}; ", path));
+        if let Some(domain) = domain {
+            base.push_str(&format!("Domain={}; ", domain.name));
+        }
         let same_site_str = match self.same_site {
             SameSite::None => "None",
             SameSite::Lax => "Lax",
             SameSite::Strict => "Strict",
         };
         base.push_str(&format!("SameSite={}; ", same_site_str));
-        if self.secure {
+        if secure {
             base.push_str("Secure; ");
         }
         if self.is_http_only {
             base.push_str("HttpOnly; ");
         }
-        base.trim_end().to_string()
+        base.trim_end_matches("; ").to_string()
     }
 
     /// Sets the maximum age of the cookie.
@@ -145,6 +179,42 @@ impl Cookie {
         self
     }
 
+    /// Turns the cookie into one that deletes it from the browser.
+    ///
+    /// The value is cleared and the cookie is sent with `Max-Age=0` and an
+    /// `Expires` date at the Unix epoch, so clients that only understand
+    /// either attribute drop it. Browsers only delete a cookie whose `Path`
+    /// and `Domain` match the ones it was set with; those are kept. Any
+    /// earlier [`expires`](Self::expires) is ignored.
+    ///
+    /// # Returns
+    ///
+    /// The modified `Cookie` instance for chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sunweb::webserver::Domain;
+    /// use sunweb::webserver::http_packet::header::headers::cookie::Cookie;
+    ///
+    /// let domain = Domain::new("example.com");
+    /// let cookie = Cookie::new("session_id", "abc123", &domain)
+    ///     .path("/admin")
+    ///     .expires(Some(3600))
+    ///     .delete();
+    ///
+    /// assert_eq!(
+    ///     cookie.to_set_cookie_string(),
+    ///     "session_id=; Max-Age=0; Expires=Thu, 01 Jan 1970 00:00:00 GMT; \
+    ///      Path=/admin; Domain=example.com; SameSite=Lax"
+    /// );
+    /// ```
+    pub fn delete(mut self) -> Self {
+        self.value.clear();
+        self.deleted = true;
+        self
+    }
+
     /// Marks the cookie as secure.
     ///
     /// This ensures that the cookie is only sent over HTTPS connections.
@@ -236,10 +306,33 @@ impl Cookie {
         self.same_site = same_site;
         self
     }
-}
 
-impl FromIterator<bool> for Cookie {
-    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
-        iter.into_iter().collect()
+    /// Omits the `Domain` attribute, so the cookie is only sent to the exact
+    /// host that set it and not to its subdomains.
+    ///
+    /// # Returns
+    ///
+    /// The modified `Cookie` instance for chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sunweb::webserver::Domain;
+    /// use sunweb::webserver::http_packet::header::headers::cookie::Cookie;
+    ///
+    /// let domain = Domain::new("example.com");
+    /// let cookie = Cookie::new("theme", "dark", &domain).host_only();
+    /// assert_eq!(cookie.to_set_cookie_string(), "theme=dark; Path=/; SameSite=Lax");
+    /// ```
+    pub fn host_only(mut self) -> Self {
+        self.domain = None;
+        self
     }
 }
+
+/// `true` if the cookie name starts with `prefix`, compared case-insensitively
+/// as browsers do.
+fn has_prefix(name: &str, prefix: &str) -> bool {
+    name.get(..prefix.len())
+        .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+}
@@ -0,0 +1,175 @@
+use crate::webserver::route::HTTPMethod;
+
+/// A cross-origin resource sharing policy.
+///
+/// Lists the origins allowed to read responses, plus the methods, request
+/// headers and credentials they may use. Apply it with
+/// [`HTTPResponse::apply_cors`](crate::webserver::responses::HTTPResponse::apply_cors),
+/// which only echoes the request's `Origin` back when the policy allows it.
+#[derive(Clone, Debug)]
+pub struct CorsPolicy {
+    /// Origins allowed to read responses, e.g. `https://app.example.com`.
+    origins: Vec<String>,
+    /// Whether every origin is allowed.
+    any_origin: bool,
+    /// Methods allowed in cross-origin requests.
+    methods: Vec<HTTPMethod>,
+    /// Request headers allowed in cross-origin requests.
+    headers: Vec<String>,
+    /// Whether cookies and HTTP authentication may be included.
+    credentials: bool,
+    /// How long browsers may cache preflight results, in seconds.
+    max_age: Option<u64>,
+}
+
+impl CorsPolicy {
+    /// Creates a policy that allows no origins until some are added with
+    /// [`allow_origin`](Self::allow_origin).
+    ///
+    /// `GET`, `HEAD` and `POST` are allowed by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sunweb::webserver::http_packet::header::headers::cors::CorsPolicy;
+    /// use sunweb::webserver::route::HTTPMethod;
+    ///
+    /// let policy = CorsPolicy::new()
+    ///     .allow_origin("https://app.example.com")
+    ///     .allow_methods(&[HTTPMethod::GET, HTTPMethod::PUT])
+    ///     .allow_headers(&["Content-Type", "Authorization"])
+    ///     .allow_credentials()
+    ///     .max_age(600);
+    /// assert!(policy.allows_origin("https://app.example.com"));
+    /// assert!(!policy.allows_origin("https://evil.example"));
+    /// ```
+    pub fn new() -> CorsPolicy {
+        Self {
+            origins: Vec::new(),
+            any_origin: false,
+            methods: vec![HTTPMethod::GET, HTTPMethod::HEAD, HTTPMethod::POST],
+            headers: Vec::new(),
+            credentials: false,
+            max_age: None,
+        }
+    }
+
+    /// Creates a policy that allows every origin.
+    ///
+    /// Responses carry `Access-Control-Allow-Origin: *`. Credentials are never
+    /// allowed for such a policy, since browsers reject `*` for credentialed
+    /// requests and echoing every origin instead would let any site read
+    /// responses with the user's cookies.
+    pub fn any_origin() -> CorsPolicy {
+        Self {
+            any_origin: true,
+            ..Self::new()
+        }
+    }
+
+    /// Adds an origin to the allowlist.
+    ///
+    /// # Arguments
+    ///
+    /// * `origin` - The origin as sent by browsers: scheme, host and optional port.
+    pub fn allow_origin(mut self, origin: &str) -> Self {
+        self.origins.push(origin.trim_end_matches('/').to_string());
+        self
+    }
+
+    /// Replaces the allowed methods.
+    pub fn allow_methods(mut self, methods: &[HTTPMethod]) -> Self {
+        self.methods = methods.to_vec();
+        self
+    }
+
+    /// Replaces the allowed request headers.
+    pub fn allow_headers(mut self, headers: &[&str]) -> Self {
+        self.headers = headers.iter().map(|h| h.to_string()).collect();
+        self
+    }
+
+    /// Allows cookies and HTTP authentication in cross-origin requests.
+    ///
+    /// Ignored for an [`any_origin`](Self::any_origin) policy, which keeps
+    /// answering with `*` and no credentials.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sunweb::webserver::http_packet::header::headers::cors::CorsPolicy;
+    /// use sunweb::webserver::responses::HTTPResponse;
+    ///
+    /// let mut response = HTTPResponse::ok();
+    /// response.apply_cors(
+    ///     &CorsPolicy::any_origin().allow_credentials(),
+    ///     Some("https://evil.example"),
+    /// );
+    /// assert_eq!(response.get_header("Access-Control-Allow-Origin"), Some("*".into()));
+    /// assert_eq!(response.get_header("Access-Control-Allow-Credentials"), None);
+    /// ```
+    pub fn allow_credentials(mut self) -> Self {
+        self.credentials = true;
+        self
+    }
+
+    /// Sets how long browsers may cache preflight results.
+    ///
+    /// # Arguments
+    ///
+    /// * `seconds` - The cache duration in seconds.
+    pub fn max_age(mut self, seconds: u64) -> Self {
+        self.max_age = Some(seconds);
+        self
+    }
+
+    /// Returns `true` if `origin` may read responses under this policy.
+    ///
+    /// Origins are compared case-insensitively.
+    pub fn allows_origin(&self, origin: &str) -> bool {
+        self.any_origin
+            || self
+                .origins
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(origin))
+    }
+
+    /// The `Access-Control-Allow-Origin` value for a request from `origin`,
+    /// or `None` if the origin is not allowed.
+    pub(crate) fn allow_origin_value(&self, origin: Option<&str>) -> Option<String> {
+        if self.any_origin {
+            return Some("*".to_string());
+        }
+        origin
+            .filter(|origin| self.allows_origin(origin))
+            .map(str::to_string)
+    }
+
+    pub(crate) fn methods(&self) -> &[HTTPMethod] {
+        &self.methods
+    }
+
+    pub(crate) fn headers(&self) -> &[String] {
+        &self.headers
+    }
+
+    /// Whether responses depend on the request's origin, and so need
+    /// `Vary: Origin`.
+    pub(crate) fn varies_by_origin(&self) -> bool {
+        !self.any_origin
+    }
+
+    pub(crate) fn credentials(&self) -> bool {
+        self.credentials && !self.any_origin
+    }
+
+    pub(crate) fn max_age_seconds(&self) -> Option<u64> {
+        self.max_age
+    }
+}
+
+impl Default for CorsPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,7 +1,8 @@
-﻿pub(crate) mod cache_control;
+﻿pub mod cache_control;
 pub(crate) mod content_encoding;
 pub(crate) mod content_security_policy;
-pub(crate) mod cookie;
+pub mod cookie;
+pub mod cors;
 pub(crate) mod frame_option;
 pub(crate) mod referer_policy;
 pub(crate) mod transfer_encoding;
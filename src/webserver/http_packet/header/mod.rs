@@ -1,15 +1,37 @@
 use crate::webserver::http_packet::header::connection::ConnectionType;
-use crate::webserver::http_packet::header::content_types::text::TextSubType;
 use crate::webserver::http_packet::header::content_types::ContentType;
+use crate::webserver::http_packet::header::content_types::text::TextSubType;
 use crate::webserver::http_packet::header::headers::frame_option::FrameOption;
+use crate::webserver::route::HTTPMethod;
 use chrono::{DateTime, Utc};
 use headers::cookie::Cookie;
+use headers::cors::CorsPolicy;
 use std::collections::HashMap;
 
 pub mod connection;
 pub mod content_types;
 pub mod headers;
 
+/// `strftime` format of an HTTP date (RFC 7231 IMF-fixdate).
+const HTTP_DATE: &str = "%a, %d %b %Y %H:%M:%S GMT";
+
+/// Builds a `Strict-Transport-Security` header value.
+pub(crate) fn hsts_value(max_age_seconds: u64, include_subdomains: bool, preload: bool) -> String {
+    let mut value = format!("max-age={}", max_age_seconds);
+    if include_subdomains {
+        value.push_str("; includeSubDomains");
+    }
+    if preload {
+        value.push_str("; preload");
+    }
+    value
+}
+
+/// Removes the characters that would split a header line.
+fn strip_line_breaks(value: &str) -> String {
+    value.replace(['\r', '\n', '\0'], "")
+}
+
 /// Represents HTTP response headers
 ///
 /// The `HTTPHeader` struct manages HTTP headers including content type,
@@ -18,6 +40,10 @@ pub mod headers;
 pub struct HTTPHeader {
     pub(crate) values: HashMap<String, String>,
     pub content_type: ContentType,
+    /// Boundary parameter appended to a `multipart/*` content type.
+    pub(crate) content_type_boundary: Option<String>,
+    /// Charset parameter appended to textual content types; `None` sends none.
+    pub(crate) charset: Option<String>,
     pub content_length: Option<u64>,
     pub connection: ConnectionType,
     cookies: Vec<Cookie>,
@@ -29,30 +55,61 @@ impl HTTPHeader {
         Self {
             values,
             content_type: ContentType::Text(TextSubType::Html),
+            content_type_boundary: None,
+            charset: Some(String::from("utf-8")),
             content_length: None,
             connection: ConnectionType::KeepAlive,
             cookies: Vec::new(),
         }
     }
 
+    /// The `Content-Type` value including its `charset` and `boundary` parameters.
+    ///
+    /// Line breaks are removed like in [`add_header`](Self::add_header), since
+    /// the charset and custom media types may come from user input.
+    pub(crate) fn content_type_value(&self) -> String {
+        let mut value = self.content_type.to_string();
+        if let Some(charset) = &self.charset
+            && self.content_type.is_textual()
+        {
+            value.push_str(&format!("; charset={}", charset));
+        }
+        if let Some(boundary) = &self.content_type_boundary {
+            value.push_str(&format!("; boundary={}", boundary));
+        }
+        strip_line_breaks(&value)
+    }
+
     /// Converts the headers to a string representation
     pub(crate) fn as_str(&self) -> String {
         let mut result = String::new();
 
         for (k, v) in &self.values {
+            // Content-Length is derived from the body when the message is serialized.
+            if k.eq_ignore_ascii_case("Content-Length") {
+                continue;
+            }
             result.push_str(&format!("{}: {}\r\n", k, v));
         }
 
         for cookie in &self.cookies {
-            result.push_str(&format!("Set-Cookie: {}\r\n", cookie.as_string()));
+            result.push_str(&format!(
+                "Set-Cookie: {}\r\n",
+                strip_line_breaks(&cookie.to_set_cookie_string())
+            ));
         }
 
         result
     }
 
     /// Adds a header to the response
+    ///
+    /// CR, LF and NUL characters are removed from the name and value, so a
+    /// value taken from user input cannot end the header line early and
+    /// inject further headers or a body.
     pub fn add_header(&mut self, key: &str, value: &str) {
-        self.values.insert(key.to_string(), value.to_string());
+        self.values
+            .insert(strip_line_breaks(key), strip_line_breaks(value));
     }
 
     /// Gets a header value by name (case-insensitive)
@@ -106,9 +163,8 @@ impl HTTPHeader {
     /// let cookie = Cookie::new("session", "abc123");
     /// headers.expire_cookie(cookie);
     /// ```
-    pub(crate) fn expire_cookie(&mut self, mut cookie: Cookie) {
-        cookie = cookie.expires(Some(0));
-        self.cookies.push(cookie);
+    pub(crate) fn expire_cookie(&mut self, cookie: Cookie) {
+        self.cookies.push(cookie.delete());
     }
 
     /// Sets the Date header to the current UTC time
@@ -126,7 +182,7 @@ impl HTTPHeader {
     /// ```
     pub(crate) fn set_date_now(&mut self) {
         let now = Utc::now();
-        self.add_header("Date", &now.format("%a, %d %b %Y %H:%M:%S GMT").to_string());
+        self.add_header("Date", &now.format(HTTP_DATE).to_string());
     }
 
     /// Sets the Server header to identify the server software
@@ -163,6 +219,15 @@ impl HTTPHeader {
         self.add_header("Location", url);
     }
 
+    /// Sets the Upgrade header and marks the connection for upgrading
+    ///
+    /// # Arguments
+    /// * `protocols` - Protocols the client may switch to, most preferred first
+    pub(crate) fn set_upgrade(&mut self, protocols: &[&str]) {
+        self.add_header("Upgrade", &protocols.join(", "));
+        self.connection = ConnectionType::Upgrade;
+    }
+
     /// Sets the Cache-Control header with custom directives
     ///
     /// # Arguments
@@ -251,10 +316,38 @@ impl HTTPHeader {
     /// headers.set_last_modified(Utc::now());
     /// ```
     pub(crate) fn set_last_modified(&mut self, datetime: DateTime<Utc>) {
-        self.add_header(
-            "Last-Modified",
-            &datetime.format("%a, %d %b %Y %H:%M:%S GMT").to_string(),
-        );
+        self.add_header("Last-Modified", &datetime.format(HTTP_DATE).to_string());
+    }
+
+    /// Sets the Expires header
+    ///
+    /// Indicates the date after which the response is considered stale.
+    ///
+    /// # Arguments
+    /// * `datetime` - The expiry timestamp
+    pub(crate) fn set_expires(&mut self, datetime: DateTime<Utc>) {
+        self.add_header("Expires", &datetime.format(HTTP_DATE).to_string());
+    }
+
+    /// Sets the Retry-After header to a delay in seconds
+    ///
+    /// Tells the client how long to wait before retrying, typically alongside
+    /// `503 Service Unavailable` or `429 Too Many Requests`.
+    ///
+    /// # Arguments
+    /// * `seconds` - The delay in seconds
+    pub(crate) fn set_retry_after_secs(&mut self, seconds: u64) {
+        self.add_header("Retry-After", &seconds.to_string());
+    }
+
+    /// Sets the Retry-After header to a date
+    ///
+    /// Tells the client the earliest time at which it should retry.
+    ///
+    /// # Arguments
+    /// * `datetime` - The time after which the request may be retried
+    pub(crate) fn set_retry_after_date(&mut self, datetime: DateTime<Utc>) {
+        self.add_header("Retry-After", &datetime.format(HTTP_DATE).to_string());
     }
 
     /// Sets the Content-Encoding header
@@ -347,11 +440,10 @@ impl HTTPHeader {
     /// headers.set_hsts(31536000, true); // 1 year, include subdomains
     /// ```
     pub(crate) fn set_hsts(&mut self, max_age_seconds: u64, include_subdomains: bool) {
-        let mut value = format!("max-age={}", max_age_seconds);
-        if include_subdomains {
-            value.push_str("; includeSubDomains");
-        }
-        self.add_header("Strict-Transport-Security", &value);
+        self.add_header(
+            "Strict-Transport-Security",
+            &hsts_value(max_age_seconds, include_subdomains, false),
+        );
     }
 
     /// Sets Content-Security-Policy header
@@ -412,6 +504,18 @@ impl HTTPHeader {
         self.set_csp("default-src 'self'");
     }
 
+    /// Applies the headers from [`apply_security_headers`](Self::apply_security_headers)
+    /// that are not already present, leaving explicitly set values untouched.
+    pub(crate) fn apply_default_security_headers(&mut self) {
+        let mut defaults = HTTPHeader::new(HashMap::new());
+        defaults.apply_security_headers();
+        for (key, value) in defaults.values {
+            if self.get_header(&key).is_none() {
+                self.values.insert(key, value);
+            }
+        }
+    }
+
     // ===== CORS Headers =====
 
     /// Sets Access-Control-Allow-Origin header
@@ -446,9 +550,10 @@ impl HTTPHeader {
     /// use std::collections::HashMap;
     ///
     /// let mut headers = HTTPHeader::new(HashMap::new());
-    /// headers.set_cors_methods(&["GET", "POST", "PUT"]);
+    /// headers.set_cors_methods(&[HTTPMethod::GET, HTTPMethod::POST, HTTPMethod::PUT]);
     /// ```
-    pub(crate) fn set_cors_methods(&mut self, methods: &[&str]) {
+    pub(crate) fn set_cors_methods(&mut self, methods: &[HTTPMethod]) {
+        let methods: Vec<String> = methods.iter().map(HTTPMethod::to_string).collect();
         self.add_header("Access-Control-Allow-Methods", &methods.join(", "));
     }
 
@@ -525,8 +630,49 @@ impl HTTPHeader {
     /// ```
     pub(crate) fn apply_cors_permissive(&mut self) {
         self.set_cors_origin("*");
-        self.set_cors_methods(&["GET", "POST", "PUT", "DELETE", "OPTIONS", "PATCH"]);
+        self.set_cors_methods(&[
+            HTTPMethod::GET,
+            HTTPMethod::POST,
+            HTTPMethod::PUT,
+            HTTPMethod::DELETE,
+            HTTPMethod::OPTIONS,
+            HTTPMethod::PATCH,
+        ]);
         self.set_cors_headers(&["*"]);
         self.set_cors_max_age(86400);
     }
+
+    /// Applies a [`CorsPolicy`] to a response for a request from `origin`.
+    ///
+    /// `Vary: Origin` is added for allowlist policies, whether or not the
+    /// origin is allowed, so caches keep responses for different origins apart.
+    /// Nothing else is added when the origin is not allowed. Otherwise the
+    /// origin is echoed (or `*` for a wildcard policy), and the policy's
+    /// methods, headers, credentials and max-age follow.
+    pub(crate) fn apply_cors(&mut self, policy: &CorsPolicy, origin: Option<&str>) {
+        if policy.varies_by_origin() {
+            let vary = match self.get_header("Vary") {
+                Some(vary) if !vary.to_lowercase().contains("origin") => {
+                    format!("{vary}, Origin")
+                }
+                Some(vary) => vary,
+                None => "Origin".to_string(),
+            };
+            self.add_header("Vary", &vary);
+        }
+        let Some(allow_origin) = policy.allow_origin_value(origin) else {
+            return;
+        };
+
+        self.set_cors_origin(&allow_origin);
+        self.set_cors_methods(policy.methods());
+        if !policy.headers().is_empty() {
+            let headers: Vec<&str> = policy.headers().iter().map(String::as_str).collect();
+            self.set_cors_headers(&headers);
+        }
+        self.set_cors_credentials(policy.credentials());
+        if let Some(seconds) = policy.max_age_seconds() {
+            self.set_cors_max_age(seconds);
+        }
+    }
 }
@@ -1,7 +1,10 @@
 ﻿use crate::webserver::requests::HTTPRequest;
 use crate::webserver::responses::HTTPResponse;
 use chrono::Utc;
-use log::{Level, Metadata, Record};
+use log::{Level, LevelFilter, Metadata, Record, SetLoggerError};
+use std::cell::RefCell;
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 /// ANSI color code for red text.
 const RED: &str = "\x1b[31m";
@@ -21,6 +24,19 @@ const DIM: &str = "\x1b[2m";
 /// ANSI color code to reset text formatting.
 const RESET: &str = "\x1b[0m";
 
+/// Level overrides as `(target prefix, level)` pairs, set with
+/// [`Logger::with_target_level`].
+static TARGET_LEVELS: RwLock<Vec<(String, LevelFilter)>> = RwLock::new(Vec::new());
+
+/// Source of the ids correlating the start and end lines of a request.
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    /// The id and `METHOD [host] path` of the request being served on this
+    /// thread, left by `log_request_start` for `log_request_end`.
+    static CURRENT_REQUEST: RefCell<Option<(u64, String)>> = const { RefCell::new(None) };
+}
+
 /// A custom logger that provides colored console output based on log level.
 ///
 /// This logger uses ANSI escape codes to colorize log messages:
@@ -33,14 +49,19 @@ const RESET: &str = "\x1b[0m";
 /// The logger implements the `log::Log` trait, allowing integration with
 /// the standard Rust `log` facade.
 ///
+/// Noisy targets can be quieted with per-target levels, see
+/// [`with_target_level`](Self::with_target_level).
+///
 /// # Examples
 ///
 /// ```rust
 /// use log::SetLoggerError;
-/// use crate::webserver::logger::Logger;
+/// use sunweb::webserver::logger::Logger;
+///
+/// static LOGGER: Logger = Logger;
 ///
 /// # fn main() -> Result<(), SetLoggerError> {
-/// log::set_logger(&Logger).unwrap();
+/// log::set_logger(&LOGGER).unwrap();
 /// log::set_max_level(log::LevelFilter::Trace);
 /// log::error!("This will appear in red");
 /// log::info!("This will appear in blue");
@@ -53,9 +74,10 @@ impl log::Log for Logger {
     /// Determines if a log message should be processed based on its metadata.
     ///
     /// Returns `true` if the log level of the metadata is less than or equal to
-    /// the maximum allowed log level set by `log::max_level()`.
+    /// the level configured for its target, or to `log::max_level()` if no
+    /// target override matches.
     fn enabled(&self, metadata: &Metadata) -> bool {
-        metadata.level() <= log::max_level()
+        metadata.level() <= self.level_for(metadata.target())
     }
 
     /// Logs a record with appropriate coloring based on its level.
@@ -84,10 +106,95 @@ impl log::Log for Logger {
 }
 
 impl Logger {
+    /// Sets the level for log records whose target is `target` or one of its
+    /// submodules, e.g. `sunweb::webserver::proxy`.
+    ///
+    /// When several prefixes match, the longest one wins. The global
+    /// `log::max_level()` still filters records before they reach the logger,
+    /// so overrides can only make a target quieter than the global level.
+    ///
+    /// Overrides are process-wide, like the installed logger itself, and a
+    /// later call for the same target replaces the earlier level.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The target prefix, usually a module path.
+    /// * `level` - The most verbose level printed for that target.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use log::{Level, LevelFilter, Log, Metadata};
+    /// use sunweb::webserver::logger::Logger;
+    ///
+    /// let logger = Logger;
+    /// logger.with_target_level("sunweb::webserver::proxy", LevelFilter::Warn);
+    /// log::set_max_level(LevelFilter::Debug);
+    ///
+    /// let record = |target, level| Metadata::builder().target(target).level(level).build();
+    /// assert!(!logger.enabled(&record("sunweb::webserver::proxy", Level::Debug)));
+    /// assert!(!logger.enabled(&record("sunweb::webserver::proxy::pool", Level::Info)));
+    /// assert!(logger.enabled(&record("sunweb::webserver::proxy", Level::Warn)));
+    /// assert!(logger.enabled(&record("sunweb::webserver::client_handling", Level::Debug)));
+    /// assert!(logger.enabled(&record("sunweb::webserver::proxyish", Level::Debug)));
+    /// ```
+    pub fn with_target_level(&self, target: &str, level: LevelFilter) -> &Self {
+        let mut targets = TARGET_LEVELS.write().unwrap_or_else(|e| e.into_inner());
+        targets.retain(|(prefix, _)| prefix != target);
+        targets.push((target.to_string(), level));
+        self
+    }
+
+    /// The level for `target`: the longest matching override, or the global level.
+    fn level_for(&self, target: &str) -> LevelFilter {
+        TARGET_LEVELS
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter(|(prefix, _)| {
+                target
+                    .strip_prefix(prefix.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or_else(log::max_level, |(_, level)| *level)
+    }
+
+    /// Installs this logger as the global logger and sets the maximum log level.
+    ///
+    /// Wraps `log::set_logger` and `log::set_max_level` in one call. A logger
+    /// can only be installed once per process; later calls return the error
+    /// instead of panicking and leave the level unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - The most verbose level that will be printed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use log::LevelFilter;
+    /// use sunweb::WEB_LOGGER;
+    ///
+    /// assert!(WEB_LOGGER.init(LevelFilter::Info).is_ok());
+    /// assert_eq!(log::max_level(), LevelFilter::Info);
+    ///
+    /// // A second call reports the existing logger instead of panicking.
+    /// assert!(WEB_LOGGER.init(LevelFilter::Trace).is_err());
+    /// assert_eq!(log::max_level(), LevelFilter::Info);
+    /// ```
+    pub fn init(&'static self, level: LevelFilter) -> Result<(), SetLoggerError> {
+        log::set_logger(self)?;
+        log::set_max_level(level);
+        Ok(())
+    }
+
     /// Logs the start of an HTTP request.
     ///
-    /// Prints the request method, host, and path along with a timestamp.
-    /// Output is dimmed for readability.
+    /// Prints one line with a timestamp, a request id, and the request method,
+    /// host and path. Output is dimmed for readability. The id appears again
+    /// on the line [`log_request_end`](Self::log_request_end) prints, so lines
+    /// of concurrent requests can be told apart.
     ///
     /// # Arguments
     ///
@@ -96,29 +203,35 @@ impl Logger {
     /// # Examples
     ///
     /// ```
-    /// use crate::webserver::logger::Logger;
-    /// use crate::webserver::requests::HTTPRequest;
+    /// use sunweb::webserver::logger::Logger;
+    /// use sunweb::webserver::requests::HTTPRequest;
+    /// use sunweb::webserver::route::HTTPMethod;
     ///
-    /// let mut req = HTTPRequest::new("GET", "/", None);
+    /// let mut req = HTTPRequest::new(HTTPMethod::GET, "/", None);
     /// Logger::log_request_start(&mut req);
     /// ```
-    pub(crate) fn log_request_start(request: &mut HTTPRequest) {
+    pub fn log_request_start(request: &mut HTTPRequest) {
+        let id = NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed);
         let host = request.host().map(|h| h.to_string()).unwrap_or_default();
+        let summary = format!("{} [{}] {}", request.method, host, request.path);
 
-        print!(
-            "{}[INFO ]{}[{}] {} [{}] {}",
+        println!(
+            "{}[INFO ]{}[{}] #{} {}",
             DIM,
             RESET,
             Utc::now().format("%Y-%m-%d %H:%M:%S"),
-            request.method,
-            host,
-            request.path
+            id,
+            summary
         );
+        CURRENT_REQUEST.with(|current| *current.borrow_mut() = Some((id, summary)));
     }
 
     /// Logs the end of an HTTP request, including the response status code.
     ///
-    /// Colors the status code based on the HTTP response class:
+    /// Prints one line repeating the id, method, host and path logged by
+    /// [`log_request_start`](Self::log_request_start) on the same thread,
+    /// followed by the status. Colors the status code based on the HTTP
+    /// response class:
     /// - `2xx` is green
     /// - `3xx` is yellow
     /// - `4xx` and `5xx` are red
@@ -131,20 +244,98 @@ impl Logger {
     /// # Examples
     ///
     /// ```
-    /// use crate::webserver::logger::Logger;
-    /// use crate::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::logger::Logger;
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
     ///
-    /// let mut res = HTTPResponse::new(200);
+    /// let mut res = HTTPResponse::new(StatusCode::Ok);
     /// Logger::log_request_end(&mut res);
     /// ```
-    pub(crate) fn log_request_end(response: &mut HTTPResponse) {
+    ///
+    /// Concurrent requests each get their own start and end lines:
+    ///
+    /// ```rust,standalone_crate
+    /// use std::io::{Read, Write};
+    /// use std::net::{TcpListener, TcpStream};
+    /// use std::process::Command;
+    /// use std::sync::Arc;
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// // The server runs in a child process so its stdout can be inspected.
+    /// if std::env::var_os("SERVE_AND_LOG").is_none() {
+    ///     let output = Command::new(std::env::current_exe().unwrap())
+    ///         .env("SERVE_AND_LOG", "1")
+    ///         .output()
+    ///         .unwrap();
+    ///     assert!(output.status.success());
+    ///     let stdout = String::from_utf8(output.stdout).unwrap();
+    ///     let lines: Vec<&str> = stdout.lines().filter(|line| line.contains(" [localhost] /")).collect();
+    ///     assert_eq!(lines.len(), 4, "{stdout}");
+    ///     assert!(lines.iter().all(|line| line.matches("GET").count() == 1), "{stdout}");
+    ///
+    ///     let id = |line: &str| line.split_whitespace().find(|word| word.starts_with('#')).unwrap().to_string();
+    ///     for path in ["/slow", "/fast"] {
+    ///         let both: Vec<&&str> = lines.iter().filter(|line| line.contains(path)).collect();
+    ///         assert_eq!(both.len(), 2, "{stdout}");
+    ///         assert!(!both[0].contains("->") && both[1].contains("-> "), "{stdout}");
+    ///         assert_eq!(id(both[0]), id(both[1]));
+    ///     }
+    ///     return;
+    /// }
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 0));
+    /// server.add_custom_route("/slow", HTTPMethod::GET, |_request, _domain| {
+    ///     thread::sleep(Duration::from_millis(300));
+    ///     HTTPResponse::with_body(StatusCode::Ok, "slow")
+    /// }, StatusCode::Ok, None);
+    /// server.add_custom_route("/fast", HTTPMethod::GET, |_request, _domain| {
+    ///     HTTPResponse::with_body(StatusCode::Ok, "fast")
+    /// }, StatusCode::Ok, None);
+    /// let server = Arc::new(server);
+    ///
+    /// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    /// let addr = listener.local_addr().unwrap();
+    /// thread::spawn(move || {
+    ///     for stream in listener.incoming() {
+    ///         let server = Arc::clone(&server);
+    ///         thread::spawn(move || server.serve_connection(stream.unwrap()));
+    ///     }
+    /// });
+    ///
+    /// let get = move |path: &str| {
+    ///     let mut client = TcpStream::connect(addr).unwrap();
+    ///     write!(client, "GET {path} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n").unwrap();
+    ///     let mut response = String::new();
+    ///     client.read_to_string(&mut response).unwrap();
+    /// };
+    /// let slow = thread::spawn(move || get("/slow"));
+    /// thread::sleep(Duration::from_millis(100));
+    /// get("/fast");
+    /// slow.join().unwrap();
+    /// ```
+    pub fn log_request_end(response: &mut HTTPResponse) {
         let color = match response.status_code.as_u16() {
             200..=299 => GREEN,
             300..=399 => YELLOW,
             400..=599 => RED,
             _ => RESET,
         };
+        let timestamp = Utc::now().format("%Y-%m-%d %H:%M:%S");
 
-        println!(" {}-> {}{}{}", color, response.status_code, RESET, "");
+        match CURRENT_REQUEST.with(|current| current.borrow_mut().take()) {
+            Some((id, summary)) => println!(
+                "{}[INFO ]{}[{}] #{} {} {}-> {}{}",
+                DIM, RESET, timestamp, id, summary, color, response.status_code, RESET
+            ),
+            None => println!(
+                "{}[INFO ]{}[{}] {}-> {}{}",
+                DIM, RESET, timestamp, color, response.status_code, RESET
+            ),
+        }
     }
 }
@@ -0,0 +1,145 @@
+//! Request metrics collected by the web server.
+//!
+//! Every handled request is recorded with its response status and the time it
+//! took to produce and send the response. A point-in-time copy is available via
+//! [`WebServer::metrics_snapshot`](crate::webserver::WebServer::metrics_snapshot)
+//! or over HTTP via [`WebServer::add_metrics_route`](crate::webserver::WebServer::add_metrics_route).
+
+use crate::webserver::responses::StatusCode;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Number of most recent latencies kept for percentile calculation.
+const LATENCY_WINDOW: usize = 1024;
+
+/// Thread-safe accumulator shared by all connections of a server.
+#[derive(Default)]
+pub(crate) struct Metrics {
+    inner: Mutex<MetricsInner>,
+}
+
+#[derive(Default)]
+struct MetricsInner {
+    total_requests: u64,
+    status_classes: [u64; 5],
+    total_latency: Duration,
+    recent_latencies: VecDeque<Duration>,
+}
+
+impl Metrics {
+    /// Records one handled request.
+    pub(crate) fn record(&self, status_code: StatusCode, latency: Duration) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.total_requests += 1;
+        if let Some(count) = (status_code.as_u16() / 100)
+            .checked_sub(1)
+            .and_then(|class| inner.status_classes.get_mut(class as usize))
+        {
+            *count += 1;
+        }
+        inner.total_latency += latency;
+        if inner.recent_latencies.len() == LATENCY_WINDOW {
+            inner.recent_latencies.pop_front();
+        }
+        inner.recent_latencies.push_back(latency);
+    }
+
+    /// Returns a copy of the current counters.
+    pub(crate) fn snapshot(&self) -> MetricsSnapshot {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let mut sorted: Vec<Duration> = inner.recent_latencies.iter().copied().collect();
+        sorted.sort();
+        let percentile = |p: usize| {
+            if sorted.is_empty() {
+                Duration::ZERO
+            } else {
+                sorted[((sorted.len() - 1) * p) / 100]
+            }
+        };
+
+        MetricsSnapshot {
+            total_requests: inner.total_requests,
+            informational: inner.status_classes[0],
+            success: inner.status_classes[1],
+            redirection: inner.status_classes[2],
+            client_error: inner.status_classes[3],
+            server_error: inner.status_classes[4],
+            average_latency: match inner.total_requests {
+                0 => Duration::ZERO,
+                n => {
+                    let nanos = inner.total_latency.as_nanos() / u128::from(n);
+                    Duration::from_nanos(u64::try_from(nanos).unwrap_or(u64::MAX))
+                }
+            },
+            p50_latency: percentile(50),
+            p95_latency: percentile(95),
+            p99_latency: percentile(99),
+        }
+    }
+}
+
+/// A point-in-time copy of the server's request metrics.
+///
+/// Percentiles are computed over the most recent 1024 requests; totals and the
+/// average cover every request since the server was created.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MetricsSnapshot {
+    /// Number of requests handled.
+    pub total_requests: u64,
+    /// Responses with a `1xx` status.
+    pub informational: u64,
+    /// Responses with a `2xx` status.
+    pub success: u64,
+    /// Responses with a `3xx` status.
+    pub redirection: u64,
+    /// Responses with a `4xx` status.
+    pub client_error: u64,
+    /// Responses with a `5xx` status.
+    pub server_error: u64,
+    /// Mean time to handle a request.
+    pub average_latency: Duration,
+    /// Median time to handle a request.
+    pub p50_latency: Duration,
+    /// 95th percentile time to handle a request.
+    pub p95_latency: Duration,
+    /// 99th percentile time to handle a request.
+    pub p99_latency: Duration,
+}
+
+impl MetricsSnapshot {
+    /// Renders the snapshot in a simple `name value` text format, one metric per line.
+    ///
+    /// Latencies are reported in seconds.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sunweb::webserver::metrics::MetricsSnapshot;
+    ///
+    /// let text = MetricsSnapshot::default().to_text();
+    /// assert!(text.starts_with("requests_total 0\n"));
+    /// assert!(text.contains("responses_total{class=\"2xx\"} 0\n"));
+    /// ```
+    pub fn to_text(&self) -> String {
+        let mut text = format!("requests_total {}\n", self.total_requests);
+        for (class, count) in [
+            ("1xx", self.informational),
+            ("2xx", self.success),
+            ("3xx", self.redirection),
+            ("4xx", self.client_error),
+            ("5xx", self.server_error),
+        ] {
+            text.push_str(&format!("responses_total{{class=\"{class}\"}} {count}\n"));
+        }
+        for (name, latency) in [
+            ("request_duration_seconds_avg", self.average_latency),
+            ("request_duration_seconds_p50", self.p50_latency),
+            ("request_duration_seconds_p95", self.p95_latency),
+            ("request_duration_seconds_p99", self.p99_latency),
+        ] {
+            text.push_str(&format!("{name} {:.6}\n", latency.as_secs_f64()));
+        }
+        text
+    }
+}
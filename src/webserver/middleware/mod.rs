@@ -24,6 +24,7 @@ use crate::webserver::route::Route;
 ///
 /// Variants are deliberately *not* generic so the rest of the server can
 /// pattern-match over them without type gymnastics.
+#[derive(Clone)]
 pub enum MiddlewareFn {
     /// `fn(&mut HTTPRequest)` – mutate the incoming request.
     HTTPRequest(fn(&mut HTTPRequest)),
@@ -47,13 +48,17 @@ pub enum MiddlewareFn {
 
 /// A middleware rule: domain pattern + route pattern + one of the functions
 /// above.
+#[derive(Clone)]
 pub struct Middleware {
     /// Domain that must match (or `*` for any).
     pub(crate) domain: Domain,
-    /// Route prefix that must match (or `*` for any).
+    /// Route that must match exactly, `prefix/*` for a path and everything
+    /// below it, or `*` for any.
     pub(crate) route: String,
     /// Function(s) to execute.
     pub(crate) f: MiddlewareFn,
+    /// Whether this is the access logger, skipped for routes without access logging.
+    pub(crate) access_log: bool,
 }
 
 impl Middleware {
@@ -100,6 +105,7 @@ impl Middleware {
             domain: domain.unwrap_or_else(|| Domain::new("*")),
             route: route.unwrap_or_else(|| "*".to_string()),
             f: MiddlewareFn::HTTPRequest(f),
+            access_log: false,
         }
     }
 
@@ -146,6 +152,7 @@ impl Middleware {
             domain: domain.unwrap_or_else(|| Domain::new("*")),
             route: route.unwrap_or_else(|| "*".to_string()),
             f: MiddlewareFn::HTTPResponse(f),
+            access_log: false,
         }
     }
 
@@ -202,6 +209,7 @@ impl Middleware {
             domain: domain.unwrap_or_else(|| Domain::new("*")),
             route: route.unwrap_or_else(|| "*".to_string()),
             f: MiddlewareFn::Both(f_req, f_res),
+            access_log: false,
         }
     }
 
@@ -251,12 +259,13 @@ impl Middleware {
             domain: domain.unwrap_or_else(|| Domain::new("*")),
             route: route.unwrap_or_else(|| "*".to_string()),
             f: MiddlewareFn::BothHTTPResponse(f),
+            access_log: false,
         }
     }
 
     /// Like [`new_response_both`](Self::new_response_both) but the current
     /// route table is also provided (useful for dynamic routing or logging).
-    pub fn new_response_both_w_routes(
+    pub(crate) fn new_response_both_w_routes(
         domain: Option<Domain>,
         route: Option<String>,
         f: fn(&mut HTTPRequest, HTTPResponse, &[Route]) -> HTTPResponse,
@@ -265,6 +274,20 @@ impl Middleware {
             domain: domain.unwrap_or_else(|| Domain::new("*")),
             route: route.unwrap_or_else(|| "*".to_string()),
             f: MiddlewareFn::HTTPResponseBothWithRoutes(f),
+            access_log: false,
+        }
+    }
+
+    /// Whether the route pattern covers the request `path` (without query).
+    pub(crate) fn matches_path(&self, path: &str) -> bool {
+        if self.route == "*" {
+            return true;
+        }
+        match self.route.strip_suffix("/*") {
+            Some(prefix) => path
+                .strip_prefix(prefix)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/')),
+            None => self.route == path,
         }
     }
 }
@@ -6,18 +6,19 @@
 //! # Example
 //!
 //! ```rust
-//! use webserver::{WebServer, ServerConfig, Domain};
-//! use webserver::route::HTTPMethod;
-//! use webserver::responses::StatusCode;
+//! use sunweb::webserver::{WebServer, ServerConfig};
+//! use sunweb::webserver::responses::HTTPResponse;
+//! use sunweb::webserver::responses::status_code::StatusCode;
+//! use sunweb::webserver::route::HTTPMethod;
 //!
 //! // Create a basic configuration
-//! let config = ServerConfig::new("127.0.0.1", 8080, "example.com");
+//! let config = ServerConfig::new([127, 0, 0, 1], 8080).set_base_domain("example.com".to_string());
 //! let mut server = WebServer::new(config);
 //!
 //! // Add a custom route
 //! server.add_custom_route("/api", HTTPMethod::GET, |_req, _domain| {
 //!     // Return a simple HTTP response
-//!     webserver::responses::HTTPResponse::new(200, "Hello API".to_string())
+//!     HTTPResponse::with_body(StatusCode::Ok, "Hello API")
 //! }, StatusCode::Ok, None);
 //!
 //! // Add a file route
@@ -28,28 +29,47 @@
 //! ```
 mod client_handling;
 pub(crate) mod files;
+mod http2;
 pub mod http_packet;
-pub(crate) mod logger;
+pub mod logger;
+pub mod metrics;
 pub(crate) mod middleware;
 mod proxy;
 pub mod requests;
 pub mod responses;
 pub mod route;
+mod router;
 pub(crate) mod server_config;
+mod sse;
+pub mod util;
 
-use crate::webserver::client_handling::Client;
-use crate::webserver::files::get_file_content;
-use crate::webserver::middleware::Middleware;
-use crate::webserver::route::{HTTPMethod, Route, RouteType};
-pub use crate::webserver::server_config::ServerConfig;
+use crate::webserver::client_handling::{Client, ErrorReporter, MemoryStream};
+pub use crate::webserver::client_handling::{ClientStream, UpgradedStream};
+pub use crate::webserver::files::StaticOptions;
+use crate::webserver::files::{content_type_for, get_file_content, walk_files};
+use crate::webserver::http_packet::header::content_types::ContentType;
+use crate::webserver::http_packet::header::content_types::application::ApplicationSubType;
+use crate::webserver::http_packet::header::content_types::text::TextSubType;
+use crate::webserver::http_packet::header::headers::cache_control::CacheControl;
+use crate::webserver::metrics::{Metrics, MetricsSnapshot};
+pub use crate::webserver::middleware::Middleware;
+pub use crate::webserver::proxy::CircuitBreaker;
+use crate::webserver::proxy::{ConnectionPool, Proxy};
+use crate::webserver::route::{BlockingPool, HTTPMethod, Route, RouteInfo, RouteType};
+pub use crate::webserver::router::Router;
+pub use crate::webserver::server_config::{ConfigError, ServerConfig, ServerConfigBuilder};
+pub use crate::webserver::sse::{SseBroadcaster, SseEvent};
 
-use crate::webserver::http_packet::header::connection::ConnectionType;
 use crate::webserver::logger::Logger;
 use crate::webserver::requests::HTTPRequest;
 use crate::webserver::responses::{HTTPResponse, StatusCode};
 use log::{error, info};
+use socket2::{Protocol, Socket, Type};
 use std::collections::HashMap;
-use std::net::TcpListener;
+use std::io;
+use std::net::{SocketAddr, TcpListener, ToSocketAddrs};
+#[cfg(unix)]
+use std::os::unix::net::UnixListener;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use std::thread;
@@ -89,6 +109,74 @@ impl Domain {
     pub fn as_str(&self) -> String {
         self.name.clone()
     }
+
+    /// Returns `true` if requests for `host` belong to this domain.
+    ///
+    /// Names are compared case-insensitively. A name starting with `*.`
+    /// matches any subdomain of the rest of the name, but not that name itself.
+    /// Requests whose host has no router of its own are routed to a matching
+    /// wildcard domain before falling back to the default domain.
+    ///
+    /// # Arguments
+    ///
+    /// * `host` - The request host, without a port.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sunweb::webserver::Domain;
+    ///
+    /// let wildcard = Domain::new("*.example.com");
+    /// assert!(wildcard.matches("a.example.com"));
+    /// assert!(wildcard.matches("B.example.com"));
+    /// assert!(wildcard.matches("deep.b.example.com"));
+    /// assert!(!wildcard.matches("example.com"));
+    /// assert!(!wildcard.matches("other.com"));
+    ///
+    /// assert!(Domain::new("api.example.com").matches("API.example.com"));
+    /// ```
+    ///
+    /// Subdomains without a router of their own are served by the wildcard router:
+    ///
+    /// ```rust
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{Domain, ServerConfig, WebServer};
+    ///
+    /// let config = ServerConfig::new([127, 0, 0, 1], 8080).set_base_domain("localhost".to_string());
+    /// let mut server = WebServer::new(config);
+    /// server.add_custom_route("/", HTTPMethod::GET, |_request, _domain| {
+    ///     HTTPResponse::with_body(StatusCode::Ok, "tenant")
+    /// }, StatusCode::Ok, Some(&Domain::new("*.example.com")));
+    /// server.add_custom_route("/", HTTPMethod::GET, |_request, _domain| {
+    ///     HTTPResponse::with_body(StatusCode::Ok, "default")
+    /// }, StatusCode::Ok, None);
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let get = |host: &str| {
+    ///     let raw = format!("GET / HTTP/1.1\r\nHost: {host}\r\n\r\n");
+    ///     server.handle_request(raw.as_bytes(), peer)
+    /// };
+    /// assert!(get("a.example.com").ends_with(b"\r\n\r\ntenant"));
+    /// assert!(get("b.example.com:8080").ends_with(b"\r\n\r\ntenant"));
+    /// assert!(get("other.com").ends_with(b"\r\n\r\ndefault"));
+    /// ```
+    pub fn matches(&self, host: &str) -> bool {
+        match self.name.strip_prefix("*.") {
+            Some(parent) => host
+                .len()
+                .checked_sub(parent.len() + 1)
+                .filter(|&dot| dot > 0 && host.as_bytes()[dot] == b'.')
+                .is_some_and(|dot| host[dot + 1..].eq_ignore_ascii_case(parent)),
+            None => self.name.eq_ignore_ascii_case(host),
+        }
+    }
+
+    /// Returns `true` if the name starts with the `*.` wildcard.
+    pub(crate) fn is_wildcard(&self) -> bool {
+        self.name.starts_with("*.")
+    }
 }
 
 /// The main web server structure.
@@ -103,6 +191,14 @@ pub struct WebServer {
     pub(crate) default_domain: Domain,
     /// List of middleware functions to apply to requests/responses.
     pub(crate) middleware: Arc<Vec<Middleware>>,
+    /// Request metrics shared by all connections.
+    pub(crate) metrics: Arc<Metrics>,
+    /// Idle proxy upstream connections, when keep-alive to upstreams is enabled.
+    pub(crate) proxy_pool: Option<Arc<ConnectionPool>>,
+    /// Threads running blocking route handlers, started with the first such route.
+    pub(crate) blocking_pool: Option<Arc<BlockingPool>>,
+    /// Called with every `5xx` response before it is sent.
+    pub(crate) error_reporter: Option<ErrorReporter>,
 }
 
 impl WebServer {
@@ -129,21 +225,33 @@ impl WebServer {
         domains.insert(default_domain.clone(), Arc::new(Mutex::new(Vec::new())));
         let mut middlewares = Vec::new();
 
-        let logging_start_middleware =
+        let mut logging_start_middleware =
             Middleware::new_request(None, None, Logger::log_request_start);
-        let logging_end_middleware = Middleware::new_response(None, None, Logger::log_request_end);
+        logging_start_middleware.access_log = true;
+        let mut logging_end_middleware =
+            Middleware::new_response(None, None, Logger::log_request_end);
+        logging_end_middleware.access_log = true;
         let error_page_middleware =
             Middleware::new_response_both_w_routes(None, None, Self::error_page);
 
         middlewares.push(logging_start_middleware);
         middlewares.push(logging_end_middleware);
         middlewares.push(error_page_middleware);
+        if config.security_headers {
+            middlewares.push(Middleware::new_response(None, None, Self::security_headers));
+        }
 
         WebServer {
+            proxy_pool: config
+                .proxy_pool_size
+                .map(|max_idle| Arc::new(ConnectionPool::new(max_idle))),
+            blocking_pool: None,
             config,
             domains: Arc::new(Mutex::new(domains)),
             default_domain,
             middleware: Arc::from(middlewares),
+            metrics: Arc::new(Metrics::default()),
+            error_reporter: None,
         }
     }
 
@@ -152,12 +260,54 @@ impl WebServer {
     /// This will bind the server to the configured IP and port, spawn threads to handle
     /// incoming connections, and apply registered middleware to all requests.
     ///
-    /// # Panics
+    /// Problems found by [`validate`](Self::validate) are logged before the
+    /// server starts listening.
     ///
-    /// This function will panic if the server fails to bind to the IP/port.
+    /// If the address cannot be bound, e.g. because the port is in use, the
+    /// error is logged and the function returns. Use
+    /// [`start_blocking`](Self::start_blocking) to handle the error yourself.
     pub fn start(&self) {
+        if let Err(e) = self.start_blocking() {
+            error!("Cannot listen on {}: {e}", self.config.ip_as_string());
+        }
+    }
+
+    /// Starts the web server, returning an error if it cannot listen.
+    ///
+    /// Works like [`start`](Self::start) and blocks while serving
+    /// connections, but returns the error from binding the configured IP and
+    /// port instead of logging it.
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`io::Error`] from binding the listener, such as
+    /// [`AddrInUse`](io::ErrorKind::AddrInUse) or
+    /// [`PermissionDenied`](io::ErrorKind::PermissionDenied).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::io::ErrorKind;
+    /// use std::net::TcpListener;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let taken = TcpListener::bind("127.0.0.1:0").unwrap();
+    /// let port = taken.local_addr().unwrap().port();
+    ///
+    /// let server = WebServer::new(ServerConfig::new([127, 0, 0, 1], port));
+    /// let error = server.start_blocking().unwrap_err();
+    /// assert_eq!(error.kind(), ErrorKind::AddrInUse);
+    ///
+    /// // `start` logs the same error and returns instead of panicking.
+    /// server.start();
+    /// ```
+    pub fn start_blocking(&self) -> io::Result<()> {
+        self.log_problems();
         let bind_addr = self.config.ip_as_string();
-        let listener = TcpListener::bind(&bind_addr).unwrap();
+        let listener = match self.config.listen_backlog {
+            Some(backlog) => bind_with_backlog(&bind_addr, backlog)?,
+            None => TcpListener::bind(&bind_addr)?,
+        };
         if self.config.using_https {
             info!("Server running on https://{bind_addr}/");
         } else {
@@ -168,193 +318,2070 @@ impl WebServer {
         }
         for stream in listener.incoming() {
             match stream {
-                Ok(stream) => {
-                    let domains = Arc::clone(&self.domains);
-                    let middleware = Arc::clone(&self.middleware);
-                    let default_domain = self.default_domain.clone();
-                    let tls_config = self.config.tls_config.clone();
-
-                    thread::spawn(move || {
-                        let mut client =
-                            Client::new(stream, domains, default_domain, middleware, tls_config);
-
-                        let mut i = 0;
-                        loop {
-                            match client.handle(i) {
-                                Some(connection_type) => match connection_type {
-                                    ConnectionType::KeepAlive => {
-                                        i += 1;
-                                        continue;
-                                    }
-                                    _ => {
-                                        error!("Connection closed: {connection_type}");
-                                        break;
-                                    }
-                                },
-                                None => break,
-                            };
+                Ok(stream) => self.spawn_client(stream),
+                Err(e) => eprintln!("Connection failed: {e}"),
+            }
+        }
+        Ok(())
+    }
+
+    /// Starts the web server on a Unix domain socket instead of TCP.
+    ///
+    /// Useful when running behind a reverse proxy such as nginx. Connections are
+    /// handled exactly like TCP connections, including TLS if configured. The
+    /// configured IP and port are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Filesystem path of the socket to create.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the socket cannot be bound (e.g. the path already exists).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::io::{Read, Write};
+    /// use std::os::unix::net::UnixStream;
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let path = std::env::temp_dir().join(format!("sunweb-{}.sock", std::process::id()));
+    /// let _ = std::fs::remove_file(&path);
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_custom_route("/", HTTPMethod::GET, |_request, _domain| {
+    ///     let mut response = HTTPResponse::new(StatusCode::Ok);
+    ///     response.set_body_string("Hello over a socket".to_string());
+    ///     response
+    /// }, StatusCode::Ok, None);
+    ///
+    /// let socket = path.to_str().unwrap().to_string();
+    /// thread::spawn(move || server.start_unix(&socket));
+    /// while !path.exists() {
+    ///     thread::sleep(Duration::from_millis(10));
+    /// }
+    ///
+    /// let mut stream = UnixStream::connect(&path).unwrap();
+    /// stream.write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+    /// let mut response = String::new();
+    /// stream.read_to_string(&mut response).unwrap();
+    ///
+    /// assert!(response.starts_with("HTTP/1.1 200 OK"));
+    /// assert!(response.ends_with("Hello over a socket"));
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    #[cfg(unix)]
+    pub fn start_unix(&self, path: &str) {
+        self.log_problems();
+        let listener = UnixListener::bind(path).unwrap();
+        info!("Server running on unix:{path}");
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => self.spawn_client(stream),
+                Err(e) => eprintln!("Connection failed: {e}"),
+            }
+        }
+    }
+
+    /// Checks the routes and configuration for mistakes that would otherwise only
+    /// show up as failing requests.
+    ///
+    /// Reports static routes whose folder does not exist, file routes that loaded
+    /// no content, proxy routes with an unparseable URL, routes registered twice
+    /// for the same path and method, and inconsistent TLS settings.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())`, or every problem found as a human-readable message.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// assert_eq!(server.validate(), Ok(()));
+    ///
+    /// server.add_static_route("/assets", HTTPMethod::GET, "./no-such-folder", StatusCode::Ok, None);
+    /// server.add_proxy_route("/api", "ftp://example.com", StatusCode::Ok, None);
+    ///
+    /// let problems = server.validate().unwrap_err();
+    /// assert_eq!(problems, vec![
+    ///     "Static route GET /assets on localhost: folder ./no-such-folder does not exist".to_string(),
+    ///     "Proxy route GET /api on localhost: cannot parse URL ftp://example.com".to_string(),
+    /// ]);
+    /// ```
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut problems = self.config.problems();
+
+        let guard = self.domains.lock().unwrap();
+        let mut domains: Vec<_> = guard.iter().collect();
+        domains.sort_by(|(a, _), (b, _)| a.name.cmp(&b.name));
+
+        for (domain, routes) in domains {
+            let routes = routes.lock().unwrap();
+            for (i, route) in routes.iter().enumerate() {
+                let name = format!("{} {} on {}", route.method, route.route, domain.name);
+                match route.route_type {
+                    RouteType::Static => {
+                        if let Some(folder) = &route.folder
+                            && !PathBuf::from(folder).is_dir()
+                        {
+                            problems.push(format!(
+                                "Static route {name}: folder {folder} does not exist"
+                            ));
                         }
+                    }
+                    RouteType::File if route.content.as_ref().is_none_or(|c| c.is_empty()) => {
+                        problems.push(format!(
+                            "File route {name}: no content loaded (file missing or empty)"
+                        ));
+                    }
+                    RouteType::Proxy => {
+                        if let Some(external) = &route.external
+                            && Proxy::new(external.clone()).parse_url().is_none()
+                        {
+                            problems
+                                .push(format!("Proxy route {name}: cannot parse URL {external}"));
+                        }
+                    }
+                    _ => {}
+                }
+
+                let duplicate = route.route_type != RouteType::Error
+                    && routes[..i].iter().any(|earlier| {
+                        earlier.route_type != RouteType::Error
+                            && earlier.route == route.route
+                            && earlier.method == route.method
                     });
+                if duplicate {
+                    problems.push(format!("Route {name} is registered more than once"));
                 }
-                Err(e) => eprintln!("Connection failed: {e}"),
             }
         }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+
+    /// Logs every problem reported by [`validate`](Self::validate).
+    fn log_problems(&self) {
+        if let Err(problems) = self.validate() {
+            for problem in problems {
+                error!("{problem}");
+            }
+        }
+    }
+
+    /// Serves a single, already established connection on the current thread.
+    ///
+    /// This is the transport-agnostic core of [`start`](Self::start): any stream
+    /// implementing [`ClientStream`] can be handed over, e.g. a socket accepted by
+    /// your own listener or an in-memory pipe. Returns once the client closes the
+    /// connection or stops asking for keep-alive.
+    ///
+    /// Requests carrying `Expect: 100-continue` are answered with an interim
+    /// `100 Continue` response before their body is read.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::io::{Cursor, Read, Write};
+    /// use std::sync::{Arc, Mutex};
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ClientStream, ServerConfig, WebServer};
+    ///
+    /// struct Pipe {
+    ///     input: Cursor<Vec<u8>>,
+    ///     output: Arc<Mutex<Vec<u8>>>,
+    /// }
+    ///
+    /// impl Read for Pipe {
+    ///     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+    ///         self.input.read(buf)
+    ///     }
+    /// }
+    ///
+    /// impl Write for Pipe {
+    ///     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    ///         self.output.lock().unwrap().write(buf)
+    ///     }
+    ///     fn flush(&mut self) -> std::io::Result<()> {
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// impl ClientStream for Pipe {}
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_custom_route("/ping", HTTPMethod::GET, |_request, _domain| {
+    ///     let mut response = HTTPResponse::new(StatusCode::Ok);
+    ///     response.set_body_string("pong".to_string());
+    ///     response
+    /// }, StatusCode::Ok, None);
+    ///
+    /// let output = Arc::new(Mutex::new(Vec::new()));
+    /// server.serve_connection(Pipe {
+    ///     input: Cursor::new(b"GET /ping HTTP/1.1\r\nHost: localhost\r\n\r\n".to_vec()),
+    ///     output: Arc::clone(&output),
+    /// });
+    ///
+    /// let response = String::from_utf8(output.lock().unwrap().clone()).unwrap();
+    /// assert!(response.starts_with("HTTP/1.1 200 OK"));
+    /// assert!(response.ends_with("pong"));
+    /// ```
+    ///
+    /// A client that waits for `100 Continue` before sending its body:
+    ///
+    /// ```rust
+    /// use std::io::{Read, Write};
+    /// use std::net::{TcpListener, TcpStream};
+    /// use std::thread;
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 0));
+    /// server.add_custom_route("/upload", HTTPMethod::POST, |request, _domain| {
+    ///     let body = String::from_utf8_lossy(request.body().unwrap_or_default()).into_owned();
+    ///     HTTPResponse::with_body(StatusCode::Ok, body)
+    /// }, StatusCode::Ok, None);
+    ///
+    /// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    /// let addr = listener.local_addr().unwrap();
+    /// thread::spawn(move || {
+    ///     let (stream, _) = listener.accept().unwrap();
+    ///     server.serve_connection(stream);
+    /// });
+    ///
+    /// let mut client = TcpStream::connect(addr).unwrap();
+    /// client.write_all(b"POST /upload HTTP/1.1\r\nHost: localhost\r\nContent-Length: 5\r\n\
+    ///     Expect: 100-continue\r\nConnection: close\r\n\r\n").unwrap();
+    ///
+    /// let mut interim = [0u8; 25];
+    /// client.read_exact(&mut interim).unwrap();
+    /// assert_eq!(&interim, b"HTTP/1.1 100 Continue\r\n\r\n");
+    ///
+    /// client.write_all(b"hello").unwrap();
+    /// let mut response = String::new();
+    /// client.read_to_string(&mut response).unwrap();
+    /// assert!(response.starts_with("HTTP/1.1 200 OK"));
+    /// assert!(response.ends_with("hello"));
+    /// ```
+    ///
+    /// Over TLS the body is read up to its `Content-Length` as well, even when
+    /// it arrives in several records after the headers:
+    ///
+    /// ```rust
+    /// use std::io::{Read, Write};
+    /// use std::net::{TcpListener, TcpStream};
+    /// use std::sync::Arc;
+    /// use std::thread;
+    /// use rustls::pki_types::ServerName;
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    /// let dir = tempfile::tempdir().unwrap();
+    /// let key_path = dir.path().join("key.pem");
+    /// let cert_path = dir.path().join("cert.pem");
+    /// std::fs::write(&key_path, cert.signing_key.serialize_pem()).unwrap();
+    /// std::fs::write(&cert_path, cert.cert.pem()).unwrap();
+    ///
+    /// let config = ServerConfig::new([127, 0, 0, 1], 0)
+    ///     .add_cert(key_path.display().to_string(), cert_path.display().to_string());
+    /// let mut server = WebServer::new(config);
+    /// server.add_custom_route("/upload", HTTPMethod::POST, |request, _domain| {
+    ///     let body = request.body().unwrap_or_default();
+    ///     let all_x = body.iter().all(|&b| b == b'x');
+    ///     HTTPResponse::with_body(StatusCode::Ok, format!("{} {all_x}", body.len()))
+    /// }, StatusCode::Ok, None);
+    ///
+    /// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    /// let addr = listener.local_addr().unwrap();
+    /// thread::spawn(move || {
+    ///     let (stream, _) = listener.accept().unwrap();
+    ///     server.serve_connection(stream);
+    /// });
+    ///
+    /// let mut roots = rustls::RootCertStore::empty();
+    /// roots.add(cert.cert.der().clone()).unwrap();
+    /// let client_config = rustls::ClientConfig::builder()
+    ///     .with_root_certificates(roots)
+    ///     .with_no_client_auth();
+    /// let conn = rustls::ClientConnection::new(
+    ///     Arc::new(client_config),
+    ///     ServerName::try_from("localhost").unwrap(),
+    /// ).unwrap();
+    /// let mut tls = rustls::StreamOwned::new(conn, TcpStream::connect(addr).unwrap());
+    ///
+    /// const SIZE: usize = 100_000;
+    /// write!(tls, "POST /upload HTTP/1.1\r\nHost: localhost\r\nContent-Length: {SIZE}\r\nConnection: close\r\n\r\n").unwrap();
+    /// tls.flush().unwrap();
+    /// tls.write_all(&[b'x'; SIZE / 2]).unwrap();
+    /// tls.flush().unwrap();
+    /// tls.write_all(&[b'x'; SIZE / 2]).unwrap();
+    ///
+    /// let mut response = Vec::new();
+    /// let _ = tls.read_to_end(&mut response);
+    /// let response = String::from_utf8(response).unwrap();
+    /// assert!(response.starts_with("HTTP/1.1 200 OK"));
+    /// assert!(response.ends_with(&format!("\r\n\r\n{SIZE} true")));
+    /// ```
+    ///
+    /// A request whose length is declared by both `Content-Length` and
+    /// `Transfer-Encoding` is rejected and the connection closed, so nothing
+    /// hidden in its body is served as a second request:
+    ///
+    /// ```rust
+    /// use std::io::{Read, Write};
+    /// use std::net::{TcpListener, TcpStream};
+    /// use std::thread;
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 0));
+    /// server.add_custom_route("/admin", HTTPMethod::GET, |_request, _domain| {
+    ///     HTTPResponse::with_body(StatusCode::Ok, "secret")
+    /// }, StatusCode::Ok, None);
+    ///
+    /// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    /// let addr = listener.local_addr().unwrap();
+    /// thread::spawn(move || {
+    ///     let (stream, _) = listener.accept().unwrap();
+    ///     server.serve_connection(stream);
+    /// });
+    ///
+    /// let smuggled = "GET /admin HTTP/1.1\r\nHost: localhost\r\n\r\n";
+    /// let mut client = TcpStream::connect(addr).unwrap();
+    /// write!(
+    ///     client,
+    ///     "POST / HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\
+    ///      Transfer-Encoding: chunked\r\n\r\n0\r\n\r\n{smuggled}",
+    ///     5 + smuggled.len(),
+    /// ).unwrap();
+    ///
+    /// let mut response = String::new();
+    /// client.read_to_string(&mut response).unwrap();
+    /// assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+    /// assert!(response.contains("Connection: close\r\n"));
+    /// assert_eq!(response.matches("HTTP/1.1").count(), 1);
+    /// assert!(!response.contains("secret"));
+    /// ```
+    pub fn serve_connection<S: ClientStream>(&self, stream: S) {
+        self.client(stream).serve();
+    }
+
+    /// Runs a single raw request through the server in-process and returns the
+    /// serialized response.
+    ///
+    /// The request is parsed, passed through the middleware, routed and
+    /// serialized exactly as it would be for a network client, only without a
+    /// socket (and without TLS). Mostly useful for testing routes and middleware.
+    ///
+    /// # Arguments
+    ///
+    /// * `raw` - The complete HTTP request as sent on the wire.
+    /// * `peer` - The address reported as the client's address.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_custom_route("/hello", HTTPMethod::GET, |_request, _domain| {
+    ///     let mut response = HTTPResponse::new(StatusCode::Ok);
+    ///     response.set_body_string("Hello".to_string());
+    ///     response
+    /// }, StatusCode::Ok, None);
+    /// server.add_custom_route("/old", HTTPMethod::GET, |_request, _domain| {
+    ///     HTTPResponse::redirect("/new", true)
+    /// }, StatusCode::Ok, None);
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    ///
+    /// let response = server.handle_request(b"GET /hello HTTP/1.1\r\nHost: localhost\r\n\r\n", peer);
+    /// let response = String::from_utf8(response).unwrap();
+    /// assert!(response.starts_with("HTTP/1.1 200 OK"));
+    /// assert!(response.ends_with("Hello"));
+    ///
+    /// let response = server.handle_request(b"GET /missing HTTP/1.1\r\nHost: localhost\r\n\r\n", peer);
+    /// assert!(response.starts_with(b"HTTP/1.1 404 Not Found"));
+    ///
+    /// let response = server.handle_request(b"GET /old HTTP/1.1\r\nHost: localhost\r\n\r\n", peer);
+    /// let response = String::from_utf8(response).unwrap();
+    /// assert!(response.starts_with("HTTP/1.1 308 Permanent Redirect"));
+    /// assert!(response.contains("Location: /new\r\n"));
+    /// ```
+    ///
+    /// A request whose body is shorter than its `Content-Length` is rejected:
+    ///
+    /// ```rust
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let raw = b"POST /upload HTTP/1.1\r\nHost: localhost\r\nContent-Length: 10\r\n\r\nabc";
+    /// let response = server.handle_request(raw, peer);
+    /// assert!(response.starts_with(b"HTTP/1.1 400 Bad Request"));
+    /// ```
+    ///
+    /// An HTTP/1.1 request without a `Host` header is rejected, while an
+    /// HTTP/1.0 one is served by the default domain:
+    ///
+    /// ```rust
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_custom_route("/", HTTPMethod::GET, |_request, domain| {
+    ///     HTTPResponse::with_body(StatusCode::Ok, domain.as_str())
+    /// }, StatusCode::Ok, None);
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    ///
+    /// let response = server.handle_request(b"GET / HTTP/1.1\r\n\r\n", peer);
+    /// let response = String::from_utf8(response).unwrap();
+    /// assert!(response.starts_with("HTTP/1.1 400 Bad Request"));
+    /// assert!(response.contains("Connection: close\r\n"));
+    ///
+    /// let response = server.handle_request(b"GET / HTTP/1.0\r\n\r\n", peer);
+    /// let response = String::from_utf8(response).unwrap();
+    /// assert!(response.starts_with("HTTP/1.1 200 OK"));
+    /// assert!(response.ends_with("\r\n\r\nlocalhost"));
+    /// ```
+    ///
+    /// Unknown methods and HTTP versions get their own status codes:
+    ///
+    /// ```rust
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    ///
+    /// let response = server.handle_request(b"BREW / HTTP/1.1\r\nHost: localhost\r\n\r\n", peer);
+    /// assert!(response.starts_with(b"HTTP/1.1 501 Not Implemented"));
+    ///
+    /// let response = server.handle_request(b"GET / HTTP/3.0\r\nHost: localhost\r\n\r\n", peer);
+    /// assert!(response.starts_with(b"HTTP/1.1 505 HTTP Version Not Supported"));
+    /// ```
+    pub fn handle_request(&self, raw: &[u8], peer: SocketAddr) -> Vec<u8> {
+        let mut client = self
+            .client(MemoryStream::new(raw, Some(peer)))
+            .without_tls();
+        client.handle(0);
+        client.into_stream().output
+    }
+
+    /// Spawns a thread serving a single accepted connection.
+    fn spawn_client<S: ClientStream + 'static>(&self, stream: S) {
+        let mut client = self.client(stream);
+        thread::spawn(move || client.serve());
+    }
+
+    /// Builds a `Client` for the given stream sharing this server's routes and middleware.
+    fn client<S: ClientStream>(&self, stream: S) -> Client<S> {
+        let mut client = Client::new(
+            stream,
+            Arc::clone(&self.domains),
+            self.default_domain.clone(),
+            Arc::clone(&self.middleware),
+            Arc::clone(&self.metrics),
+            self.config.tls_config.clone(),
+            self.config
+                .security_headers
+                .then(|| self.config.hsts.clone()),
+        );
+        client.max_requests = Some(self.config.max_keepalive_requests);
+        client.read_timeout = self.config.read_timeout;
+        client.write_timeout = self.config.write_timeout;
+        client.proxy_pool = self.proxy_pool.clone();
+        client.handler_timeout = self.config.handler_timeout;
+        client.strict_parsing = self.config.strict_parsing;
+        client.max_request_line = self.config.max_request_line;
+        client.max_header_size = self.config.max_header_size;
+        client.max_body_size = self.config.max_body_size;
+        client.tcp_nodelay = self.config.tcp_nodelay;
+        client.continue_hook = self.config.continue_hook.clone();
+        client.error_reporter = self.error_reporter.clone();
+        client
+    }
+
+    /// Sets a callback invoked with every `5xx` response before it is sent.
+    ///
+    /// The reporter receives the request as it arrived (before request
+    /// middleware) and the final response, e.g. to forward failures to an
+    /// error tracker. It covers handler panics, handler timeouts, proxy errors
+    /// and `5xx` responses returned by handlers. For a panicking handler the
+    /// panic message is available through
+    /// [`HTTPResponse::panic_message`](responses::HTTPResponse::panic_message).
+    ///
+    /// # Arguments
+    ///
+    /// * `reporter` - Called with the request and the response.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::sync::{Arc, Mutex};
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let reports = Arc::new(Mutex::new(Vec::new()));
+    /// let sink = Arc::clone(&reports);
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.set_error_reporter(move |request, response| {
+    ///     sink.lock().unwrap().push((
+    ///         request.path.clone(),
+    ///         response.status_code,
+    ///         response.panic_message().map(str::to_string),
+    ///     ));
+    /// });
+    /// server.add_custom_route("/crash", HTTPMethod::GET, |_request, _domain| {
+    ///     panic!("database is gone")
+    /// }, StatusCode::Ok, None);
+    /// server.add_custom_route("/fine", HTTPMethod::GET, |_request, _domain| {
+    ///     HTTPResponse::ok()
+    /// }, StatusCode::Ok, None);
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let response = server.handle_request(b"GET /crash HTTP/1.1\r\nHost: localhost\r\n\r\n", peer);
+    /// assert!(response.starts_with(b"HTTP/1.1 500 Internal Server Error"));
+    /// server.handle_request(b"GET /fine HTTP/1.1\r\nHost: localhost\r\n\r\n", peer);
+    ///
+    /// assert_eq!(
+    ///     *reports.lock().unwrap(),
+    ///     [("/crash".to_string(), StatusCode::InternalServerError, Some("database is gone".to_string()))],
+    /// );
+    /// ```
+    pub fn set_error_reporter(
+        &mut self,
+        reporter: impl Fn(&HTTPRequest, &HTTPResponse) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.error_reporter = Some(Arc::new(reporter));
+        self
+    }
+
+    /// Adds a subdomain router for the specified domain.
+    ///
+    /// The router is stored as the lowercase `"{subdomain}.{base_domain}"`. Incoming
+    /// `Host` headers are normalized the same way (lowercased, port stripped), so
+    /// `Host: API.localhost:8443` is served by the `api.localhost` router.
+    ///
+    /// # Arguments
+    ///
+    /// * `domain` - A reference to the `Domain` to register.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{Domain, ServerConfig, WebServer};
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_subdomain_router(&Domain::new("api"));
+    /// server.add_custom_route("/", HTTPMethod::GET, |_request, _domain| {
+    ///     HTTPResponse::with_body(StatusCode::Ok, "api")
+    /// }, StatusCode::Ok, Some(&Domain::new("api.localhost")));
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let response = server.handle_request(b"GET / HTTP/1.1\r\nHost: api.localhost:8443\r\n\r\n", peer);
+    /// assert!(response.ends_with(b"\r\n\r\napi"));
+    /// ```
+    pub fn add_subdomain_router(&mut self, domain: &Domain) {
+        let mut guard = self.domains.lock().unwrap();
+        let domain_str = format!("{}.{}", domain.name, self.default_domain.name).to_lowercase();
+        guard
+            .entry(Domain::new(&*domain_str))
+            .or_insert_with(|| Arc::new(Mutex::new(Vec::new())));
+    }
+
+    /// Adds a file-based route to the server.
+    ///
+    /// The `Content-Type` is guessed from the file extension; files with an
+    /// unknown extension are sent as `text/html`.
+    ///
+    /// # Arguments
+    ///
+    /// * `route` - The URL path to match (e.g., "/about").
+    /// * `method` - HTTP method for the route.
+    /// * `file_path` - Local path to the file to serve.
+    /// * `response_codes` - Status code to respond with.
+    /// * `domain` - Optional `Domain`; defaults to the default domain.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let dir = tempfile::tempdir().unwrap();
+    /// let data = dir.path().join("data.json");
+    /// std::fs::write(&data, r#"{"ok":true}"#).unwrap();
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_route_file("/data", HTTPMethod::GET, data.to_str().unwrap(), StatusCode::Ok, None);
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let response = server.handle_request(b"GET /data HTTP/1.1\r\nHost: localhost\r\n\r\n", peer);
+    /// let response = String::from_utf8(response).unwrap();
+    /// assert!(response.contains("Content-Type: application/json; charset=utf-8\r\n"));
+    /// assert!(response.ends_with(r#"{"ok":true}"#));
+    /// ```
+    pub fn add_route_file(
+        &mut self,
+        route: &str,
+        method: HTTPMethod,
+        file_path: &str,
+        response_codes: StatusCode,
+        domain: Option<&Domain>,
+    ) -> &mut Self {
+        self.add_route_file_with_cache_control(
+            route,
+            method,
+            file_path,
+            response_codes,
+            None,
+            domain,
+        )
+    }
+
+    /// Adds a file-based route that sends a `Cache-Control` header.
+    ///
+    /// Works like [`add_route_file`](Self::add_route_file).
+    ///
+    /// # Arguments
+    ///
+    /// * `route` - The URL path to match (e.g., "/about").
+    /// * `method` - HTTP method for the route.
+    /// * `file_path` - Local path to the file to serve.
+    /// * `response_codes` - Status code to respond with.
+    /// * `cache_control` - Optional `Cache-Control` directive sent with the file; `None` sends no header.
+    /// * `domain` - Optional `Domain`; defaults to the default domain.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sunweb::webserver::http_packet::header::headers::cache_control::CacheControl;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let dir = tempfile::tempdir().unwrap();
+    /// let logo = dir.path().join("logo.svg");
+    /// std::fs::write(&logo, "<svg/>").unwrap();
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// let cache = CacheControl::Multiple(vec![CacheControl::MaxAge(86400), CacheControl::Public]);
+    /// server.add_route_file_with_cache_control(
+    ///     "/logo.svg", HTTPMethod::GET, logo.to_str().unwrap(), StatusCode::Ok, Some(cache), None,
+    /// );
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let response = server.handle_request(b"GET /logo.svg HTTP/1.1\r\nHost: localhost\r\n\r\n", peer);
+    /// assert!(String::from_utf8_lossy(&response).contains("Cache-Control: max-age=86400, public\r\n"));
+    /// ```
+    pub fn add_route_file_with_cache_control(
+        &mut self,
+        route: &str,
+        method: HTTPMethod,
+        file_path: &str,
+        response_codes: StatusCode,
+        cache_control: Option<CacheControl>,
+        domain: Option<&Domain>,
+    ) -> &mut Self {
+        let domain = domain
+            .cloned()
+            .unwrap_or_else(|| self.default_domain.clone());
+
+        let path = PathBuf::from(file_path);
+        let content = get_file_content(&path);
+
+        {
+            let mut guard = self.domains.lock().unwrap();
+            let domain_routes = guard
+                .entry(domain.clone())
+                .or_insert_with(|| Arc::new(Mutex::new(Vec::new())));
+
+            let mut routes = domain_routes.lock().unwrap();
+            routes.push(Route::new_file(
+                route.to_string(),
+                method,
+                response_codes,
+                domain,
+                content,
+                content_type_for(&path),
+                cache_control,
+            ));
+        }
+
+        self
+    }
+
+    /// Adds a `GET` route serving a file with `200 OK`.
+    ///
+    /// Shorthand for [`add_route_file`](Self::add_route_file) without a
+    /// `Cache-Control` header; the `Content-Type` is guessed from the file
+    /// extension.
+    ///
+    /// # Arguments
+    ///
+    /// * `route` - The URL path to match (e.g., "/about").
+    /// * `file_path` - Local path to the file to serve.
+    /// * `domain` - Optional `Domain`; defaults to the default domain.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let dir = tempfile::tempdir().unwrap();
+    /// let path = |name: &str| dir.path().join(name).display().to_string();
+    /// std::fs::write(path("about.html"), "<h1>About</h1>").unwrap();
+    /// std::fs::write(path("site.css"), "h1 { color: red; }").unwrap();
+    /// std::fs::write(path("app.js"), "console.log(1);").unwrap();
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server
+    ///     .add_page("/about", &path("about.html"), None)
+    ///     .add_page("/site.css", &path("site.css"), None)
+    ///     .add_page("/app.js", &path("app.js"), None);
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let get = |path: &str| {
+    ///     let raw = format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n");
+    ///     String::from_utf8(server.handle_request(raw.as_bytes(), peer)).unwrap()
+    /// };
+    ///
+    /// let page = get("/about");
+    /// assert!(page.starts_with("HTTP/1.1 200 OK"));
+    /// assert!(page.contains("Content-Type: text/html; charset=utf-8\r\n"));
+    /// assert!(page.ends_with("\r\n\r\n<h1>About</h1>"));
+    ///
+    /// assert!(get("/site.css").contains("Content-Type: text/css; charset=utf-8\r\n"));
+    /// assert!(get("/app.js").contains("Content-Type: application/javascript"));
+    /// ```
+    pub fn add_page(&mut self, route: &str, file_path: &str, domain: Option<&Domain>) -> &mut Self {
+        self.add_route_file(route, HTTPMethod::GET, file_path, StatusCode::Ok, domain)
+    }
+
+    /// Adds a route serving a fixed in-memory buffer.
+    ///
+    /// Useful for generated or embedded assets (e.g. via `include_bytes!`) that
+    /// don't live on disk. The route answers `GET` requests with `200 OK`.
+    ///
+    /// # Arguments
+    ///
+    /// * `route` - The URL path to match (e.g., "/logo.png").
+    /// * `bytes` - The content to serve.
+    /// * `content_type` - The `Content-Type` to send with the content.
+    /// * `domain` - Optional `Domain`; defaults to the default domain.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sunweb::webserver::http_packet::header::content_types::ContentType;
+    /// use sunweb::webserver::http_packet::header::content_types::image::ImageSubType;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let png = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_bytes_route("/logo.png", png.clone(), ContentType::Image(ImageSubType::Png), None);
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let response = server.handle_request(b"GET /logo.png HTTP/1.1\r\nHost: localhost\r\n\r\n", peer);
+    /// let head = String::from_utf8_lossy(&response);
+    /// assert!(head.contains("Content-Type: image/png\r\n"));
+    /// assert!(head.contains("Content-Length: 8\r\n"));
+    /// assert!(response.ends_with(&png));
+    /// ```
+    pub fn add_bytes_route(
+        &mut self,
+        route: &str,
+        bytes: Vec<u8>,
+        content_type: ContentType,
+        domain: Option<&Domain>,
+    ) -> &mut Self {
+        let domain = domain
+            .cloned()
+            .unwrap_or_else(|| self.default_domain.clone());
+
+        {
+            let mut guard = self.domains.lock().unwrap();
+            let domain_routes = guard
+                .entry(domain.clone())
+                .or_insert_with(|| Arc::new(Mutex::new(Vec::new())));
+
+            let mut routes = domain_routes.lock().unwrap();
+            routes.push(Route::new_bytes(
+                route.to_string(),
+                HTTPMethod::GET,
+                StatusCode::Ok,
+                domain,
+                Arc::new(bytes),
+                content_type,
+            ));
+        }
+        self
+    }
+
+    /// Registers a route for every file in a folder, read once at startup.
+    ///
+    /// Unlike [`add_static_route`](Self::add_static_route), which looks files
+    /// up on each request, the folder is walked now, including subdirectories,
+    /// and each file becomes its own `GET` route below `mount`. The files are
+    /// held in memory and served like [`add_bytes_route`](Self::add_bytes_route),
+    /// with a `Content-Type` guessed from the extension. Files added to the
+    /// folder later are not served.
+    ///
+    /// # Arguments
+    ///
+    /// * `mount` - The URL path the folder is served under (e.g., "/assets").
+    /// * `folder` - Local folder to register.
+    /// * `domain` - Optional `Domain`; defaults to the default domain.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the folder or one of its files cannot be read, in
+    /// which case no route is added.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sunweb::webserver::route::RouteType;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let site = tempfile::tempdir().unwrap();
+    /// std::fs::write(site.path().join("index.html"), "<h1>Home</h1>").unwrap();
+    /// std::fs::create_dir(site.path().join("css")).unwrap();
+    /// std::fs::write(site.path().join("css/site.css"), "body {}").unwrap();
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_route_dir("/app", site.path().to_str().unwrap(), None).unwrap();
+    ///
+    /// let routes: Vec<_> = server.routes(None).into_iter().map(|r| (r.route, r.route_type)).collect();
+    /// assert_eq!(routes, [
+    ///     ("/app/css/site.css".to_string(), RouteType::Bytes),
+    ///     ("/app/index.html".to_string(), RouteType::Bytes),
+    /// ]);
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let response = server.handle_request(b"GET /app/css/site.css HTTP/1.1\r\nHost: localhost\r\n\r\n", peer);
+    /// let response = String::from_utf8(response).unwrap();
+    /// assert!(response.contains("Content-Type: text/css"));
+    /// assert!(response.ends_with("body {}"));
+    ///
+    /// assert!(server.add_route_dir("/missing", "/no/such/folder", None).is_err());
+    /// ```
+    pub fn add_route_dir(
+        &mut self,
+        mount: &str,
+        folder: &str,
+        domain: Option<&Domain>,
+    ) -> std::io::Result<&mut Self> {
+        let folder = PathBuf::from(folder);
+        let mut files = Vec::new();
+        for relative in walk_files(&folder)? {
+            let route = relative
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .fold(mount.trim_end_matches('/').to_string(), |route, segment| {
+                    format!("{route}/{segment}")
+                });
+            let path = folder.join(&relative);
+            let content_type = StaticOptions::new().content_type_for(&path);
+            files.push((route, std::fs::read(&path)?, content_type));
+        }
+
+        for (route, bytes, content_type) in files {
+            self.add_bytes_route(&route, bytes, content_type, domain);
+        }
+        Ok(self)
+    }
+
+    /// Adds a health-check route for load balancers and orchestrators.
+    ///
+    /// The route answers `GET` requests with `200 OK` and the JSON body
+    /// `{"status":"ok"}`. Use [`add_health_route_with_body`](Self::add_health_route_with_body)
+    /// to send a different body.
+    ///
+    /// The route is registered on every domain known so far, so add it after
+    /// the other domains' routes; hosts without routes of their own fall back
+    /// to the default domain and get it too. Health checks are not written to
+    /// the access log.
+    ///
+    /// # Arguments
+    ///
+    /// * `route` - The URL path to match (e.g., "/healthz").
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{Domain, ServerConfig, WebServer};
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// let api = Domain::new("api.example.com");
+    /// server.add_custom_route("/", HTTPMethod::GET, |_request, _domain| {
+    ///     HTTPResponse::with_body(StatusCode::Ok, "api")
+    /// }, StatusCode::Ok, Some(&api));
+    /// server.add_health_route("/healthz");
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// for host in ["localhost", "api.example.com", "unknown.example"] {
+    ///     let raw = format!("GET /healthz HTTP/1.1\r\nHost: {host}\r\n\r\n");
+    ///     let response = server.handle_request(raw.as_bytes(), peer);
+    ///     let response = String::from_utf8_lossy(&response);
+    ///     assert!(response.starts_with("HTTP/1.1 200 OK"), "{host}");
+    ///     assert!(response.contains("Content-Type: application/json; charset=utf-8\r\n"));
+    ///     assert!(response.ends_with(r#"{"status":"ok"}"#));
+    /// }
+    /// ```
+    pub fn add_health_route(&mut self, route: &str) -> &mut Self {
+        self.add_health_route_with_body(route, r#"{"status":"ok"}"#)
+    }
+
+    /// Adds a health-check route answering `GET` with `200 OK` and a custom JSON body.
+    ///
+    /// # Arguments
+    ///
+    /// * `route` - The URL path to match (e.g., "/health").
+    /// * `body` - The JSON body to respond with.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_health_route_with_body("/health", r#"{"status":"ok","version":"1.0"}"#);
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let response = server.handle_request(b"GET /health HTTP/1.1\r\nHost: localhost\r\n\r\n", peer);
+    /// assert!(response.ends_with(br#"{"status":"ok","version":"1.0"}"#));
+    /// ```
+    pub fn add_health_route_with_body(&mut self, route: &str, body: &str) -> &mut Self {
+        let body = Arc::new(body.as_bytes().to_vec());
+        let guard = self.domains.lock().unwrap();
+        for (domain, domain_routes) in guard.iter() {
+            let mut health = Route::new_bytes(
+                route.to_string(),
+                HTTPMethod::GET,
+                StatusCode::Ok,
+                domain.clone(),
+                Arc::clone(&body),
+                ContentType::Application(ApplicationSubType::Json),
+            );
+            health.access_log = false;
+            domain_routes.lock().unwrap().push(health);
+        }
+        drop(guard);
+        self
+    }
+
+    /// Returns the request metrics collected since the server was created.
+    ///
+    /// Counts every request handled on any connection, including requests run
+    /// through [`handle_request`](Self::handle_request).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_health_route("/healthz");
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// for _ in 0..3 {
+    ///     server.handle_request(b"GET /healthz HTTP/1.1\r\nHost: localhost\r\n\r\n", peer);
+    /// }
+    /// server.handle_request(b"GET /missing HTTP/1.1\r\nHost: localhost\r\n\r\n", peer);
+    ///
+    /// let metrics = server.metrics_snapshot();
+    /// assert_eq!(metrics.total_requests, 4);
+    /// assert_eq!(metrics.success, 3);
+    /// assert_eq!(metrics.client_error, 1);
+    /// ```
+    pub fn metrics_snapshot(&self) -> MetricsSnapshot {
+        self.metrics.snapshot()
+    }
+
+    /// Adds a route exposing the server's request metrics in plain text.
+    ///
+    /// The route answers `GET` requests with the output of
+    /// [`MetricsSnapshot::to_text`].
+    ///
+    /// # Arguments
+    ///
+    /// * `route` - The URL path to match (e.g., "/metrics").
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_metrics_route("/metrics");
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// server.handle_request(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n", peer);
+    /// let response = server.handle_request(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n", peer);
+    /// let response = String::from_utf8_lossy(&response);
+    /// assert!(response.contains("Content-Type: text/plain"));
+    /// assert!(response.contains("requests_total 1\n"));
+    /// ```
+    pub fn add_metrics_route(&mut self, route: &str) -> &mut Self {
+        let metrics = Arc::clone(&self.metrics);
+        self.add_custom_route(
+            route,
+            HTTPMethod::GET,
+            move |_request, _domain| {
+                let mut response =
+                    HTTPResponse::with_body(StatusCode::Ok, metrics.snapshot().to_text());
+                response.set_content_type(ContentType::Text(TextSubType::Plain));
+                response
+            },
+            StatusCode::Ok,
+            None,
+        )
+    }
+
+    /// Adds a static folder route (serving all files in a directory).
+    ///
+    /// # Arguments
+    ///
+    /// * `route` - The URL path to match (e.g., "/static").
+    /// * `method` - HTTP method.
+    /// * `folder` - Local folder path containing static files.
+    /// * `response_codes` - Status code for successful responses.
+    /// * `domain` - Optional `Domain`; defaults to the default domain.
+    ///
+    /// # Example
+    ///
+    /// A `Cache-Control` header is sent when it is configured with
+    /// [`add_static_route_with_options`](Self::add_static_route_with_options):
+    ///
+    /// ```rust
+    /// use sunweb::webserver::http_packet::header::headers::cache_control::CacheControl;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::{ServerConfig, StaticOptions, WebServer};
+    ///
+    /// let assets = tempfile::tempdir().unwrap();
+    /// std::fs::write(assets.path().join("app.css"), "body {}").unwrap();
+    /// let folder = assets.path().to_str().unwrap();
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// let cache = CacheControl::Multiple(vec![CacheControl::MaxAge(86400), CacheControl::Public]);
+    /// let options = StaticOptions::new().cache_control(cache);
+    /// server.add_static_route_with_options("/assets", HTTPMethod::GET, folder, StatusCode::Ok, options, None);
+    /// server.add_static_route("/raw", HTTPMethod::GET, folder, StatusCode::Ok, None);
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let cached = server.handle_request(b"GET /assets/app.css HTTP/1.1\r\nHost: localhost\r\n\r\n", peer);
+    /// assert!(String::from_utf8_lossy(&cached).contains("Cache-Control: max-age=86400, public\r\n"));
+    ///
+    /// let uncached = server.handle_request(b"GET /raw/app.css HTTP/1.1\r\nHost: localhost\r\n\r\n", peer);
+    /// assert!(!String::from_utf8_lossy(&uncached).contains("Cache-Control"));
+    /// ```
+    ///
+    /// Files are served with `Accept-Ranges: bytes`. A `Range` header with one range
+    /// gets a `206 Partial Content` response with that slice; several ranges get a
+    /// `multipart/byteranges` body with one part per range, streamed from the
+    /// file. Ranges that overlap or touch are merged first. Requests for more
+    /// than 16 ranges, or for more bytes than the file has, are answered with
+    /// the whole file:
+    ///
+    /// ```rust
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let assets = tempfile::tempdir().unwrap();
+    /// std::fs::write(assets.path().join("data.txt"), "0123456789abcdefghij").unwrap();
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_static_route("/files", HTTPMethod::GET, assets.path().to_str().unwrap(), StatusCode::Ok, None);
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let raw = b"GET /files/data.txt HTTP/1.1\r\nHost: localhost\r\nRange: bytes=0-3,10-12\r\n\r\n";
+    /// let response = String::from_utf8(server.handle_request(raw, peer)).unwrap();
+    /// let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    /// assert!(head.starts_with("HTTP/1.1 206 Partial Content"));
+    ///
+    /// let boundary = head
+    ///     .lines()
+    ///     .find_map(|l| l.strip_prefix("Content-Type: multipart/byteranges; boundary="))
+    ///     .unwrap();
+    /// let parts: Vec<(&str, &str)> = body
+    ///     .split(&format!("--{boundary}"))
+    ///     .filter(|part| part.starts_with("\r\n"))
+    ///     .map(|part| part.trim_end_matches("\r\n").split_once("\r\n\r\n").unwrap())
+    ///     .collect();
+    /// assert_eq!(parts.len(), 2);
+    /// assert!(parts[0].0.contains("Content-Range: bytes 0-3/20"));
+    /// assert_eq!(parts[0].1, "0123");
+    /// assert!(parts[1].0.contains("Content-Range: bytes 10-12/20"));
+    /// assert_eq!(parts[1].1, "abc");
+    /// assert!(body.ends_with(&format!("--{boundary}--\r\n")));
+    ///
+    /// // Overlapping and adjacent ranges are merged into one.
+    /// let raw = b"GET /files/data.txt HTTP/1.1\r\nHost: localhost\r\nRange: bytes=4-5,0-3,2-4\r\n\r\n";
+    /// let merged = String::from_utf8(server.handle_request(raw, peer)).unwrap();
+    /// assert!(merged.contains("Content-Range: bytes 0-5/20\r\n"));
+    /// assert!(merged.ends_with("\r\n\r\n012345"));
+    ///
+    /// // Repeated ranges, or more than 16 of them, get the whole file instead.
+    /// let spec = vec!["0-"; 1000].join(",");
+    /// let raw = format!("GET /files/data.txt HTTP/1.1\r\nHost: localhost\r\nRange: bytes={spec}\r\n\r\n");
+    /// let full = String::from_utf8(server.handle_request(raw.as_bytes(), peer)).unwrap();
+    /// assert!(full.starts_with("HTTP/1.1 200 OK"));
+    /// assert!(full.ends_with("\r\n\r\n0123456789abcdefghij"));
+    ///
+    /// let spec = (0..17).map(|i| format!("{i}-{i}")).collect::<Vec<_>>().join(",");
+    /// let raw = format!("GET /files/data.txt HTTP/1.1\r\nHost: localhost\r\nRange: bytes={spec}\r\n\r\n");
+    /// let full = String::from_utf8(server.handle_request(raw.as_bytes(), peer)).unwrap();
+    /// assert!(full.starts_with("HTTP/1.1 200 OK"));
+    /// ```
+    ///
+    /// Files carry an `ETag` and `Last-Modified` header. A `Range` request with
+    /// `If-Range` gets the range only while the given validator still matches,
+    /// and the whole file once it changed or if the validator cannot be parsed:
+    ///
+    /// ```rust
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let assets = tempfile::tempdir().unwrap();
+    /// std::fs::write(assets.path().join("data.txt"), "0123456789").unwrap();
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_static_route("/files", HTTPMethod::GET, assets.path().to_str().unwrap(), StatusCode::Ok, None);
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let get = |extra: &str| {
+    ///     let raw = format!("GET /files/data.txt HTTP/1.1\r\nHost: localhost\r\n{extra}\r\n");
+    ///     String::from_utf8(server.handle_request(raw.as_bytes(), peer)).unwrap()
+    /// };
+    /// let header = |response: &str, name: &str| {
+    ///     response.lines().find_map(|l| l.strip_prefix(name)).unwrap().to_string()
+    /// };
+    ///
+    /// let full = get("");
+    /// let etag = header(&full, "ETag: ");
+    /// let last_modified = header(&full, "Last-Modified: ");
+    ///
+    /// let resumed = get(&format!("Range: bytes=4-\r\nIf-Range: {etag}\r\n"));
+    /// assert!(resumed.starts_with("HTTP/1.1 206 Partial Content"));
+    /// assert!(resumed.ends_with("\r\n\r\n456789"));
+    ///
+    /// let by_date = get(&format!("Range: bytes=4-\r\nIf-Range: {last_modified}\r\n"));
+    /// assert!(by_date.ends_with("\r\n\r\n456789"));
+    ///
+    /// let changed = get("Range: bytes=4-\r\nIf-Range: \"outdated\"\r\n");
+    /// assert!(changed.starts_with("HTTP/1.1 200 OK"));
+    /// assert!(changed.ends_with("\r\n\r\n0123456789"));
+    ///
+    /// let weak = get(&format!("Range: bytes=4-\r\nIf-Range: W/{etag}\r\n"));
+    /// assert!(weak.ends_with("\r\n\r\n0123456789"));
+    ///
+    /// let older = get("Range: bytes=4-\r\nIf-Range: Sun, 06 Nov 1994 08:49:37 GMT\r\n");
+    /// assert!(older.ends_with("\r\n\r\n0123456789"));
+    ///
+    /// let asctime = get("Range: bytes=4-\r\nIf-Range: Sun Nov  6 08:49:37 1994\r\n");
+    /// assert!(asctime.starts_with("HTTP/1.1 200 OK"));
+    /// assert!(asctime.ends_with("\r\n\r\n0123456789"));
+    ///
+    /// let list = get(&format!("Range: bytes=4-\r\nIf-Range: {etag}, \"other\"\r\n"));
+    /// assert!(list.ends_with("\r\n\r\n0123456789"));
+    /// ```
+    ///
+    /// Files larger than 1 MiB are streamed from disk as they are sent rather
+    /// than read into memory first; `Content-Length` comes from the file size.
+    /// Here the end of the file is changed after the response has started and
+    /// the client still receives the new bytes:
+    ///
+    /// ```rust
+    /// use std::fs::OpenOptions;
+    /// use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+    /// use std::net::{TcpListener, TcpStream};
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// const SIZE: usize = 16 * 1024 * 1024;
+    /// let assets = tempfile::tempdir().unwrap();
+    /// let path = assets.path().join("video.bin");
+    /// std::fs::write(&path, vec![b'x'; SIZE]).unwrap();
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_static_route("/media", HTTPMethod::GET, assets.path().to_str().unwrap(), StatusCode::Ok, None);
+    ///
+    /// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    /// let addr = listener.local_addr().unwrap();
+    /// std::thread::spawn(move || {
+    ///     let (stream, _) = listener.accept().unwrap();
+    ///     server.serve_connection(stream);
+    /// });
+    ///
+    /// let mut client = TcpStream::connect(addr).unwrap();
+    /// client
+    ///     .write_all(b"GET /media/video.bin HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+    ///     .unwrap();
+    /// let mut reader = BufReader::new(client);
+    /// let mut head = String::new();
+    /// while !head.ends_with("\r\n\r\n") {
+    ///     reader.read_line(&mut head).unwrap();
+    /// }
+    /// assert!(head.starts_with("HTTP/1.1 200 OK\r\n"));
+    /// assert!(head.contains(&format!("Content-Length: {SIZE}\r\n")));
+    ///
+    /// let mut file = OpenOptions::new().write(true).open(&path).unwrap();
+    /// file.seek(SeekFrom::End(-4)).unwrap();
+    /// file.write_all(b"tail").unwrap();
+    ///
+    /// let mut body = Vec::new();
+    /// reader.read_to_end(&mut body).unwrap();
+    /// assert_eq!(body.len(), SIZE);
+    /// assert!(body.ends_with(b"xxtail"));
+    /// ```
+    pub fn add_static_route(
+        &mut self,
+        route: &str,
+        method: HTTPMethod,
+        folder: &str,
+        response_codes: StatusCode,
+        domain: Option<&Domain>,
+    ) -> &mut Self {
+        let options = StaticOptions::default();
+        self.add_static_route_with_options(route, method, folder, response_codes, options, domain)
+    }
+
+    /// Adds a static folder route configured with [`StaticOptions`].
+    ///
+    /// [`add_static_route`](Self::add_static_route) is this method with default
+    /// options.
+    ///
+    /// # Arguments
+    ///
+    /// * `route` - The URL path to match (e.g., "/static").
+    /// * `method` - HTTP method.
+    /// * `folder` - Local folder path containing static files.
+    /// * `response_codes` - Status code for successful responses.
+    /// * `options` - Index file, directory listing, caching and hidden-file behavior.
+    /// * `domain` - Optional `Domain`; defaults to the default domain.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sunweb::webserver::http_packet::header::headers::cache_control::CacheControl;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::{ServerConfig, StaticOptions, WebServer};
+    ///
+    /// let site = tempfile::tempdir().unwrap();
+    /// std::fs::write(site.path().join("index.html"), "<h1>Home</h1>").unwrap();
+    /// std::fs::write(site.path().join(".env"), "SECRET=1").unwrap();
+    /// std::fs::create_dir(site.path().join("docs")).unwrap();
+    /// std::fs::write(site.path().join("docs/guide.txt"), "read me").unwrap();
+    ///
+    /// let options = StaticOptions::new()
+    ///     .index_file("index.html")
+    ///     .directory_listing(true)
+    ///     .cache_control(CacheControl::MaxAge(60))
+    ///     .deny_hidden_files();
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_static_route_with_options(
+    ///     "/site", HTTPMethod::GET, site.path().to_str().unwrap(), StatusCode::Ok, options, None,
+    /// );
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let get = |path: &str| {
+    ///     let raw = format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n");
+    ///     String::from_utf8(server.handle_request(raw.as_bytes(), peer)).unwrap()
+    /// };
+    ///
+    /// // The folder itself is served by its index file.
+    /// let index = get("/site/");
+    /// assert!(index.contains("Cache-Control: max-age=60\r\n"));
+    /// assert!(index.ends_with("<h1>Home</h1>"));
+    ///
+    /// // A directory without an index file is listed.
+    /// let listing = get("/site/docs");
+    /// assert!(listing.starts_with("HTTP/1.1 200 OK"));
+    /// assert!(listing.contains("<a href=\"/site/docs/guide.txt\">guide.txt</a>"));
+    ///
+    /// // Hidden files and paths leaving the folder are not served.
+    /// assert!(get("/site/.env").starts_with("HTTP/1.1 404 Not Found"));
+    /// assert!(get("/site/../etc/passwd").starts_with("HTTP/1.1 404 Not Found"));
+    /// ```
+    pub fn add_static_route_with_options(
+        &mut self,
+        route: &str,
+        method: HTTPMethod,
+        folder: &str,
+        response_codes: StatusCode,
+        options: StaticOptions,
+        domain: Option<&Domain>,
+    ) -> &mut Self {
+        let domain = domain
+            .cloned()
+            .unwrap_or_else(|| self.default_domain.clone());
+
+        let folder_path = PathBuf::from(folder);
+        if !folder_path.exists() {
+            error!("Static route file does not exist");
+        }
+
+        {
+            let mut guard = self.domains.lock().unwrap();
+            let domain_routes = guard
+                .entry(domain.clone())
+                .or_insert_with(|| Arc::new(Mutex::new(Vec::new())));
+
+            let mut routes = domain_routes.lock().unwrap();
+            routes.push(Route::new_static(
+                route.to_string(),
+                method,
+                response_codes,
+                domain,
+                String::from(folder),
+                options,
+            ));
+        }
+        self
+    }
+
+    /// Adds a custom route with a handler function.
+    ///
+    /// The handler receives the request and the [`Domain`] it was addressed to,
+    /// taken from the `Host` header (lowercased, port stripped). Requests without
+    /// a `Host` header get the domain the route was registered on.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sunweb::webserver::{WebServer, ServerConfig};
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    ///
+    /// let config = ServerConfig::new([127, 0, 0, 1], 8080).set_base_domain("example.com".to_string());
+    /// let mut server = WebServer::new(config);
+    /// server.add_custom_route("/api", HTTPMethod::GET, |_request, _domain| {
+    ///     HTTPResponse::with_body(StatusCode::Ok, "Hello API")
+    /// }, StatusCode::Ok, None);
+    /// ```
+    ///
+    /// Subdomains without their own router fall back to the default domain's
+    /// routes; the handler still sees the subdomain that was requested:
+    ///
+    /// ```rust
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_custom_route("/", HTTPMethod::GET, |_request, domain| {
+    ///     HTTPResponse::with_body(StatusCode::Ok, domain.as_str())
+    /// }, StatusCode::Ok, None);
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let response = server.handle_request(b"GET / HTTP/1.1\r\nHost: Shop.localhost:8080\r\n\r\n", peer);
+    /// assert!(response.ends_with(b"\r\n\r\nshop.localhost"));
+    /// ```
+    ///
+    /// Several methods can share a path. `OPTIONS` requests without their own
+    /// route are answered with `204 No Content` and an `Allow` header listing
+    /// the path's methods; other unregistered methods get `405` with the same
+    /// header:
+    ///
+    /// ```rust
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_custom_route("/items", HTTPMethod::GET, |_request, _domain| {
+    ///     HTTPResponse::with_body(StatusCode::Ok, "list")
+    /// }, StatusCode::Ok, None);
+    /// server.add_custom_route("/items", HTTPMethod::POST, |_request, _domain| {
+    ///     HTTPResponse::with_body(StatusCode::Ok, "created")
+    /// }, StatusCode::Ok, None);
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let send = |request: &str| {
+    ///     String::from_utf8(server.handle_request(request.as_bytes(), peer)).unwrap()
+    /// };
+    ///
+    /// let options = send("OPTIONS /items HTTP/1.1\r\nHost: localhost\r\n\r\n");
+    /// assert!(options.starts_with("HTTP/1.1 204"));
+    /// assert!(options.contains("Allow: GET, POST, OPTIONS\r\n"));
+    ///
+    /// let post = send("POST /items HTTP/1.1\r\nHost: localhost\r\nContent-Length: 0\r\n\r\n");
+    /// assert!(post.ends_with("\r\n\r\ncreated"));
+    ///
+    /// let delete = send("DELETE /items HTTP/1.1\r\nHost: localhost\r\n\r\n");
+    /// assert!(delete.starts_with("HTTP/1.1 405"));
+    /// assert!(delete.contains("Allow: GET, POST, OPTIONS\r\n"));
+    ///
+    /// let missing = send("OPTIONS /nothing HTTP/1.1\r\nHost: localhost\r\n\r\n");
+    /// assert!(missing.starts_with("HTTP/1.1 404"));
+    /// ```
+    pub fn add_custom_route(
+        &mut self,
+        route: &str,
+        method: HTTPMethod,
+        f: impl Fn(HTTPRequest, &Domain) -> HTTPResponse + Send + Sync + 'static,
+        response_codes: StatusCode,
+        domain: Option<&Domain>,
+    ) -> &mut Self {
+        let domain = domain
+            .cloned()
+            .unwrap_or_else(|| self.default_domain.clone());
+        {
+            let mut guard = self.domains.lock().unwrap();
+            let domain_routes = guard
+                .entry(domain.clone())
+                .or_insert_with(|| Arc::new(Mutex::new(Vec::new())));
+
+            let mut routes = domain_routes.lock().unwrap();
+            routes.push(Route::new_custom(
+                route.to_string(),
+                method,
+                response_codes,
+                domain,
+                f,
+            ));
+        }
+        self
+    }
+
+    /// Adds a custom route whose handler can fail.
+    ///
+    /// Works like [`add_custom_route`](Self::add_custom_route), but the handler
+    /// returns a `Result`; an `Err` is converted into the response that is
+    /// sent. [`HttpError`](responses::http_error::HttpError) covers the common
+    /// case of a status code with a plain-text message.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::http_error::HttpError;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_custom_route_result("/double", HTTPMethod::GET, |request, _domain| {
+    ///     let n: i64 = request
+    ///         .query_params
+    ///         .get("n")
+    ///         .and_then(|n| n.parse().ok())
+    ///         .ok_or_else(|| HttpError::bad_request("n must be a number"))?;
+    ///     Ok::<_, HttpError>(HTTPResponse::with_body(StatusCode::Ok, (n * 2).to_string()))
+    /// }, StatusCode::Ok, None);
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    ///
+    /// let response = server.handle_request(b"GET /double?n=21 HTTP/1.1\r\nHost: localhost\r\n\r\n", peer);
+    /// assert!(response.starts_with(b"HTTP/1.1 200 OK"));
+    /// assert!(response.ends_with(b"\r\n\r\n42"));
+    ///
+    /// let response = server.handle_request(b"GET /double?n=abc HTTP/1.1\r\nHost: localhost\r\n\r\n", peer);
+    /// let response = String::from_utf8(response).unwrap();
+    /// assert!(response.starts_with("HTTP/1.1 400 Bad Request"));
+    /// assert!(response.contains("Content-Type: text/plain"));
+    /// assert!(response.ends_with("\r\n\r\nn must be a number"));
+    /// ```
+    pub fn add_custom_route_result<E: Into<HTTPResponse>>(
+        &mut self,
+        route: &str,
+        method: HTTPMethod,
+        f: impl Fn(HTTPRequest, &Domain) -> Result<HTTPResponse, E> + Send + Sync + 'static,
+        response_codes: StatusCode,
+        domain: Option<&Domain>,
+    ) -> &mut Self {
+        self.add_custom_route(
+            route,
+            method,
+            move |request, domain| f(request, domain).unwrap_or_else(Into::into),
+            response_codes,
+            domain,
+        )
+    }
+
+    /// Adds a custom route with middleware that only runs for this route.
+    ///
+    /// Request middleware sees the request before the handler and response
+    /// middleware can post-process the handler's response, e.g. to add headers
+    /// to a response built by shared code. Route middleware runs inside the
+    /// server-wide middleware: after its request middleware and before its
+    /// response middleware. Domain and route patterns of the given middleware
+    /// are still checked against the request, so `None` is usually passed for
+    /// both.
+    ///
+    /// # Arguments
+    ///
+    /// * `route` - URL path to match (e.g., "/api").
+    /// * `method` - HTTP method to respond to.
+    /// * `f` - Closure that takes an `HTTPRequest` and `Domain` and returns an `HTTPResponse`.
+    /// * `response_codes` - Default status code for the response.
+    /// * `middleware` - Middleware applied in order around the handler.
+    /// * `domain` - Optional domain reference; if `None`, the default domain is used.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sunweb::webserver::requests::HTTPRequest;
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{Middleware, ServerConfig, WebServer};
+    ///
+    /// fn listing(request: HTTPRequest) -> HTTPResponse {
+    ///     HTTPResponse::with_body(StatusCode::Ok, request.path.clone())
+    /// }
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_custom_route_with_middleware(
+    ///     "/users",
+    ///     HTTPMethod::GET,
+    ///     |request, _domain| listing(request),
+    ///     StatusCode::Ok,
+    ///     vec![
+    ///         Middleware::new_request(None, None, |request| request.path.push_str("?page=1")),
+    ///         Middleware::new_response(None, None, |response| {
+    ///             response.add_header("Cache-Control", "no-store");
+    ///         }),
+    ///     ],
+    ///     None,
+    /// );
+    /// server.add_custom_route("/teams", HTTPMethod::GET, |request, _domain| listing(request), StatusCode::Ok, None);
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let users = String::from_utf8(server.handle_request(b"GET /users HTTP/1.1\r\nHost: localhost\r\n\r\n", peer)).unwrap();
+    /// assert!(users.contains("Cache-Control: no-store\r\n"));
+    /// assert!(users.ends_with("\r\n\r\n/users?page=1"));
+    ///
+    /// let teams = String::from_utf8(server.handle_request(b"GET /teams HTTP/1.1\r\nHost: localhost\r\n\r\n", peer)).unwrap();
+    /// assert!(!teams.contains("Cache-Control"));
+    /// assert!(teams.ends_with("\r\n\r\n/teams"));
+    /// ```
+    pub fn add_custom_route_with_middleware(
+        &mut self,
+        route: &str,
+        method: HTTPMethod,
+        f: impl Fn(HTTPRequest, &Domain) -> HTTPResponse + Send + Sync + 'static,
+        response_codes: StatusCode,
+        middleware: Vec<Middleware>,
+        domain: Option<&Domain>,
+    ) -> &mut Self {
+        let domain = domain
+            .cloned()
+            .unwrap_or_else(|| self.default_domain.clone());
+
+        let mut custom = Route::new_custom(route.to_string(), method, response_codes, domain, f);
+        custom.middleware = middleware;
+        {
+            let mut guard = self.domains.lock().unwrap();
+            let domain_routes = guard
+                .entry(custom.domain.clone())
+                .or_insert_with(|| Arc::new(Mutex::new(Vec::new())));
+            domain_routes.lock().unwrap().push(custom);
+        }
+        self
     }
 
-    /// Adds a subdomain router for the specified domain.
+    /// Adds a custom route whose handler runs on a separate thread pool.
+    ///
+    /// Use this for handlers that do heavy CPU work or slow blocking I/O. All
+    /// blocking routes share a pool of
+    /// [`ServerConfig::set_blocking_threads`] threads, so only that many of
+    /// their handlers run at once; further requests queue for a free thread.
+    /// The pool limits concurrency, it does not free the connection: the
+    /// connection's own thread waits for the response the whole time. A
+    /// [handler timeout](ServerConfig::set_handler_timeout) still applies,
+    /// including the time spent waiting for a free pool thread.
     ///
     /// # Arguments
     ///
-    /// * `domain` - A reference to the `Domain` to register.
+    /// * `route` - URL path to match (e.g., "/report").
+    /// * `method` - HTTP method to respond to.
+    /// * `f` - Closure that takes an `HTTPRequest` and `Domain` and returns an `HTTPResponse`.
+    /// * `response_codes` - Default status code for the response.
+    /// * `domain` - Optional domain reference; if `None`, the default domain is used.
     ///
     /// # Example
     ///
     /// ```rust
-    /// use webserver::{WebServer, ServerConfig, Domain};
-    /// let config = ServerConfig::new("127.0.0.1", 8080, "example.com");
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::{Arc, Barrier};
+    /// use std::thread;
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let config = ServerConfig::new([127, 0, 0, 1], 8080).set_blocking_threads(2);
     /// let mut server = WebServer::new(config);
-    /// let domain = Domain::new("api");
-    /// server.add_subdomain_router(&domain);
-    /// ```
-    pub fn add_subdomain_router(&mut self, domain: &Domain) {
-        let mut guard = self.domains.lock().unwrap();
-        let domain_str = format!(
-            "{}.{}",
-            domain.name.to_lowercase(),
-            self.default_domain.name
-        );
-        guard
-            .entry(Domain::new(&*domain_str))
-            .or_insert_with(|| Arc::new(Mutex::new(Vec::new())));
-    }
-
-    /// Adds a file-based route to the server.
+    /// let running = Arc::new(AtomicUsize::new(0));
+    /// let most_running = Arc::new(AtomicUsize::new(0));
+    /// let pair = Arc::new(Barrier::new(2));
+    /// {
+    ///     let (running, most_running) = (Arc::clone(&running), Arc::clone(&most_running));
+    ///     server.add_blocking_route("/report", HTTPMethod::GET, move |_request, _domain| {
+    ///         let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+    ///         most_running.fetch_max(now, Ordering::SeqCst);
+    ///         // Only returns once a second handler runs alongside this one.
+    ///         pair.wait();
+    ///         running.fetch_sub(1, Ordering::SeqCst);
+    ///         HTTPResponse::with_body(StatusCode::Ok, "report")
+    ///     }, StatusCode::Ok, None);
+    /// }
+    /// let server = Arc::new(server);
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
     ///
-    /// # Arguments
+    /// let reports: Vec<_> = (0..6)
+    ///     .map(|_| {
+    ///         let server = Arc::clone(&server);
+    ///         thread::spawn(move || {
+    ///             server.handle_request(b"GET /report HTTP/1.1\r\nHost: localhost\r\n\r\n", peer)
+    ///         })
+    ///     })
+    ///     .collect();
+    /// for report in reports {
+    ///     assert!(report.join().unwrap().ends_with(b"\r\n\r\nreport"));
+    /// }
     ///
-    /// * `route` - The URL path to match (e.g., "/about").
-    /// * `method` - HTTP method for the route.
-    /// * `file_path` - Local path to the file to serve.
-    /// * `response_codes` - Status code to respond with.
-    /// * `domain` - Optional `Domain`; defaults to the default domain.
-    pub fn add_route_file(
+    /// // Six requests arrived at once, but the two pool threads ran two handlers at a time.
+    /// assert_eq!(most_running.load(Ordering::SeqCst), 2);
+    /// ```
+    pub fn add_blocking_route(
         &mut self,
         route: &str,
         method: HTTPMethod,
-        file_path: &str,
+        f: impl Fn(HTTPRequest, &Domain) -> HTTPResponse + Send + Sync + 'static,
         response_codes: StatusCode,
         domain: Option<&Domain>,
     ) -> &mut Self {
         let domain = domain
             .cloned()
             .unwrap_or_else(|| self.default_domain.clone());
+        let threads = self.config.blocking_threads;
+        let pool = self
+            .blocking_pool
+            .get_or_insert_with(|| Arc::new(BlockingPool::new(threads)));
 
-        let content = get_file_content(&PathBuf::from(file_path));
-
+        let mut custom = Route::new_custom(route.to_string(), method, response_codes, domain, f);
+        custom.blocking_pool = Some(Arc::clone(pool));
         {
             let mut guard = self.domains.lock().unwrap();
             let domain_routes = guard
-                .entry(domain.clone())
+                .entry(custom.domain.clone())
                 .or_insert_with(|| Arc::new(Mutex::new(Vec::new())));
-
-            let mut routes = domain_routes.lock().unwrap();
-            routes.push(Route::new_file(
-                route.to_string(),
-                method,
-                response_codes,
-                domain,
-                content,
-            ));
+            domain_routes.lock().unwrap().push(custom);
         }
-
         self
     }
 
-    /// Adds a static folder route (serving all files in a directory).
+    /// Adds a route whose handler takes over the connection, e.g. to speak
+    /// WebSockets or another protocol after an `Upgrade` request.
+    ///
+    /// Once a request to the route has been parsed and passed through the
+    /// middleware, `f` receives it together with the client connection,
+    /// decrypted if it uses TLS. Response middleware sees a placeholder
+    /// `101 Switching Protocols`; if it returns a response with another
+    /// status, e.g. `401` for a client that is not logged in, that response
+    /// is sent and `f` is not called. Otherwise no response is sent: the
+    /// handler writes its own, typically `101 Switching Protocols`, and then
+    /// speaks the new protocol. The connection is closed when the handler
+    /// returns and is never reused for further requests. Over HTTP/2, where
+    /// a connection cannot be taken over, the route answers `501`.
+    ///
+    /// The handler runs on the connection's thread without a read timeout.
+    /// Bytes the client sent before receiving the handler's response, beyond
+    /// the request itself, are not passed on.
     ///
     /// # Arguments
     ///
-    /// * `route` - The URL path to match (e.g., "/static").
-    /// * `method` - HTTP method.
-    /// * `folder` - Local folder path containing static files.
-    /// * `response_codes` - Status code for successful responses.
-    /// * `domain` - Optional `Domain`; defaults to the default domain.
-    pub fn add_static_route(
+    /// * `route` - The path to match, e.g. `/ws`.
+    /// * `method` - The HTTP method to match, `GET` for WebSockets.
+    /// * `f` - Closure given the request and the connection.
+    /// * `domain` - Optional domain to register the route on; `None` uses the default domain.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::io::{BufRead, BufReader, Read, Write};
+    /// use std::net::{TcpListener, TcpStream};
+    /// use std::thread;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 0));
+    /// server.add_upgrade_route("/echo", HTTPMethod::GET, |request, stream| {
+    ///     assert_eq!(request.get_header("Upgrade"), Some("echo".to_string()));
+    ///     stream.write_all(b"HTTP/1.1 101 Switching Protocols\r\n\
+    ///         Connection: Upgrade\r\nUpgrade: echo\r\n\r\n").unwrap();
+    ///     let mut buffer = [0u8; 64];
+    ///     loop {
+    ///         match stream.read(&mut buffer) {
+    ///             Ok(0) | Err(_) => break,
+    ///             Ok(n) => stream.write_all(&buffer[..n]).unwrap(),
+    ///         }
+    ///     }
+    /// }, None);
+    ///
+    /// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    /// let addr = listener.local_addr().unwrap();
+    /// let serving = thread::spawn(move || {
+    ///     let (stream, _) = listener.accept().unwrap();
+    ///     server.serve_connection(stream);
+    /// });
+    ///
+    /// let mut client = TcpStream::connect(addr).unwrap();
+    /// client.write_all(b"GET /echo HTTP/1.1\r\nHost: localhost\r\n\
+    ///     Connection: Upgrade\r\nUpgrade: echo\r\n\r\n").unwrap();
+    /// let mut reader = BufReader::new(client.try_clone().unwrap());
+    /// let mut head = String::new();
+    /// while !head.ends_with("\r\n\r\n") {
+    ///     reader.read_line(&mut head).unwrap();
+    /// }
+    /// assert!(head.starts_with("HTTP/1.1 101 Switching Protocols\r\n"));
+    ///
+    /// client.write_all(b"hello").unwrap();
+    /// let mut echoed = [0u8; 5];
+    /// reader.read_exact(&mut echoed).unwrap();
+    /// assert_eq!(&echoed, b"hello");
+    ///
+    /// // Closing our side ends the handler, and with it the connection;
+    /// // no keep-alive response follows.
+    /// client.shutdown(std::net::Shutdown::Write).unwrap();
+    /// serving.join().unwrap();
+    /// let mut rest = Vec::new();
+    /// reader.read_to_end(&mut rest).unwrap();
+    /// assert!(rest.is_empty());
+    /// ```
+    ///
+    /// Response middleware can refuse the upgrade:
+    ///
+    /// ```rust
+    /// use std::io::Write;
+    /// use sunweb::webserver::requests::HTTPRequest;
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{Middleware, ServerConfig, WebServer};
+    ///
+    /// fn require_token(request: &mut HTTPRequest, response: HTTPResponse) -> HTTPResponse {
+    ///     match request.get_header("Authorization") {
+    ///         Some(token) if token == "Bearer secret" => response,
+    ///         _ => HTTPResponse::new(StatusCode::Unauthorized),
+    ///     }
+    /// }
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_upgrade_route("/live", HTTPMethod::GET, |_request, stream| {
+    ///     stream.write_all(b"HTTP/1.1 101 Switching Protocols\r\n\r\nupgraded").unwrap();
+    /// }, None);
+    /// server.add_middleware(Middleware::new_response_both(None, None, require_token));
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let upgrade = |auth: &str| {
+    ///     let raw = format!("GET /live HTTP/1.1\r\nHost: localhost\r\n{auth}Connection: Upgrade\r\nUpgrade: live\r\n\r\n");
+    ///     String::from_utf8(server.handle_request(raw.as_bytes(), peer)).unwrap()
+    /// };
+    ///
+    /// let refused = upgrade("");
+    /// assert!(refused.starts_with("HTTP/1.1 401 Unauthorized\r\n"));
+    /// assert!(!refused.contains("upgraded"));
+    ///
+    /// let accepted = upgrade("Authorization: Bearer secret\r\n");
+    /// assert_eq!(accepted, "HTTP/1.1 101 Switching Protocols\r\n\r\nupgraded");
+    /// ```
+    pub fn add_upgrade_route(
         &mut self,
         route: &str,
         method: HTTPMethod,
-        folder: &str,
-        response_codes: StatusCode,
+        f: impl Fn(HTTPRequest, &mut dyn UpgradedStream) + Send + Sync + 'static,
         domain: Option<&Domain>,
     ) -> &mut Self {
         let domain = domain
             .cloned()
             .unwrap_or_else(|| self.default_domain.clone());
-
-        let folder_path = PathBuf::from(folder);
-        if !folder_path.exists() {
-            error!("Static route file does not exist");
-        }
-
+        let upgrade = Route::new_upgrade(route.to_string(), method, domain, f);
         {
             let mut guard = self.domains.lock().unwrap();
             let domain_routes = guard
-                .entry(domain.clone())
+                .entry(upgrade.domain.clone())
                 .or_insert_with(|| Arc::new(Mutex::new(Vec::new())));
-
-            let mut routes = domain_routes.lock().unwrap();
-            routes.push(Route::new_static(
-                route.to_string(),
-                method,
-                response_codes,
-                domain,
-                String::from(folder),
-            ));
+            domain_routes.lock().unwrap().push(upgrade);
         }
         self
     }
 
-    /// Adds a custom route with a handler function.
+    /// Adds a `GET` route streaming server-sent events.
+    ///
+    /// Every request to the route is answered with a `text/event-stream` body
+    /// that stays open and receives each event published through the returned
+    /// [`SseBroadcaster`]. Clients whose connection closes are dropped from it.
     ///
     /// # Example
     ///
     /// ```rust
-    /// use webserver::{WebServer, ServerConfig, Domain, HTTPRequest, HTTPResponse};
-    /// use webserver::route::HTTPMethod;
-    /// use webserver::responses::StatusCode;
+    /// use sunweb::webserver::{ServerConfig, SseEvent, WebServer};
+    /// use std::io::{BufRead, BufReader, Write};
+    /// use std::net::{TcpListener, TcpStream};
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
     ///
-    /// let config = ServerConfig::new("127.0.0.1", 8080, "example.com");
-    /// let mut server = WebServer::new(config);
-    /// server.add_custom_route("/api", HTTPMethod::GET, |_request, _domain| {
-    ///     HTTPResponse::new(200, "Hello API".to_string())
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// let events = server.add_sse_route("/events", None);
+    /// let server = Arc::new(server);
+    ///
+    /// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    /// let addr = listener.local_addr().unwrap();
+    /// std::thread::spawn(move || {
+    ///     for stream in listener.incoming() {
+    ///         let server = Arc::clone(&server);
+    ///         std::thread::spawn(move || server.serve_connection(stream.unwrap()));
+    ///     }
+    /// });
+    ///
+    /// let subscribe = || {
+    ///     let mut client = TcpStream::connect(addr).unwrap();
+    ///     client.write_all(b"GET /events HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+    ///     let mut reader = BufReader::new(client);
+    ///     let mut head = String::new();
+    ///     let mut line = String::new();
+    ///     while line != "\r\n" {
+    ///         line.clear();
+    ///         reader.read_line(&mut line).unwrap();
+    ///         head.push_str(&line);
+    ///     }
+    ///     assert!(head.contains("Content-Type: text/event-stream"));
+    ///     reader
+    /// };
+    /// let mut first = subscribe();
+    /// let mut second = subscribe();
+    /// assert_eq!(events.subscriber_count(), 2);
+    ///
+    /// assert_eq!(events.publish(SseEvent::new("hello").event("greeting")), 2);
+    /// for reader in [&mut first, &mut second] {
+    ///     let mut size = String::new();
+    ///     reader.read_line(&mut size).unwrap();
+    ///     let mut chunk = String::new();
+    ///     while !chunk.ends_with("\n\n") {
+    ///         reader.read_line(&mut chunk).unwrap();
+    ///     }
+    ///     assert_eq!(chunk, "event: greeting\ndata: hello\n\n");
+    /// }
+    ///
+    /// // A closed connection is noticed once writing to it fails.
+    /// drop(second);
+    /// for _ in 0..100 {
+    ///     if events.subscriber_count() == 1 {
+    ///         break;
+    ///     }
+    ///     events.publish("ping");
+    ///     std::thread::sleep(Duration::from_millis(20));
+    /// }
+    /// assert_eq!(events.subscriber_count(), 1);
+    /// ```
+    pub fn add_sse_route(&mut self, route: &str, domain: Option<&Domain>) -> SseBroadcaster {
+        let broadcaster = SseBroadcaster::new();
+        let subscriptions = broadcaster.clone();
+        self.add_custom_route(
+            route,
+            HTTPMethod::GET,
+            move |_request, _domain| {
+                let mut response = HTTPResponse::new(StatusCode::Ok);
+                response.set_content_type(ContentType::Text(TextSubType::EventStream));
+                response.set_cache_control("no-cache");
+                response.set_body_reader(subscriptions.subscribe(), None);
+                response
+            },
+            StatusCode::Ok,
+            domain,
+        );
+        broadcaster
+    }
+
+    /// Mounts the routes and middleware of `router` under `prefix`.
+    ///
+    /// A route `/x` of the router is served at `{prefix}/x`, and its `/` at
+    /// `{prefix}/`. The router's middleware runs only for requests to `prefix`
+    /// and paths below it, after the server's own request middleware.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - The path prefix, e.g. `/admin`.
+    /// * `router` - The router to mount.
+    /// * `domain` - Domain to mount on; `None` uses the default domain for both
+    ///   the routes and the middleware.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{Domain, Router, ServerConfig, WebServer};
+    ///
+    /// fn mark_admin(response: &mut HTTPResponse) {
+    ///     response.add_header("X-Admin", "1");
+    /// }
+    ///
+    /// let mut reports = Router::new();
+    /// reports.add_custom_route("/daily", HTTPMethod::GET, |_request, _domain| {
+    ///     HTTPResponse::with_body(StatusCode::Ok, "daily report")
+    /// }, StatusCode::Ok);
+    ///
+    /// let mut admin = Router::new();
+    /// admin.add_custom_route("/", HTTPMethod::GET, |_request, _domain| {
+    ///     HTTPResponse::with_body(StatusCode::Ok, "admin home")
+    /// }, StatusCode::Ok);
+    /// admin.add_response_middleware(mark_admin);
+    /// admin.mount("/reports", reports);
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_custom_route("/", HTTPMethod::GET, |_request, _domain| {
+    ///     HTTPResponse::with_body(StatusCode::Ok, "public")
     /// }, StatusCode::Ok, None);
+    /// server.mount("/admin", admin, None);
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let get = |path: &str| {
+    ///     let raw = format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n");
+    ///     String::from_utf8(server.handle_request(raw.as_bytes(), peer)).unwrap()
+    /// };
+    ///
+    /// let response = get("/admin/");
+    /// assert!(response.ends_with("\r\n\r\nadmin home"));
+    /// assert!(response.contains("X-Admin: 1\r\n"));
+    ///
+    /// let response = get("/admin/reports/daily");
+    /// assert!(response.ends_with("\r\n\r\ndaily report"));
+    /// assert!(response.contains("X-Admin: 1\r\n"));
+    ///
+    /// let response = get("/administrator");
+    /// assert!(response.ends_with("\r\n\r\npublic"));
+    /// assert!(!response.contains("X-Admin"));
+    ///
+    /// // The router was mounted on the default domain, so its middleware
+    /// // leaves the same path on another domain alone.
+    /// let api = Domain::new("api.example.com");
+    /// server.add_custom_route("/admin/", HTTPMethod::GET, |_request, _domain| {
+    ///     HTTPResponse::with_body(StatusCode::Ok, "api admin")
+    /// }, StatusCode::Ok, Some(&api));
+    /// let raw = b"GET /admin/ HTTP/1.1\r\nHost: api.example.com\r\n\r\n";
+    /// let response = String::from_utf8(server.handle_request(raw, peer)).unwrap();
+    /// assert!(response.ends_with("\r\n\r\napi admin"));
+    /// assert!(!response.contains("X-Admin"));
     /// ```
-    pub fn add_custom_route(
-        &mut self,
-        route: &str,
-        method: HTTPMethod,
-        f: impl Fn(HTTPRequest, &Domain) -> HTTPResponse + Send + Sync + 'static,
-        response_codes: StatusCode,
-        domain: Option<&Domain>,
-    ) -> &mut Self {
-        let domain = domain
+    pub fn mount(&mut self, prefix: &str, router: Router, domain: Option<&Domain>) -> &mut Self {
+        let router = router.prefixed(prefix);
+        let route_domain = domain
             .cloned()
             .unwrap_or_else(|| self.default_domain.clone());
         {
             let mut guard = self.domains.lock().unwrap();
             let domain_routes = guard
-                .entry(domain.clone())
+                .entry(route_domain.clone())
                 .or_insert_with(|| Arc::new(Mutex::new(Vec::new())));
 
             let mut routes = domain_routes.lock().unwrap();
-            routes.push(Route::new_custom(
-                route.to_string(),
-                method,
-                response_codes,
-                domain,
-                f,
-            ));
+            routes.extend(router.routes.into_iter().map(|mut route| {
+                route.domain = route_domain.clone();
+                route
+            }));
         }
+
+        let middleware = Arc::make_mut(&mut self.middleware);
+        middleware.extend(router.middleware.into_iter().map(|mut middleware| {
+            middleware.domain = route_domain.clone();
+            middleware
+        }));
+        self
+    }
+
+    /// Registers middleware for every request the server handles.
+    ///
+    /// Middleware runs in registration order, after the built-in logging,
+    /// error page and security header middleware. Requests already being
+    /// served when it is added are not affected, so register middleware
+    /// before calling [`start`](Self::start).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{Middleware, ServerConfig, WebServer};
+    ///
+    /// fn powered_by(response: &mut HTTPResponse) {
+    ///     response.add_header("X-Powered-By", "sunweb");
+    /// }
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_custom_route("/hello", HTTPMethod::GET, |_request, _domain| {
+    ///     HTTPResponse::with_body(StatusCode::Ok, "hello")
+    /// }, StatusCode::Ok, None);
+    /// server.add_middleware(Middleware::new_response(None, None, powered_by));
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let get = |path: &str| {
+    ///     let raw = format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n");
+    ///     String::from_utf8(server.handle_request(raw.as_bytes(), peer)).unwrap()
+    /// };
+    ///
+    /// let response = get("/hello");
+    /// assert!(response.ends_with("\r\n\r\nhello"));
+    /// assert!(response.contains("X-Powered-By: sunweb\r\n"));
+    ///
+    /// // Error pages pass through the same chain.
+    /// let response = get("/missing");
+    /// assert!(response.starts_with("HTTP/1.1 404"));
+    /// assert!(response.contains("X-Powered-By: sunweb\r\n"));
+    /// ```
+    pub fn add_middleware(&mut self, middleware: Middleware) -> &mut Self {
+        Arc::make_mut(&mut self.middleware).push(middleware);
         self
     }
 
@@ -369,6 +2396,33 @@ impl WebServer {
     /// * `file` - Path to the HTML file to serve as the error page.
     /// * `response_codes` - Status code that this error page corresponds to (e.g., `StatusCode::NotFound`).
     /// * `domain` - Optional domain reference; if `None`, the default domain is used.
+    ///
+    /// Error pages are looked up on the domain the request was addressed to, so a
+    /// subdomain can have its own pages.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{Domain, ServerConfig, WebServer};
+    ///
+    /// let dir = tempfile::tempdir().unwrap();
+    /// let page = dir.path().join("404.html");
+    /// std::fs::write(&page, "api page not found").unwrap();
+    /// let api = Domain::new("api.localhost");
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_subdomain_router(&Domain::new("api"));
+    /// server.add_static_route("/docs", HTTPMethod::GET, dir.path().to_str().unwrap(), StatusCode::Ok, Some(&api));
+    /// server.add_error_route(page.to_str().unwrap(), StatusCode::NotFound, Some(&api));
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let response = server.handle_request(b"GET /docs/missing.html HTTP/1.1\r\nHost: api.localhost\r\n\r\n", peer);
+    /// let response = String::from_utf8_lossy(&response);
+    /// assert!(response.starts_with("HTTP/1.1 404 Not Found"));
+    /// assert!(response.ends_with("api page not found"));
+    /// ```
     pub fn add_error_route(
         &mut self,
         file: &str,
@@ -403,18 +2457,138 @@ impl WebServer {
     /// Incoming requests matching `route` will be forwarded to `external` URL.
     /// This is useful for integrating microservices or external APIs.
     ///
+    /// The full request path and query string are appended to the path of
+    /// `external`, so with `external` set to `https://backend/v2` a request to
+    /// `/api/users?active=1` is forwarded to `https://backend/v2/api/users?active=1`.
+    ///
     /// # Arguments
     ///
     /// * `route` - URL path to match (e.g., "/api").
     /// * `external` - Full external URL to forward the request to (e.g., "https://api.example.com").
     /// * `response_codes` - Status code to respond with for successful proxying.
     /// * `domain` - Optional domain reference; if `None`, the default domain is used.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::io::{BufRead, BufReader, Write};
+    /// use std::net::TcpListener;
+    /// use std::thread;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// // An upstream that answers with the request line it received.
+    /// let upstream = TcpListener::bind("127.0.0.1:0").unwrap();
+    /// let upstream_addr = upstream.local_addr().unwrap();
+    /// thread::spawn(move || {
+    ///     for stream in upstream.incoming() {
+    ///         let mut stream = stream.unwrap();
+    ///         let mut reader = BufReader::new(stream.try_clone().unwrap());
+    ///         let mut request_line = String::new();
+    ///         reader.read_line(&mut request_line).unwrap();
+    ///         let mut line = String::new();
+    ///         while reader.read_line(&mut line).unwrap() > 2 {
+    ///             line.clear();
+    ///         }
+    ///         let body = request_line.trim_end();
+    ///         let response = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{body}", body.len());
+    ///         stream.write_all(response.as_bytes()).unwrap();
+    ///     }
+    /// });
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_proxy_route("/api", &format!("http://{upstream_addr}/v2/"), StatusCode::Ok, None);
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let forwarded = |path: &str| {
+    ///     let raw = format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n");
+    ///     let response = String::from_utf8(server.handle_request(raw.as_bytes(), peer)).unwrap();
+    ///     response.split("\r\n\r\n").nth(1).unwrap().to_string()
+    /// };
+    ///
+    /// assert_eq!(forwarded("/api/users/7/posts?active=1&sort=name"), "GET /v2/api/users/7/posts?active=1&sort=name HTTP/1.1");
+    /// assert_eq!(forwarded("/api?page=2"), "GET /v2/api?page=2 HTTP/1.1");
+    /// assert_eq!(forwarded("/api/"), "GET /v2/api/ HTTP/1.1");
+    /// ```
     pub fn add_proxy_route(
         &mut self,
         route: &str,
         external: &str,
         response_codes: StatusCode,
         domain: Option<&Domain>,
+    ) -> &mut Self {
+        self.add_proxy_route_with_breaker(route, external, response_codes, None, domain)
+    }
+
+    /// Adds a proxy route guarded by a [`CircuitBreaker`].
+    ///
+    /// While the breaker is open, requests are answered with
+    /// `503 Service Unavailable` and a `Retry-After` header instead of waiting
+    /// on an upstream that is known to be failing.
+    ///
+    /// # Arguments
+    ///
+    /// * `route` - URL path to match (e.g., "/api").
+    /// * `external` - Full external URL to forward the request to.
+    /// * `response_codes` - Status code to respond with for successful proxying.
+    /// * `circuit_breaker` - The breaker to use; `None` always forwards.
+    /// * `domain` - Optional domain reference; if `None`, the default domain is used.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::io::{Read, Write};
+    /// use std::net::TcpListener;
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::{CircuitBreaker, ServerConfig, WebServer};
+    ///
+    /// // Reserve a port, then close it so the upstream refuses connections.
+    /// let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    ///
+    /// let breaker = CircuitBreaker::new(2, Duration::from_millis(300));
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_proxy_route_with_breaker(
+    ///     "/api", &format!("http://127.0.0.1:{port}"), StatusCode::Ok, Some(breaker), None,
+    /// );
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let get = || {
+    ///     let raw = b"GET /api/status HTTP/1.1\r\nHost: localhost\r\n\r\n";
+    ///     String::from_utf8(server.handle_request(raw, peer)).unwrap()
+    /// };
+    ///
+    /// // Two failures trip the breaker; the upstream is then left alone.
+    /// assert!(get().starts_with("HTTP/1.1 502 Bad Gateway"));
+    /// assert!(get().starts_with("HTTP/1.1 502 Bad Gateway"));
+    /// let tripped = get();
+    /// assert!(tripped.starts_with("HTTP/1.1 503 Service Unavailable"));
+    /// assert!(tripped.contains("Retry-After: 1\r\n"));
+    ///
+    /// // The upstream recovers; after the cooldown a probe gets through and closes the breaker.
+    /// let upstream = TcpListener::bind(("127.0.0.1", port)).unwrap();
+    /// thread::spawn(move || {
+    ///     for stream in upstream.incoming().take(2) {
+    ///         let mut stream = stream.unwrap();
+    ///         let mut request = [0u8; 1024];
+    ///         let _ = stream.read(&mut request).unwrap();
+    ///         stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\n\
+    ///             Content-Length: 2\r\nConnection: close\r\n\r\nup").unwrap();
+    ///     }
+    /// });
+    /// assert!(get().starts_with("HTTP/1.1 503 Service Unavailable"));
+    /// thread::sleep(Duration::from_millis(350));
+    /// assert!(get().ends_with("\r\n\r\nup"));
+    /// assert!(get().ends_with("\r\n\r\nup"));
+    /// ```
+    pub fn add_proxy_route_with_breaker(
+        &mut self,
+        route: &str,
+        external: &str,
+        response_codes: StatusCode,
+        circuit_breaker: Option<CircuitBreaker>,
+        domain: Option<&Domain>,
     ) -> &mut Self {
         let domain = domain
             .cloned()
@@ -433,11 +2607,123 @@ impl WebServer {
                 domain,
                 response_codes,
                 external.to_string(),
+                circuit_breaker,
             ));
         }
         self
     }
 
+    /// Lists the routes registered for a domain, in registration order.
+    ///
+    /// # Arguments
+    ///
+    /// * `domain` - Optional `Domain`; defaults to the default domain.
+    ///
+    /// # Returns
+    ///
+    /// A summary of every route, or an empty list if the domain has no router.
+    pub fn routes(&self, domain: Option<&Domain>) -> Vec<RouteInfo> {
+        let domain = domain.unwrap_or(&self.default_domain);
+        let guard = self.domains.lock().unwrap();
+        match guard.get(domain) {
+            Some(routes) => routes.lock().unwrap().iter().map(Route::info).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Lists the domains that have a router, sorted by name.
+    ///
+    /// This includes the base domain, every domain added with
+    /// [`add_subdomain_router`](Self::add_subdomain_router) and any domain a
+    /// route was registered for.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sunweb::webserver::{Domain, ServerConfig, WebServer};
+    ///
+    /// let config = ServerConfig::new([127, 0, 0, 1], 8080).set_base_domain("example.com".to_string());
+    /// let mut server = WebServer::new(config);
+    /// server.add_subdomain_router(&Domain::new("api"));
+    /// server.add_subdomain_router(&Domain::new("Shop"));
+    ///
+    /// assert_eq!(server.domains(), ["api.example.com", "example.com", "shop.example.com"]);
+    /// ```
+    pub fn domains(&self) -> Vec<String> {
+        let mut domains: Vec<String> = self
+            .domains
+            .lock()
+            .unwrap()
+            .keys()
+            .map(|domain| domain.as_str().to_string())
+            .collect();
+        domains.sort();
+        domains
+    }
+
+    /// Removes a route registered with the given path and method.
+    ///
+    /// # Arguments
+    ///
+    /// * `route` - The URL path the route was registered with.
+    /// * `method` - HTTP method of the route.
+    /// * `domain` - Optional `Domain`; defaults to the default domain.
+    ///
+    /// # Returns
+    ///
+    /// `true` if a route was removed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::{HTTPMethod, RouteType};
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_custom_route("/admin", HTTPMethod::GET, |_request, _domain| {
+    ///     HTTPResponse::with_body(StatusCode::Ok, "admin")
+    /// }, StatusCode::Ok, None);
+    ///
+    /// let routes = server.routes(None);
+    /// assert_eq!(routes.len(), 1);
+    /// assert_eq!(routes[0].route, "/admin");
+    /// assert_eq!(routes[0].route_type, RouteType::Custom);
+    ///
+    /// assert!(server.remove_route("/admin", HTTPMethod::GET, None));
+    /// assert!(server.routes(None).is_empty());
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let response = server.handle_request(b"GET /admin HTTP/1.1\r\nHost: localhost\r\n\r\n", peer);
+    /// assert!(response.starts_with(b"HTTP/1.1 404 Not Found"));
+    /// ```
+    pub fn remove_route(
+        &mut self,
+        route: &str,
+        method: HTTPMethod,
+        domain: Option<&Domain>,
+    ) -> bool {
+        let domain = domain.unwrap_or(&self.default_domain);
+        let guard = self.domains.lock().unwrap();
+        let Some(routes) = guard.get(domain) else {
+            return false;
+        };
+
+        let mut routes = routes.lock().unwrap();
+        let before = routes.len();
+        routes.retain(|r| !(r.route == route && r.method == method));
+        routes.len() != before
+    }
+
+    /// Internal middleware function adding the default security headers.
+    ///
+    /// Registered when [`ServerConfig::default_security_headers`] is enabled.
+    /// Headers the handler already set are kept.
+    pub(crate) fn security_headers(response: &mut HTTPResponse) {
+        response.message.headers.apply_default_security_headers();
+    }
+
     /// Internal middleware function for handling error pages.
     ///
     /// This function is used internally to override default error responses
@@ -471,9 +2757,31 @@ impl WebServer {
             if let Some(content) = &route.content {
                 let mut response = HTTPResponse::new(status_code);
                 response.set_body_string(content.to_string());
+                return response;
             }
         }
 
         response
     }
 }
+
+/// Binds a listener to `addr` whose pending-connection queue holds `backlog`
+/// connections.
+fn bind_with_backlog(addr: &str, backlog: u32) -> io::Result<TcpListener> {
+    let addr = addr
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "No address to bind to"))?;
+    let socket = Socket::new(
+        socket2::Domain::for_address(addr),
+        Type::STREAM,
+        Some(Protocol::TCP),
+    )?;
+    // Like `TcpListener::bind`, so a restarted server can bind while old
+    // connections are still in TIME_WAIT.
+    #[cfg(unix)]
+    socket.set_reuse_address(true)?;
+    socket.bind(&addr.into())?;
+    socket.listen(i32::try_from(backlog).unwrap_or(i32::MAX))?;
+    Ok(socket.into())
+}
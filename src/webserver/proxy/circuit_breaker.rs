@@ -0,0 +1,77 @@
+//! Short-circuiting of proxy routes whose upstream keeps failing.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Stops forwarding to an upstream after repeated failures.
+///
+/// After `failure_threshold` consecutive failed requests the breaker opens and
+/// the route answers `503 Service Unavailable` without contacting the upstream.
+/// Once the cooldown has passed, a single request is let through as a probe:
+/// if it succeeds the breaker closes again, otherwise it stays open for another
+/// cooldown. A request fails when the upstream cannot be reached or sends no
+/// response.
+///
+/// Clones share their state, so one breaker can guard several routes to the
+/// same upstream.
+#[derive(Clone, Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    state: Arc<Mutex<BreakerState>>,
+}
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    /// When the breaker lets the next probe through; `None` while closed.
+    open_until: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    /// Creates a closed breaker.
+    ///
+    /// # Arguments
+    ///
+    /// * `failure_threshold` - Consecutive failures that open the breaker; at least 1.
+    /// * `cooldown` - How long the breaker stays open before probing the upstream again.
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> CircuitBreaker {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+            state: Arc::new(Mutex::new(BreakerState::default())),
+        }
+    }
+
+    /// Decides whether a request may be forwarded.
+    ///
+    /// Returns `Err(remaining)` with the time left until the next probe while
+    /// the breaker is open. When the cooldown has passed the calling request
+    /// becomes the probe and later requests are held off for another cooldown
+    /// until it reports back.
+    pub(crate) fn try_acquire(&self) -> Result<(), Duration> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(open_until) = state.open_until else {
+            return Ok(());
+        };
+        let now = Instant::now();
+        if now < open_until {
+            return Err(open_until - now);
+        }
+        state.open_until = Some(now + self.cooldown);
+        Ok(())
+    }
+
+    /// Records the outcome of a forwarded request.
+    pub(crate) fn record(&self, success: bool) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if success {
+            *state = BreakerState::default();
+            return;
+        }
+        state.consecutive_failures = state.consecutive_failures.saturating_add(1);
+        if state.consecutive_failures >= self.failure_threshold {
+            state.open_until = Some(Instant::now() + self.cooldown);
+        }
+    }
+}
@@ -5,7 +5,16 @@
 //! 2. one-shot `GET` requests
 //! 3. minimal HTTP/1.1 response parsing (headers + chunked or `Content-Length` body)
 //!
-//! Timeouts are hard-coded to 5 s.  Keep-alive is **not** supported.
+//! Timeouts are hard-coded to 5 s.  Connections are closed after every request
+//! unless the server enables keep-alive to upstreams with
+//! [`ServerConfig::set_proxy_pool_size`](crate::webserver::ServerConfig::set_proxy_pool_size),
+//! in which case idle connections are kept in a [`ConnectionPool`] and reused.
+
+mod circuit_breaker;
+mod pool;
+
+pub use circuit_breaker::CircuitBreaker;
+pub(crate) use pool::{ConnectionPool, Upstream};
 
 use log::warn;
 use rustls::{ClientConfig, ClientConnection, RootCertStore, StreamOwned};
@@ -17,7 +26,7 @@ use std::sync::{Arc, OnceLock};
 use std::time::Duration;
 
 /// Transport scheme inferred from the URL.
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub(crate) enum ProxySchema {
     /// Plain-text HTTP (port 80 by default).
     HTTP,
@@ -182,28 +191,12 @@ impl Proxy {
         path: &str,
         host: &str,
     ) -> Option<Vec<u8>> {
-        static TLS_CONFIG: OnceLock<Arc<ClientConfig>> = OnceLock::new();
-
-        let config = TLS_CONFIG.get_or_init(|| {
-            let mut root_store = RootCertStore::empty();
-            let certs = load_native_certs();
-            for cert in certs.certs {
-                let _ = root_store.add(cert);
-            }
-
-            Arc::new(
-                ClientConfig::builder()
-                    .with_root_certificates(root_store)
-                    .with_no_client_auth(),
-            )
-        });
-
         let server_name = match ServerName::try_from(host.to_string()) {
             Ok(s) => s,
             Err(_) => return None,
         };
 
-        let conn = match ClientConnection::new(config.clone(), server_name) {
+        let conn = match ClientConnection::new(tls_config(), server_name) {
             Ok(c) => c,
             Err(_) => return None,
         };
@@ -227,6 +220,50 @@ impl Proxy {
         Some(response)
     }
 
+    /// Sends a `GET` for the parsed URL over a pooled keep-alive connection.
+    ///
+    /// An idle connection to the same `(host, port, scheme)` is reused when one
+    /// is available. If it turns out to be stale (closed by the upstream), or
+    /// none is idle, a new connection is opened. The connection goes back into
+    /// the pool once the response has been read completely, unless the
+    /// upstream asked to close it.
+    pub(crate) fn send_pooled_request(&self, pool: &ConnectionPool) -> Option<Vec<u8>> {
+        if let Some(mut upstream) = pool.checkout(&self.host, self.port, self.scheme) {
+            match exchange(&mut upstream, &self.path, &self.host) {
+                Some((response, reusable)) => {
+                    if reusable {
+                        pool.checkin(&self.host, self.port, self.scheme, upstream);
+                    }
+                    return Some(response);
+                }
+                None => warn!(
+                    "Pooled connection to {}:{} is stale, reconnecting",
+                    self.host, self.port
+                ),
+            }
+        }
+
+        let mut upstream = self.open_upstream()?;
+        let (response, reusable) = exchange(&mut upstream, &self.path, &self.host)?;
+        if reusable {
+            pool.checkin(&self.host, self.port, self.scheme, upstream);
+        }
+        Some(response)
+    }
+
+    /// Opens a new connection to the parsed upstream, wrapped in TLS for HTTPS.
+    fn open_upstream(&self) -> Option<Upstream> {
+        let stream = Self::connect_to_server(&self.host, self.port)?;
+        match self.scheme {
+            ProxySchema::HTTP => Some(Upstream::Plain(stream)),
+            ProxySchema::HTTPS => {
+                let server_name = ServerName::try_from(self.host.clone()).ok()?;
+                let conn = ClientConnection::new(tls_config(), server_name).ok()?;
+                Some(Upstream::Tls(Box::new(StreamOwned::new(conn, stream))))
+            }
+        }
+    }
+
     /// Minimal HTTP response parser.
     ///
     /// Returns `(body_bytes, content_type_string)`:
@@ -286,6 +323,142 @@ impl Proxy {
     }
 }
 
+/// Client TLS configuration trusting the native root store, loaded once.
+fn tls_config() -> Arc<ClientConfig> {
+    static TLS_CONFIG: OnceLock<Arc<ClientConfig>> = OnceLock::new();
+
+    TLS_CONFIG
+        .get_or_init(|| {
+            let mut root_store = RootCertStore::empty();
+            let certs = load_native_certs();
+            for cert in certs.certs {
+                let _ = root_store.add(cert);
+            }
+
+            Arc::new(
+                ClientConfig::builder()
+                    .with_root_certificates(root_store)
+                    .with_no_client_auth(),
+            )
+        })
+        .clone()
+}
+
+/// How the end of an upstream response body is determined.
+enum Framing {
+    /// `Content-Length` bytes (zero for bodiless status codes).
+    Length(usize),
+    /// `Transfer-Encoding: chunked`, ending with the zero-length chunk.
+    Chunked,
+    /// Neither; the body ends when the upstream closes the connection.
+    UntilClose,
+}
+
+/// Sends a keep-alive `GET` request and reads exactly one response.
+///
+/// Returns the raw response and whether the connection can carry another
+/// request, or `None` if the upstream closed the connection or an I/O error
+/// occurred before the response was complete.
+fn exchange(upstream: &mut Upstream, path: &str, host: &str) -> Option<(Vec<u8>, bool)> {
+    let request = format!(
+        "GET {} HTTP/1.1\r\nHost: {}\r\nConnection: keep-alive\r\nAccept-Encoding: identity\r\n\r\n",
+        path, host
+    );
+    upstream.write_all(request.as_bytes()).ok()?;
+    upstream.flush().ok()?;
+
+    let mut buffer = Vec::new();
+    let mut temp = [0u8; 8192];
+    let header_end = loop {
+        if let Some(end) = find_header_end(&buffer) {
+            break end;
+        }
+        match upstream.read(&mut temp) {
+            Ok(0) => return None,
+            Ok(n) => buffer.extend_from_slice(&temp[..n]),
+            Err(e) => {
+                warn!("Failed to read from upstream: {}", e);
+                return None;
+            }
+        }
+    };
+
+    let headers = String::from_utf8_lossy(&buffer[..header_end]).to_lowercase();
+    let status = headers
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse::<u16>().ok())
+        .unwrap_or(0);
+    let mut framing = Framing::UntilClose;
+    let mut close = headers.starts_with("http/1.0");
+    for line in headers.lines() {
+        if let Some((name, value)) = line.split_once(':') {
+            match name.trim() {
+                "content-length" => {
+                    if let Ok(len) = value.trim().parse() {
+                        framing = Framing::Length(len);
+                    }
+                }
+                "transfer-encoding" if value.contains("chunked") => framing = Framing::Chunked,
+                "connection" if value.contains("close") => close = true,
+                _ => {}
+            }
+        }
+    }
+    if (100..200).contains(&status) || status == 204 || status == 304 {
+        framing = Framing::Length(0);
+    }
+
+    let body_start = header_end + 4;
+    loop {
+        let end = match framing {
+            Framing::Length(len) => Some(body_start + len).filter(|&end| buffer.len() >= end),
+            Framing::Chunked => chunked_body_end(&buffer[body_start..]).map(|len| body_start + len),
+            Framing::UntilClose => None,
+        };
+        if let Some(end) = end {
+            buffer.truncate(end);
+            return Some((buffer, !close));
+        }
+
+        match upstream.read(&mut temp) {
+            Ok(0) if matches!(framing, Framing::UntilClose) => return Some((buffer, false)),
+            Ok(0) => return None,
+            Ok(n) => buffer.extend_from_slice(&temp[..n]),
+            Err(e) => {
+                warn!("Failed to read from upstream: {}", e);
+                return None;
+            }
+        }
+    }
+}
+
+/// Returns the length of a complete chunked body, including the final
+/// zero-length chunk and trailers, or `None` if more data is needed.
+fn chunked_body_end(data: &[u8]) -> Option<usize> {
+    let mut pos = 0;
+    loop {
+        let line_end = pos + data[pos..].windows(2).position(|w| w == b"\r\n")? + 2;
+        let line_text = String::from_utf8_lossy(&data[pos..line_end - 2]);
+        let size = usize::from_str_radix(line_text.trim().split(';').next()?, 16).ok()?;
+        pos = line_end;
+
+        if size == 0 {
+            // Trailers, if any, end with an empty line.
+            return if data[pos..].starts_with(b"\r\n") {
+                Some(pos + 2)
+            } else {
+                find_header_end(&data[pos..]).map(|end| pos + end + 4)
+            };
+        }
+
+        pos += size + 2;
+        if pos > data.len() {
+            return None;
+        }
+    }
+}
+
 /// Returns the index of the first `\r\n\r\n` sequence, marking the end of
 /// HTTP headers.
 pub(crate) fn find_header_end(buffer: &[u8]) -> Option<usize> {
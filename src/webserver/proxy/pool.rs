@@ -0,0 +1,85 @@
+//! Reuse of idle upstream connections across proxied requests.
+
+use crate::webserver::proxy::ProxySchema;
+use rustls::{ClientConnection, StreamOwned};
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Idle connections older than this are dropped instead of reused.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Identifies the upstream a connection is open to.
+type PoolKey = (String, u16, ProxySchema);
+
+/// An open connection to an upstream server.
+pub(crate) enum Upstream {
+    /// Plain-text HTTP.
+    Plain(TcpStream),
+    /// TLS-wrapped HTTPS.
+    Tls(Box<StreamOwned<ClientConnection, TcpStream>>),
+}
+
+impl Read for Upstream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Upstream::Plain(stream) => stream.read(buf),
+            Upstream::Tls(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Upstream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Upstream::Plain(stream) => stream.write(buf),
+            Upstream::Tls(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Upstream::Plain(stream) => stream.flush(),
+            Upstream::Tls(stream) => stream.flush(),
+        }
+    }
+}
+
+/// Idle upstream connections keyed by `(host, port, scheme)`, shared by all
+/// client connections of a server.
+pub(crate) struct ConnectionPool {
+    idle: Mutex<HashMap<PoolKey, Vec<(Upstream, Instant)>>>,
+    /// Maximum number of idle connections kept per upstream.
+    max_idle: usize,
+}
+
+impl ConnectionPool {
+    pub(crate) fn new(max_idle: usize) -> Self {
+        Self {
+            idle: Mutex::new(HashMap::new()),
+            max_idle,
+        }
+    }
+
+    /// Takes the most recently used idle connection to the upstream, if any
+    /// is younger than the idle timeout.
+    pub(crate) fn checkout(&self, host: &str, port: u16, scheme: ProxySchema) -> Option<Upstream> {
+        let mut idle = self.idle.lock().unwrap_or_else(|e| e.into_inner());
+        let connections = idle.get_mut(&(host.to_string(), port, scheme))?;
+        connections.retain(|(_, since)| since.elapsed() < IDLE_TIMEOUT);
+        connections.pop().map(|(upstream, _)| upstream)
+    }
+
+    /// Returns a connection whose last response was read completely.
+    ///
+    /// The connection is dropped if the upstream already has `max_idle` idle connections.
+    pub(crate) fn checkin(&self, host: &str, port: u16, scheme: ProxySchema, upstream: Upstream) {
+        let mut idle = self.idle.lock().unwrap_or_else(|e| e.into_inner());
+        let connections = idle.entry((host.to_string(), port, scheme)).or_default();
+        if connections.len() < self.max_idle {
+            connections.push((upstream, Instant::now()));
+        }
+    }
+}
@@ -0,0 +1,76 @@
+//! Client certificates presented during a mutual-TLS handshake.
+
+use x509_parser::prelude::{FromDer, GeneralName, X509Certificate};
+
+/// The certificate a client presented during the TLS handshake.
+///
+/// Only available when the server is configured with
+/// [`ServerConfig::require_client_cert`](crate::webserver::ServerConfig::require_client_cert)
+/// or [`ServerConfig::allow_client_cert`](crate::webserver::ServerConfig::allow_client_cert).
+/// The certificate has already been verified against the configured CA.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClientCertificate {
+    der: Vec<u8>,
+    subject: String,
+    subject_alt_names: Vec<String>,
+}
+
+impl ClientCertificate {
+    /// Parses a DER-encoded X.509 certificate.
+    ///
+    /// Returns `None` if the certificate cannot be parsed.
+    pub(crate) fn from_der(der: &[u8]) -> Option<Self> {
+        let (_, cert) = X509Certificate::from_der(der).ok()?;
+
+        let subject_alt_names = cert
+            .subject_alternative_name()
+            .ok()
+            .flatten()
+            .map(|ext| {
+                ext.value
+                    .general_names
+                    .iter()
+                    .filter_map(|name| match name {
+                        GeneralName::DNSName(s) | GeneralName::RFC822Name(s) => Some(s.to_string()),
+                        GeneralName::URI(s) => Some(s.to_string()),
+                        GeneralName::IPAddress(bytes) => ip_to_string(bytes),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Some(Self {
+            der: der.to_vec(),
+            subject: cert.subject().to_string(),
+            subject_alt_names,
+        })
+    }
+
+    /// The certificate subject as a distinguished name, e.g. `"CN=alice, O=Example"`.
+    pub fn subject(&self) -> &str {
+        &self.subject
+    }
+
+    /// The subject alternative names (DNS names, email addresses, URIs and IP addresses).
+    pub fn subject_alt_names(&self) -> &[String] {
+        &self.subject_alt_names
+    }
+
+    /// The raw DER-encoded certificate.
+    pub fn der(&self) -> &[u8] {
+        &self.der
+    }
+}
+
+/// Formats an IP address SAN, which is stored as 4 or 16 raw bytes.
+fn ip_to_string(bytes: &[u8]) -> Option<String> {
+    match bytes.len() {
+        4 => Some(std::net::Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3]).to_string()),
+        16 => {
+            let octets: [u8; 16] = bytes.try_into().ok()?;
+            Some(std::net::Ipv6Addr::from(octets).to_string())
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,198 @@
+//! The conditional and caching headers of a request, parsed together.
+
+use crate::webserver::requests::entity_tag::{EntityTag, EntityTagCondition};
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+/// The `If-Modified-Since`, `If-None-Match`, `Cache-Control`, `Range` and
+/// `If-Range` headers of a request.
+///
+/// Obtained from [`HTTPRequest::conditional_headers`](crate::webserver::requests::HTTPRequest::conditional_headers).
+/// Headers that are missing or malformed are `None` (or empty), as a server
+/// must ignore them rather than reject the request. The exception is a
+/// malformed `If-Range`, kept as [`IfRange::Invalid`] because it must still
+/// turn off the `Range` header.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConditionalHeaders {
+    /// The `If-Modified-Since` date.
+    pub if_modified_since: Option<DateTime<Utc>>,
+    /// The `If-None-Match` entity tags.
+    pub if_none_match: Option<EntityTagCondition>,
+    /// The `Cache-Control` directives, lowercased, e.g. `no-cache` or `max-age=0`.
+    pub cache_control: Vec<String>,
+    /// The `Range` header.
+    pub range: Option<RangeSpec>,
+    /// The `If-Range` header.
+    pub if_range: Option<IfRange>,
+}
+
+impl ConditionalHeaders {
+    /// Parses the raw header values.
+    pub(crate) fn parse(
+        if_modified_since: Option<&str>,
+        if_none_match: Option<&str>,
+        cache_control: Option<&str>,
+        range: Option<&str>,
+        if_range: Option<&str>,
+    ) -> Self {
+        Self {
+            if_modified_since: if_modified_since.and_then(parse_http_date),
+            if_none_match: if_none_match.and_then(EntityTagCondition::parse),
+            cache_control: cache_control
+                .map(|value| {
+                    value
+                        .split(',')
+                        .map(|directive| directive.trim().to_ascii_lowercase())
+                        .filter(|directive| !directive.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            range: range.and_then(RangeSpec::parse),
+            if_range: if_range.map(IfRange::parse),
+        }
+    }
+
+    /// `true` if `Cache-Control` contains `directive`, ignoring any `=value`.
+    ///
+    /// # Arguments
+    ///
+    /// * `directive` - The directive name, e.g. `no-cache`.
+    pub fn has_cache_directive(&self, directive: &str) -> bool {
+        self.cache_control.iter().any(|entry| {
+            entry
+                .split('=')
+                .next()
+                .is_some_and(|name| name.trim().eq_ignore_ascii_case(directive))
+        })
+    }
+}
+
+/// One range of a `Range: bytes=...` header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ByteRange {
+    /// `start-end`, both offsets inclusive.
+    FromTo(u64, u64),
+    /// `start-`: from `start` to the end of the resource.
+    From(u64),
+    /// `-length`: the last `length` bytes.
+    Suffix(u64),
+}
+
+/// A parsed `Range: bytes=...` header.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RangeSpec {
+    ranges: Vec<ByteRange>,
+}
+
+impl RangeSpec {
+    /// Parses a header value; returns `None` if it is not a well-formed
+    /// byte-range request.
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        let specs = value.trim().strip_prefix("bytes=")?;
+        let mut ranges = Vec::new();
+
+        for spec in specs.split(',') {
+            let (start, end) = spec.trim().split_once('-')?;
+            ranges.push(match (start.trim(), end.trim()) {
+                ("", "") => return None,
+                ("", suffix) => ByteRange::Suffix(suffix.parse().ok()?),
+                (start, "") => ByteRange::From(start.parse().ok()?),
+                (start, end) => {
+                    let (start, end) = (start.parse().ok()?, end.parse().ok()?);
+                    if end < start {
+                        return None;
+                    }
+                    ByteRange::FromTo(start, end)
+                }
+            });
+        }
+
+        Some(Self { ranges })
+    }
+
+    /// The ranges in the order they were requested.
+    pub fn ranges(&self) -> &[ByteRange] {
+        &self.ranges
+    }
+
+    /// Resolves the ranges against a resource of `len` bytes.
+    ///
+    /// Returns inclusive `(start, end)` byte offsets with ends clamped to the
+    /// resource. Ranges starting past the end and empty suffixes are dropped,
+    /// so an empty list means the request cannot be satisfied.
+    ///
+    /// # Arguments
+    ///
+    /// * `len` - The length of the full resource in bytes.
+    pub fn resolve(&self, len: u64) -> Vec<(u64, u64)> {
+        let last = len.saturating_sub(1);
+        self.ranges
+            .iter()
+            .filter_map(|range| match *range {
+                ByteRange::Suffix(0) => None,
+                ByteRange::Suffix(suffix) => Some((len.saturating_sub(suffix), last)),
+                ByteRange::From(start) => Some((start, last)),
+                ByteRange::FromTo(start, end) => Some((start, end.min(last))),
+            })
+            .filter(|(start, _)| *start < len)
+            .collect()
+    }
+}
+
+/// The validator of an `If-Range` header.
+///
+/// A `Range` request with `If-Range` only gets the requested ranges while the
+/// resource still has this validator; otherwise the whole resource is sent.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IfRange {
+    /// An entity tag, e.g. `"v1"`.
+    EntityTag(EntityTag),
+    /// A `Last-Modified` date.
+    Date(DateTime<Utc>),
+    /// A value that is neither a single entity tag nor a supported HTTP
+    /// date. It never matches, so the whole resource is sent.
+    Invalid,
+}
+
+impl IfRange {
+    /// Parses a header value; returns [`Invalid`](Self::Invalid) if it is
+    /// neither a single entity tag nor an HTTP date.
+    pub(crate) fn parse(value: &str) -> Self {
+        let value = value.trim();
+        if value.starts_with('"') || value.starts_with("W/") {
+            return match EntityTagCondition::parse(value) {
+                Some(EntityTagCondition::Tags(mut tags)) if tags.len() == 1 => {
+                    tags.pop().map_or(Self::Invalid, Self::EntityTag)
+                }
+                _ => Self::Invalid,
+            };
+        }
+        parse_http_date(value).map_or(Self::Invalid, Self::Date)
+    }
+
+    /// `true` if the resource still has this validator, so the ranges may be
+    /// sent.
+    ///
+    /// Entity tags must match strongly; weak tags never do. Dates must equal
+    /// `last_modified` to the second.
+    ///
+    /// # Arguments
+    ///
+    /// * `etag` - The current entity tag of the resource, without quotes.
+    /// * `last_modified` - When the resource was last modified.
+    pub fn matches(&self, etag: &str, last_modified: DateTime<Utc>) -> bool {
+        match self {
+            Self::EntityTag(tag) => !tag.is_weak() && tag.value() == etag,
+            Self::Date(date) => date.timestamp() == last_modified.timestamp(),
+            Self::Invalid => false,
+        }
+    }
+}
+
+/// Parses an HTTP date, e.g. `Sun, 06 Nov 1994 08:49:37 GMT`.
+fn parse_http_date(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    NaiveDateTime::parse_from_str(value, "%a, %d %b %Y %H:%M:%S GMT")
+        .map(|date| date.and_utc())
+        .or_else(|_| DateTime::parse_from_rfc2822(value).map(|date| date.with_timezone(&Utc)))
+        .ok()
+}
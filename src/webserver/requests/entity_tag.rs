@@ -0,0 +1,88 @@
+//! Entity tags received in a request's `If-Match` and `If-None-Match` headers.
+
+/// An entity tag listed in a conditional request header.
+///
+/// The value is stored without its quotes, matching what
+/// [`HTTPResponse::set_etag`](crate::webserver::responses::HTTPResponse::set_etag) expects.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EntityTag {
+    value: String,
+    weak: bool,
+}
+
+impl EntityTag {
+    /// The opaque tag without quotes or the `W/` prefix.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// `true` for weak tags, written as `W/"..."`.
+    pub fn is_weak(&self) -> bool {
+        self.weak
+    }
+}
+
+/// The parsed value of an `If-Match` or `If-None-Match` header.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EntityTagCondition {
+    /// `*`: matches any current representation.
+    Any,
+    /// A list of entity tags.
+    Tags(Vec<EntityTag>),
+}
+
+impl EntityTagCondition {
+    /// Parses a header value; returns `None` if it lists no valid tag.
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        if value.trim() == "*" {
+            return Some(Self::Any);
+        }
+
+        let mut tags = Vec::new();
+        let mut rest = value;
+        loop {
+            rest = rest.trim_start_matches([' ', '\t', ',']);
+            if rest.is_empty() {
+                break;
+            }
+            let (weak, quoted) = match rest.strip_prefix("W/") {
+                Some(quoted) => (true, quoted),
+                None => (false, rest),
+            };
+            let (tag, after) = quoted.strip_prefix('"')?.split_once('"')?;
+            tags.push(EntityTag {
+                value: tag.to_string(),
+                weak,
+            });
+            rest = after;
+        }
+
+        (!tags.is_empty()).then_some(Self::Tags(tags))
+    }
+
+    /// Strong comparison, as used by `If-Match`: `true` if `etag` is listed as
+    /// a strong tag, or the condition is `*`.
+    ///
+    /// # Arguments
+    ///
+    /// * `etag` - The current entity tag of the resource, without quotes.
+    pub fn matches_strong(&self, etag: &str) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Tags(tags) => tags.iter().any(|tag| !tag.weak && tag.value == etag),
+        }
+    }
+
+    /// Weak comparison, as used by `If-None-Match`: `true` if `etag` is listed
+    /// with or without `W/`, or the condition is `*`.
+    ///
+    /// # Arguments
+    ///
+    /// * `etag` - The current entity tag of the resource, without quotes.
+    pub fn matches_weak(&self, etag: &str) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Tags(tags) => tags.iter().any(|tag| tag.value == etag),
+        }
+    }
+}
@@ -4,13 +4,29 @@
 //! a strongly-typed value with helpers for headers, query strings, path
 //! parameters, url-encoded forms, JSON bodies, and cookies.
 
-use crate::webserver::Domain;
+pub mod client_certificate;
+pub mod conditional_headers;
+pub mod entity_tag;
+pub mod parse_error;
+pub mod request_cookie;
+
+use crate::webserver::client_handling::DEFAULT_MAX_BODY_SIZE;
 use crate::webserver::http_packet::HTTPMessage;
 use crate::webserver::http_packet::header::HTTPHeader;
+use crate::webserver::http_packet::header::connection::ConnectionType;
 use crate::webserver::http_packet::header::content_types::ContentType;
-use crate::webserver::http_packet::header::headers::cookie::Cookie;
+use crate::webserver::http_packet::header::content_types::application::ApplicationSubType;
+use crate::webserver::requests::client_certificate::ClientCertificate;
+use crate::webserver::requests::conditional_headers::ConditionalHeaders;
+use crate::webserver::requests::entity_tag::EntityTagCondition;
+use crate::webserver::requests::parse_error::RequestParseError;
+use crate::webserver::requests::request_cookie::RequestCookie;
 use crate::webserver::route::HTTPMethod;
+use flate2::read::{GzDecoder, ZlibDecoder};
+use serde::de::DeserializeOwned;
 use std::collections::HashMap;
+use std::io::Read;
+use std::net::SocketAddr;
 use std::str::FromStr;
 
 /// A parsed HTTP/1.1 request.
@@ -34,15 +50,67 @@ pub struct HTTPRequest {
     /// `application/json` (when `Content-Type` is set).
     pub form_params: HashMap<String, String>,
     /// Cookies sent in the `Cookie:` header.
-    pub cookie_jar: Vec<Cookie>,
+    pub cookie_jar: Vec<RequestCookie>,
+    /// Address of the connected peer, when the transport provides one.
+    pub(crate) peer_addr: Option<SocketAddr>,
+    /// Protocol negotiated via TLS ALPN, if any.
+    pub(crate) alpn_protocol: Option<String>,
+    /// Certificate presented by the client during a mutual-TLS handshake.
+    pub(crate) client_certificate: Option<ClientCertificate>,
+    /// Whether the body is at least as long as the declared `Content-Length`.
+    pub(crate) body_complete: bool,
+    /// The registered route the router matched this request to.
+    pub(crate) matched_route: Option<String>,
 }
 
 impl HTTPRequest {
+    /// Builds a request programmatically, e.g. for tests or middleware.
+    ///
+    /// The request uses `HTTP/1.1` and carries no headers. Query parameters in
+    /// `path` are parsed; all other parameter maps and the cookie jar start empty.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sunweb::webserver::requests::HTTPRequest;
+    /// use sunweb::webserver::route::HTTPMethod;
+    ///
+    /// let req = HTTPRequest::new(HTTPMethod::POST, "/search?q=rust", Some(b"payload".to_vec()));
+    /// assert_eq!(req.method, HTTPMethod::POST);
+    /// assert_eq!(req.path(), "/search?q=rust");
+    /// assert_eq!(req.query_param("q"), Some("rust".into()));
+    /// assert_eq!(req.body(), Some(&b"payload"[..]));
+    /// assert!(req.host().is_none());
+    /// assert!(req.all_cookies().is_empty());
+    /// ```
+    pub fn new(method: HTTPMethod, path: &str, body: Option<Vec<u8>>) -> Self {
+        let mut message = HTTPMessage::new("HTTP/1.1".to_string(), HTTPHeader::new(HashMap::new()));
+        message.body = body;
+
+        let mut request = Self {
+            method,
+            path: path.to_string(),
+            message,
+            query_params: HashMap::new(),
+            path_params: HashMap::new(),
+            form_params: HashMap::new(),
+            cookie_jar: Vec::new(),
+            peer_addr: None,
+            alpn_protocol: None,
+            client_certificate: None,
+            body_complete: true,
+            matched_route: None,
+        };
+
+        request.parse_query_params();
+        request
+    }
+
     /// Parses a complete HTTP/1.1 request from raw bytes.
     ///
-    /// Returns `Err(description)` on any protocol violation or unsupported
-    /// encoding.  On success, query parameters, cookies and (when applicable)
-    /// form parameters are already parsed and ready to use.
+    /// Returns a [`RequestParseError`] describing the first protocol violation
+    /// or unsupported encoding found. On success, query parameters, cookies
+    /// and (when applicable) form parameters are already parsed and ready to use.
     ///
     /// # Example
     ///
@@ -51,24 +119,171 @@ impl HTTPRequest {
     /// let req = HTTPRequest::parse(raw).unwrap();
     /// assert_eq!(req.query_param("q"), Some("rust".into()));
     /// ```
-    pub fn parse(raw_request: &[u8]) -> Result<Self, String> {
-        let request_str = String::from_utf8(raw_request.to_vec())
-            .map_err(|e| format!("Invalid UTF-8 in request: {}", e))?;
+    ///
+    /// HTTP/1.1 requests must carry a `Host` header; HTTP/1.0 requests may omit it
+    /// and are routed to the server's default domain:
+    ///
+    /// ```rust
+    /// use sunweb::webserver::requests::HTTPRequest;
+    ///
+    /// assert!(HTTPRequest::parse(b"GET / HTTP/1.1\r\n\r\n").is_err());
+    ///
+    /// let req = HTTPRequest::parse(b"GET / HTTP/1.0\r\n\r\n").unwrap();
+    /// assert!(req.host().is_none());
+    /// ```
+    ///
+    /// Each kind of malformed input is reported as its own variant:
+    ///
+    /// ```rust
+    /// use sunweb::webserver::requests::HTTPRequest;
+    /// use sunweb::webserver::requests::parse_error::RequestParseError;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    ///
+    /// let error = |raw: &[u8]| HTTPRequest::parse(raw).unwrap_err();
+    /// assert_eq!(error(b""), RequestParseError::Empty);
+    /// assert_eq!(error(b"GET /\r\n\r\n"), RequestParseError::BadRequestLine);
+    /// assert_eq!(error(b"BREW / HTTP/1.1\r\nHost: a\r\n\r\n"), RequestParseError::UnknownMethod("BREW".into()));
+    /// assert_eq!(error(b"GET / HTTP/3.0\r\nHost: a\r\n\r\n"), RequestParseError::UnsupportedVersion("HTTP/3.0".into()));
+    /// assert_eq!(error(b"GET /\xff HTTP/1.1\r\nHost: a\r\n\r\n"), RequestParseError::InvalidUtf8);
+    /// assert_eq!(error(b"GET / HTTP/1.1\r\n\r\n"), RequestParseError::MissingHost);
+    /// assert_eq!(
+    ///     error(b"POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 10\r\n\r\nabc"),
+    ///     RequestParseError::BodyTooShort { expected: 10, received: 3 },
+    /// );
+    /// assert_eq!(
+    ///     error(b"POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 3\r\nTransfer-Encoding: chunked\r\n\r\nabc"),
+    ///     RequestParseError::ConflictingLength,
+    /// );
+    /// assert_eq!(
+    ///     error(b"POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 3\r\nContent-Length: 0\r\n\r\nabc"),
+    ///     RequestParseError::ConflictingLength,
+    /// );
+    /// let chunked = b"POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n0\r\n\r\n";
+    /// assert_eq!(error(chunked), RequestParseError::UnsupportedTransferEncoding("chunked".into()));
+    ///
+    /// assert_eq!(error(b"BREW / HTTP/1.1\r\n\r\n").status_code(), StatusCode::NotImplemented);
+    /// assert_eq!(error(chunked).status_code(), StatusCode::NotImplemented);
+    /// assert_eq!(error(b"GET / HTTP/3.0\r\n\r\n").status_code(), StatusCode::HTTPVersionNotSupported);
+    /// assert_eq!(error(b"").status_code(), StatusCode::BadRequest);
+    /// ```
+    pub fn parse(raw_request: &[u8]) -> Result<Self, RequestParseError> {
+        Self::parse_complete(raw_request, false, DEFAULT_MAX_BODY_SIZE)
+    }
+
+    /// Parses a complete request like [`parse`](Self::parse), but rejects
+    /// malformed header lines instead of accepting them.
+    ///
+    /// Every header line must end in CRLF and consist of a field name made of
+    /// token characters, a colon, and a value without control characters
+    /// other than tabs. Anything else, such as a bare CR or LF that would
+    /// split a value into another header once it is echoed back, is reported
+    /// as [`RequestParseError::InvalidHeader`]. The server parses requests
+    /// this way when [`ServerConfig::set_strict_parsing`](crate::webserver::ServerConfig::set_strict_parsing)
+    /// is enabled.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sunweb::webserver::requests::HTTPRequest;
+    /// use sunweb::webserver::requests::parse_error::RequestParseError;
+    ///
+    /// let injected = b"GET / HTTP/1.1\r\nHost: a\r\nX-Name: a\nSet-Cookie: evil\r\n\r\n";
+    /// assert!(HTTPRequest::parse(injected).unwrap().get_header("Set-Cookie").is_some());
+    /// assert!(matches!(
+    ///     HTTPRequest::parse_strict(injected),
+    ///     Err(RequestParseError::InvalidHeader(_))
+    /// ));
+    ///
+    /// let error = |raw: &[u8]| HTTPRequest::parse_strict(raw).unwrap_err();
+    /// assert_eq!(
+    ///     error(b"GET / HTTP/1.1\r\nHost: a\r\nX-Name: a\rSet-Cookie: evil\r\n\r\n"),
+    ///     RequestParseError::InvalidHeader("X-Name".into()),
+    /// );
+    /// assert_eq!(
+    ///     error(b"GET / HTTP/1.1\r\nHost: a\r\nX-Name: a\0b\r\n\r\n"),
+    ///     RequestParseError::InvalidHeader("X-Name".into()),
+    /// );
+    /// assert_eq!(
+    ///     error(b"GET / HTTP/1.1\r\nHost: a\r\nX(Name): a\r\n\r\n"),
+    ///     RequestParseError::InvalidHeader("X(Name)".into()),
+    /// );
+    /// assert_eq!(
+    ///     error(b"GET / HTTP/1.1\r\nHost: a\r\nX-Name : a\r\n\r\n"),
+    ///     RequestParseError::InvalidHeader("X-Name ".into()),
+    /// );
+    /// assert_eq!(
+    ///     error(b"GET / HTTP/1.1\r\nHost: a\r\n folded\r\n\r\n"),
+    ///     RequestParseError::InvalidHeader(" folded".into()),
+    /// );
+    ///
+    /// let request = HTTPRequest::parse_strict(b"GET / HTTP/1.1\r\nHost: a\r\nX-Name:\ta b\r\n\r\n").unwrap();
+    /// assert_eq!(request.get_header("X-Name"), Some("a b".to_string()));
+    /// ```
+    pub fn parse_strict(raw_request: &[u8]) -> Result<Self, RequestParseError> {
+        Self::parse_complete(raw_request, true, DEFAULT_MAX_BODY_SIZE)
+    }
+
+    /// Parses a complete request, validating header lines when `strict` is set
+    /// and rejecting bodies that decode to more than `max_body_size` bytes.
+    pub(crate) fn parse_complete(
+        raw_request: &[u8],
+        strict: bool,
+        max_body_size: usize,
+    ) -> Result<Self, RequestParseError> {
+        let request = Self::parse_head(raw_request, strict, max_body_size)?;
+        if !request.body_complete {
+            return Err(RequestParseError::BodyTooShort {
+                expected: request.content_length().unwrap_or_default(),
+                received: request.body().map_or(0, <[u8]>::len),
+            });
+        }
+        Ok(request)
+    }
+
+    /// Parses a request whose body may not have arrived completely yet.
+    ///
+    /// Like [`parse`](Self::parse), but a body shorter than `Content-Length`
+    /// is kept as received and reported by [`is_body_complete`](Self::is_body_complete).
+    /// With `strict`, header lines are validated as in [`parse_strict`](Self::parse_strict).
+    /// A compressed body is decoded to at most `max_body_size` bytes.
+    pub(crate) fn parse_head(
+        raw_request: &[u8],
+        strict: bool,
+        max_body_size: usize,
+    ) -> Result<Self, RequestParseError> {
+        let (head, remaining) = match raw_request.windows(4).position(|w| w == b"\r\n\r\n") {
+            Some(pos) => (&raw_request[..pos + 4], &raw_request[pos + 4..]),
+            None => match raw_request.windows(2).position(|w| w == b"\n\n") {
+                Some(pos) => (&raw_request[..pos + 2], &raw_request[pos + 2..]),
+                None => (raw_request, &[][..]),
+            },
+        };
+        let request_str = std::str::from_utf8(head).map_err(|_| RequestParseError::InvalidUtf8)?;
+        if strict {
+            Self::validate_header_lines(request_str)?;
+        }
 
         let mut lines = request_str.lines();
 
-        let request_line = lines.next().ok_or("Empty request")?;
+        let request_line = lines.next().ok_or(RequestParseError::Empty)?;
         let parts: Vec<&str> = request_line.split_whitespace().collect();
         if parts.len() != 3 {
-            return Err("Invalid request line format".to_string());
+            return Err(RequestParseError::BadRequestLine);
         }
 
         let method = HTTPMethod::from_str(parts[0])
-            .map_err(|_| format!("Unknown HTTP method: {}", parts[0]))?;
+            .map_err(|_| RequestParseError::UnknownMethod(parts[0].to_string()))?;
         let path = parts[1].to_string();
         let http_version = parts[2].to_string();
+        if !matches!(
+            http_version.as_str(),
+            "HTTP/1.0" | "HTTP/1.1" | "HTTP/2" | "HTTP/2.0"
+        ) {
+            return Err(RequestParseError::UnsupportedVersion(http_version));
+        }
 
         let mut header_map = HashMap::new();
+        let mut content_length: Option<String> = None;
 
         for line in &mut lines {
             if line.is_empty() {
@@ -77,36 +292,59 @@ impl HTTPRequest {
             if let Some(colon_pos) = line.find(':') {
                 let name = line[..colon_pos].trim().to_string();
                 let value = line[colon_pos + 1..].trim().to_string();
+                if name.eq_ignore_ascii_case("Content-Length") {
+                    if content_length
+                        .as_ref()
+                        .is_some_and(|previous| *previous != value)
+                    {
+                        return Err(RequestParseError::ConflictingLength);
+                    }
+                    content_length = Some(value.clone());
+                }
                 header_map.insert(name, value);
             }
         }
 
-        let headers = HTTPHeader::new(header_map);
+        let mut headers = HTTPHeader::new(header_map);
+        if http_version == "HTTP/1.1" && headers.get_header("Host").is_none() {
+            return Err(RequestParseError::MissingHost);
+        }
+        // A request with both could be framed differently by a proxy in front
+        // of the server, which allows smuggling a second request in the body.
+        if content_length.is_some() && headers.get_header("Transfer-Encoding").is_some() {
+            return Err(RequestParseError::ConflictingLength);
+        }
+        // Without decoding the body its end is unknown, and reading on as if
+        // it were empty would treat the body as the next request.
+        if let Some(coding) = headers.get_header("Transfer-Encoding") {
+            return Err(RequestParseError::UnsupportedTransferEncoding(coding));
+        }
+        headers.connection =
+            Self::parse_connection(&http_version, headers.get_header("Connection").as_deref());
 
         // Parse body if Content-Length is present
-        let body = if let Ok(Some(content_length_str)) = headers
+        let (mut body, body_complete) = match headers
             .get_header("Content-Length")
-            .ok_or("No content length")
-            .map(|h| Some(h))
+            .and_then(|len| usize::from_str(len.trim()).ok())
         {
-            if let Ok(content_length) = usize::from_str(&content_length_str) {
-                let remaining = request_str
-                    .lines()
-                    .last()
-                    .map(|l| l.as_bytes())
-                    .unwrap_or(&[]);
-                if remaining.len() >= content_length {
-                    Some(remaining[..content_length].to_vec())
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
-        } else {
-            None
+            Some(len) if remaining.len() >= len => (Some(remaining[..len].to_vec()), true),
+            Some(_) => (Some(remaining.to_vec()), false),
+            None => (None, true),
         };
 
+        if body_complete
+            && let Some(encoding) = headers.get_header("Content-Encoding")
+            && let Some(encoded) = body.take()
+        {
+            let decoded = Self::decode_body(&encoding, encoded, max_body_size)?;
+            headers.values.retain(|name, _| {
+                !name.eq_ignore_ascii_case("Content-Encoding")
+                    && !name.eq_ignore_ascii_case("Content-Length")
+            });
+            headers.add_header("Content-Length", &decoded.len().to_string());
+            body = Some(decoded);
+        }
+
         let message = HTTPMessage {
             http_version,
             headers,
@@ -121,6 +359,11 @@ impl HTTPRequest {
             path_params: HashMap::new(),
             form_params: HashMap::new(),
             cookie_jar: Vec::new(),
+            peer_addr: None,
+            alpn_protocol: None,
+            client_certificate: None,
+            body_complete,
+            matched_route: None,
         };
 
         request.parse_query_params();
@@ -130,12 +373,207 @@ impl HTTPRequest {
         Ok(request)
     }
 
+    /// Checks that every header line of `head` ends in CRLF and is a token
+    /// field name, a colon, and a value free of control characters.
+    fn validate_header_lines(head: &str) -> Result<(), RequestParseError> {
+        let mut lines = head.split("\r\n");
+        if lines
+            .next()
+            .is_some_and(|request_line| request_line.contains(['\r', '\n']))
+        {
+            return Err(RequestParseError::BadRequestLine);
+        }
+
+        for line in lines.take_while(|line| !line.is_empty()) {
+            let Some((name, value)) = line.split_once(':') else {
+                return Err(RequestParseError::InvalidHeader(line.to_string()));
+            };
+            let is_token = !name.is_empty()
+                && name
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b));
+            let is_clean = !value.chars().any(|c| c.is_control() && c != '\t');
+            if !is_token || !is_clean {
+                return Err(RequestParseError::InvalidHeader(name.to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Undoes the codings listed in a `Content-Encoding` header.
+    ///
+    /// Codings are removed in reverse order of application. `gzip`, `x-gzip`,
+    /// `deflate` (zlib format) and `identity` are understood. Decoding stops
+    /// once the output exceeds `limit` bytes, so a small compressed body
+    /// cannot expand into an arbitrarily large one.
+    fn decode_body(
+        encoding: &str,
+        mut body: Vec<u8>,
+        limit: usize,
+    ) -> Result<Vec<u8>, RequestParseError> {
+        let max_read = u64::try_from(limit).unwrap_or(u64::MAX).saturating_add(1);
+        for coding in encoding
+            .rsplit(',')
+            .map(str::trim)
+            .filter(|c| !c.is_empty())
+        {
+            let coding = coding.to_ascii_lowercase();
+            let mut decoded = Vec::new();
+            let result = match coding.as_str() {
+                "identity" => continue,
+                "gzip" | "x-gzip" => GzDecoder::new(body.as_slice())
+                    .take(max_read)
+                    .read_to_end(&mut decoded),
+                "deflate" => ZlibDecoder::new(body.as_slice())
+                    .take(max_read)
+                    .read_to_end(&mut decoded),
+                _ => return Err(RequestParseError::UnsupportedContentEncoding(coding)),
+            };
+            if result.is_err() {
+                return Err(RequestParseError::InvalidEncodedBody(coding));
+            }
+            if decoded.len() > limit {
+                return Err(RequestParseError::BodyTooLarge { limit });
+            }
+            body = decoded;
+        }
+        Ok(body)
+    }
+
     // ===== Basic Properties =====
     /// Returns the request-target (path + optional query).
     pub fn path(&self) -> &str {
         &self.path
     }
 
+    /// Returns the request-target without its query string.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sunweb::webserver::requests::HTTPRequest;
+    /// use sunweb::webserver::route::HTTPMethod;
+    ///
+    /// let req = HTTPRequest::new(HTTPMethod::GET, "/search?q=rust", None);
+    /// assert_eq!(req.path_without_query(), "/search");
+    ///
+    /// let req = HTTPRequest::new(HTTPMethod::GET, "/search", None);
+    /// assert_eq!(req.path_without_query(), "/search");
+    /// ```
+    ///
+    /// Routes are matched on this path, so a query string does not keep a
+    /// request from matching a route exactly:
+    ///
+    /// ```
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_custom_route("/search", HTTPMethod::GET, |request, _domain| {
+    ///     HTTPResponse::with_body(StatusCode::Ok, request.path_without_query())
+    /// }, StatusCode::Ok, None);
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let send = |method: &str| {
+    ///     let raw = format!("{method} /search?q=rust HTTP/1.1\r\nHost: localhost\r\n\r\n");
+    ///     String::from_utf8(server.handle_request(raw.as_bytes(), peer)).unwrap()
+    /// };
+    ///
+    /// assert!(send("GET").ends_with("\r\n\r\n/search"));
+    /// let delete = send("DELETE");
+    /// assert!(delete.starts_with("HTTP/1.1 405"));
+    /// assert!(delete.contains("Allow: GET, OPTIONS\r\n"));
+    /// ```
+    pub fn path_without_query(&self) -> &str {
+        self.path
+            .split_once('?')
+            .map_or(self.path.as_str(), |(path, _)| path)
+    }
+
+    /// Returns the path without its query string and percent-decoded, as
+    /// routes and static files are matched against it.
+    ///
+    /// Every escape is decoded except `%2F`, which stays encoded (in upper
+    /// case) so an encoded slash never splits a path segment. Malformed escapes
+    /// are kept as they are; bytes that do not form valid UTF-8 become `U+FFFD`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sunweb::webserver::requests::HTTPRequest;
+    /// use sunweb::webserver::route::HTTPMethod;
+    ///
+    /// let req = HTTPRequest::new(HTTPMethod::GET, "/my%20files/a%2fb.txt?q=a%20b", None);
+    /// assert_eq!(req.decoded_path(), "/my files/a%2Fb.txt");
+    ///
+    /// let req = HTTPRequest::new(HTTPMethod::GET, "/caf%C3%A9/100%", None);
+    /// assert_eq!(req.decoded_path(), "/café/100%");
+    /// ```
+    ///
+    /// Routes and static files see the decoded path, while `%2F` is never
+    /// taken for a directory separator and the query string is ignored:
+    ///
+    /// ```
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let dir = tempfile::tempdir().unwrap();
+    /// std::fs::write(dir.path().join("hello world.txt"), "hi").unwrap();
+    /// std::fs::create_dir(dir.path().join("sub")).unwrap();
+    /// std::fs::write(dir.path().join("sub").join("file.txt"), "nested").unwrap();
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_custom_route("/my files", HTTPMethod::GET, |_request, _domain| {
+    ///     HTTPResponse::with_body(StatusCode::Ok, "spaced")
+    /// }, StatusCode::Ok, None);
+    /// server.add_custom_route("/a/b", HTTPMethod::GET, |_request, _domain| {
+    ///     HTTPResponse::with_body(StatusCode::Ok, "nested")
+    /// }, StatusCode::Ok, None);
+    /// server.add_static_route("/static", HTTPMethod::GET, dir.path().to_str().unwrap(), StatusCode::Ok, None);
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let get = |path: &str| {
+    ///     let raw = format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n");
+    ///     String::from_utf8(server.handle_request(raw.as_bytes(), peer)).unwrap()
+    /// };
+    ///
+    /// assert!(get("/my%20files").ends_with("\r\n\r\nspaced"));
+    /// assert!(get("/my%20files?sort=name").ends_with("\r\n\r\nspaced"));
+    /// assert!(get("/a/b").ends_with("\r\n\r\nnested"));
+    /// assert!(get("/a%2Fb").starts_with("HTTP/1.1 404"));
+    /// assert!(get("/static/hello%20world.txt").ends_with("\r\n\r\nhi"));
+    /// assert!(get("/static/hello%20world.txt?v=2").ends_with("\r\n\r\nhi"));
+    /// assert!(get("/static/sub/file.txt").ends_with("\r\n\r\nnested"));
+    /// assert!(get("/static/sub%2Ffile.txt").starts_with("HTTP/1.1 404"));
+    /// assert!(get("/static/%2E%2E/secret").starts_with("HTTP/1.1 404"));
+    /// ```
+    pub fn decoded_path(&self) -> String {
+        let bytes = self.path_without_query().as_bytes();
+        let mut decoded = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            let escaped = bytes
+                .get(i + 1..i + 3)
+                .filter(|hex| bytes[i] == b'%' && hex.iter().all(u8::is_ascii_hexdigit))
+                .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+            match escaped {
+                Some(b'/') => decoded.extend_from_slice(b"%2F"),
+                Some(byte) => decoded.push(byte),
+                None => {
+                    decoded.push(bytes[i]);
+                    i += 1;
+                    continue;
+                }
+            }
+            i += 3;
+        }
+        String::from_utf8_lossy(&decoded).into_owned()
+    }
+
     // ===== Header Operations =====
     /// Case-insensitive header lookup.
     ///
@@ -156,11 +594,51 @@ impl HTTPRequest {
         self.message.headers.get_header(name).is_some()
     }
 
+    /// `true` if the header `name` is present and its value equals `value`,
+    /// ignoring ASCII case and surrounding whitespace.
+    ///
+    /// ```
+    /// use sunweb::webserver::requests::HTTPRequest;
+    ///
+    /// let raw = b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection:  Upgrade \r\n\r\n";
+    /// let req = HTTPRequest::parse(raw).unwrap();
+    /// assert!(req.header_eq("connection", "upgrade"));
+    /// assert!(!req.header_eq("Connection", "close"));
+    /// assert!(!req.header_eq("Upgrade", "websocket"));
+    /// ```
+    pub fn header_eq(&self, name: &str, value: &str) -> bool {
+        self.get_header(name)
+            .is_some_and(|actual| actual.trim().eq_ignore_ascii_case(value.trim()))
+    }
+
+    /// `true` if the request was sent by a script, as signalled by
+    /// `X-Requested-With: XMLHttpRequest`.
+    ///
+    /// ```
+    /// use sunweb::webserver::requests::HTTPRequest;
+    ///
+    /// let request = |extra: &str| {
+    ///     let raw = format!("GET / HTTP/1.1\r\nHost: localhost\r\n{extra}\r\n");
+    ///     HTTPRequest::parse(raw.as_bytes()).unwrap()
+    /// };
+    ///
+    /// assert!(request("X-Requested-With: XMLHttpRequest\r\n").is_ajax());
+    /// assert!(request("x-requested-with: xmlhttprequest\r\n").is_ajax());
+    /// assert!(!request("").is_ajax());
+    /// assert!(!request("X-Requested-With: com.example.app\r\n").is_ajax());
+    /// ```
+    pub fn is_ajax(&self) -> bool {
+        self.header_eq("X-Requested-With", "XMLHttpRequest")
+    }
+
     /// Convenience wrapper around [`Content-Type`](ContentType) parsing.
     ///
-    /// Returns `None` when the header is missing **or** malformed.
+    /// Parameters such as `; charset=utf-8` are ignored. Returns `None` when
+    /// the header is missing **or** malformed.
     pub fn content_type(&self) -> Option<ContentType> {
-        Some(self.get_content_type())
+        let value = self.get_header("Content-Type")?;
+        let mime = value.split(';').next().unwrap_or_default().trim();
+        ContentType::from_str(&mime.to_ascii_lowercase()).ok()
     }
 
     /// Returns the parsed `Content-Length`, if present and valid.
@@ -169,16 +647,424 @@ impl HTTPRequest {
             .and_then(|s| usize::from_str(&s).ok())
     }
 
+    /// Whether the body received is as long as the declared `Content-Length`.
+    ///
+    /// Only the request passed to a
+    /// [continue hook](crate::webserver::ServerConfig::set_continue_hook) can be
+    /// incomplete, since the hook runs before the body is sent.
+    /// [`parse`](Self::parse) rejects a body shorter than it was announced with
+    /// [`RequestParseError::BodyTooShort`], which the server answers with
+    /// `400 Bad Request` before the request reaches a handler. Requests without
+    /// a `Content-Length` are always complete.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sunweb::webserver::requests::HTTPRequest;
+    /// use sunweb::webserver::requests::parse_error::RequestParseError;
+    ///
+    /// let raw = b"POST /upload HTTP/1.1\r\nHost: localhost\r\nContent-Length: 10\r\n\r\nabc";
+    /// assert_eq!(
+    ///     HTTPRequest::parse(raw).unwrap_err(),
+    ///     RequestParseError::BodyTooShort { expected: 10, received: 3 },
+    /// );
+    ///
+    /// let raw = b"POST /upload HTTP/1.1\r\nHost: localhost\r\nContent-Length: 3\r\n\r\nabc";
+    /// assert!(HTTPRequest::parse(raw).unwrap().is_body_complete());
+    /// ```
+    pub fn is_body_complete(&self) -> bool {
+        self.body_complete
+    }
+
+    /// Whether the client wants the connection kept open after the response.
+    ///
+    /// An explicit `Connection: close` or `Connection: keep-alive` decides;
+    /// otherwise HTTP/1.1 defaults to keep-alive and HTTP/1.0 to close. The
+    /// server closes the connection after answering a request for which this
+    /// is `false`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sunweb::webserver::requests::HTTPRequest;
+    ///
+    /// assert!(!HTTPRequest::parse(b"GET / HTTP/1.0\r\n\r\n").unwrap().keep_alive());
+    ///
+    /// let raw = b"GET / HTTP/1.0\r\nConnection: Keep-Alive\r\n\r\n";
+    /// assert!(HTTPRequest::parse(raw).unwrap().keep_alive());
+    ///
+    /// let raw = b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n";
+    /// assert!(HTTPRequest::parse(raw).unwrap().keep_alive());
+    ///
+    /// let raw = b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n";
+    /// assert!(!HTTPRequest::parse(raw).unwrap().keep_alive());
+    /// ```
+    ///
+    /// An HTTP/1.0 client is answered with `Connection: close` and the server
+    /// hangs up, unless it asked for keep-alive:
+    ///
+    /// ```rust
+    /// use std::io::{Read, Write};
+    /// use std::net::{TcpListener, TcpStream};
+    /// use std::thread;
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 0));
+    /// server.add_custom_route("/", HTTPMethod::GET, |_request, _domain| {
+    ///     HTTPResponse::with_body(StatusCode::Ok, "ok")
+    /// }, StatusCode::Ok, None);
+    ///
+    /// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    /// let addr = listener.local_addr().unwrap();
+    /// thread::spawn(move || {
+    ///     for stream in listener.incoming().take(2) {
+    ///         server.serve_connection(stream.unwrap());
+    ///     }
+    /// });
+    ///
+    /// let mut client = TcpStream::connect(addr).unwrap();
+    /// client.write_all(b"GET / HTTP/1.0\r\n\r\n").unwrap();
+    /// let mut response = String::new();
+    /// client.read_to_string(&mut response).unwrap();
+    /// assert!(response.contains("Connection: close\r\n"));
+    ///
+    /// let mut client = TcpStream::connect(addr).unwrap();
+    /// client.write_all(b"GET / HTTP/1.0\r\nConnection: keep-alive\r\n\r\n").unwrap();
+    /// let mut first = Vec::new();
+    /// while !first.ends_with(b"ok") {
+    ///     let mut chunk = [0u8; 1024];
+    ///     let n = client.read(&mut chunk).unwrap();
+    ///     first.extend_from_slice(&chunk[..n]);
+    /// }
+    /// assert!(String::from_utf8_lossy(&first).contains("Connection: keep-alive\r\n"));
+    ///
+    /// // The connection is still open for a second request.
+    /// client.write_all(b"GET / HTTP/1.0\r\n\r\n").unwrap();
+    /// let mut second = String::new();
+    /// client.read_to_string(&mut second).unwrap();
+    /// assert!(second.ends_with("ok"));
+    /// ```
+    pub fn keep_alive(&self) -> bool {
+        self.message.headers.connection == ConnectionType::KeepAlive
+    }
+
+    /// The parsed `If-Match` header, used for optimistic concurrency on writes.
+    ///
+    /// Returns `None` when the header is missing or lists no valid tag. A
+    /// handler should answer `412 Precondition Failed` when the condition does
+    /// not [strongly match](EntityTagCondition::matches_strong) the resource's
+    /// current entity tag.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_custom_route("/doc", HTTPMethod::PUT, |request, _domain| {
+    ///     let current = "v2";
+    ///     if request.if_match().is_some_and(|condition| !condition.matches_strong(current)) {
+    ///         return HTTPResponse::new(StatusCode::PreconditionFailed);
+    ///     }
+    ///     let mut response = HTTPResponse::no_content();
+    ///     response.set_etag("v3");
+    ///     response
+    /// }, StatusCode::Ok, None);
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let put = |if_match: &str| {
+    ///     let raw = format!("PUT /doc HTTP/1.1\r\nHost: localhost\r\nIf-Match: {if_match}\r\n\r\n");
+    ///     String::from_utf8(server.handle_request(raw.as_bytes(), peer)).unwrap()
+    /// };
+    /// assert!(put("\"v1\"").starts_with("HTTP/1.1 412 Precondition Failed"));
+    /// assert!(put("W/\"v2\"").starts_with("HTTP/1.1 412 Precondition Failed"));
+    /// assert!(put("\"v1\", \"v2\"").starts_with("HTTP/1.1 204 No Content"));
+    /// assert!(put("*").starts_with("HTTP/1.1 204 No Content"));
+    /// ```
+    pub fn if_match(&self) -> Option<EntityTagCondition> {
+        EntityTagCondition::parse(&self.get_header("If-Match")?)
+    }
+
+    /// The parsed `If-None-Match` header.
+    ///
+    /// Returns `None` when the header is missing or lists no valid tag. Compare
+    /// it with [`matches_weak`](EntityTagCondition::matches_weak): a `GET`
+    /// whose condition matches is answered with `304 Not Modified`, a write
+    /// with `412 Precondition Failed`. `If-None-Match: *` on a `PUT` asks to
+    /// create the resource only if it does not exist yet.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sunweb::webserver::requests::HTTPRequest;
+    /// use sunweb::webserver::requests::entity_tag::EntityTagCondition;
+    ///
+    /// let raw = b"GET / HTTP/1.1\r\nHost: localhost\r\nIf-None-Match: W/\"a\", \"b,c\"\r\n\r\n";
+    /// let condition = HTTPRequest::parse(raw).unwrap().if_none_match().unwrap();
+    /// let EntityTagCondition::Tags(tags) = &condition else { panic!() };
+    /// assert_eq!((tags[0].value(), tags[0].is_weak()), ("a", true));
+    /// assert_eq!((tags[1].value(), tags[1].is_weak()), ("b,c", false));
+    /// assert!(condition.matches_weak("a"));
+    /// assert!(!condition.matches_strong("a"));
+    /// assert!(!condition.matches_weak("c"));
+    ///
+    /// let raw = b"PUT / HTTP/1.1\r\nHost: localhost\r\nIf-None-Match: *\r\n\r\n";
+    /// assert_eq!(HTTPRequest::parse(raw).unwrap().if_none_match(), Some(EntityTagCondition::Any));
+    ///
+    /// let raw = b"GET / HTTP/1.1\r\nHost: localhost\r\nIf-None-Match: unquoted\r\n\r\n";
+    /// assert_eq!(HTTPRequest::parse(raw).unwrap().if_none_match(), None);
+    /// ```
+    pub fn if_none_match(&self) -> Option<EntityTagCondition> {
+        EntityTagCondition::parse(&self.get_header("If-None-Match")?)
+    }
+
+    /// The `If-Modified-Since`, `If-None-Match`, `Cache-Control`, `Range` and
+    /// `If-Range` headers, parsed in one go.
+    ///
+    /// Malformed headers are left out, as they must be ignored, except for
+    /// `If-Range`, which is kept as [`IfRange::Invalid`](crate::webserver::requests::conditional_headers::IfRange::Invalid).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use chrono::{TimeZone, Utc};
+    /// use sunweb::webserver::requests::HTTPRequest;
+    /// use sunweb::webserver::requests::conditional_headers::{ByteRange, IfRange};
+    ///
+    /// let raw = b"GET /video.mp4 HTTP/1.1\r\nHost: localhost\r\n\
+    ///     If-Modified-Since: Sun, 06 Nov 1994 08:49:37 GMT\r\n\
+    ///     If-None-Match: \"v1\", W/\"v2\"\r\n\
+    ///     Cache-Control: No-Cache, max-age=0\r\n\
+    ///     Range: bytes=0-99, 500-, -10\r\nIf-Range: \"v1\"\r\n\r\n";
+    /// let headers = HTTPRequest::parse(raw).unwrap().conditional_headers();
+    ///
+    /// assert_eq!(headers.if_modified_since, Some(Utc.with_ymd_and_hms(1994, 11, 6, 8, 49, 37).unwrap()));
+    /// assert!(headers.if_none_match.as_ref().unwrap().matches_weak("v2"));
+    /// assert_eq!(headers.cache_control, ["no-cache", "max-age=0"]);
+    /// assert!(headers.has_cache_directive("max-age"));
+    /// assert!(!headers.has_cache_directive("no-store"));
+    ///
+    /// let range = headers.range.unwrap();
+    /// assert_eq!(range.ranges(), [ByteRange::FromTo(0, 99), ByteRange::From(500), ByteRange::Suffix(10)]);
+    /// assert_eq!(range.resolve(1000), [(0, 99), (500, 999), (990, 999)]);
+    /// assert_eq!(range.resolve(400), [(0, 99), (390, 399)]);
+    /// let modified = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    /// assert!(headers.if_range.as_ref().unwrap().matches("v1", modified));
+    /// assert!(!headers.if_range.as_ref().unwrap().matches("v2", modified));
+    ///
+    /// let raw = b"GET / HTTP/1.1\r\nHost: localhost\r\n\
+    ///     If-Modified-Since: yesterday\r\nRange: lines=1-2\r\nIf-Range: \"a\", \"b\"\r\n\r\n";
+    /// let headers = HTTPRequest::parse(raw).unwrap().conditional_headers();
+    /// assert_eq!(headers.if_modified_since, None);
+    /// assert_eq!(headers.if_none_match, None);
+    /// assert!(headers.cache_control.is_empty());
+    /// assert_eq!(headers.range, None);
+    /// assert_eq!(headers.if_range, Some(IfRange::Invalid));
+    /// ```
+    pub fn conditional_headers(&self) -> ConditionalHeaders {
+        ConditionalHeaders::parse(
+            self.get_header("If-Modified-Since").as_deref(),
+            self.get_header("If-None-Match").as_deref(),
+            self.get_header("Cache-Control").as_deref(),
+            self.get_header("Range").as_deref(),
+            self.get_header("If-Range").as_deref(),
+        )
+    }
+
     /// Shorthand for `User-Agent` header.
     pub fn user_agent(&self) -> Option<String> {
         self.get_header("User-Agent")
     }
 
-    /// Shorthand for `Host` header.
+    /// The `Host` header without its port, lowercased.
+    ///
+    /// This is the name used for domain routing and cookie domains. Bracketed
+    /// IPv6 literals keep their brackets.
+    ///
+    /// ```
+    /// use sunweb::webserver::requests::HTTPRequest;
+    ///
+    /// let host = |value: &str| {
+    ///     let raw = format!("GET / HTTP/1.1\r\nHost: {value}\r\n\r\n");
+    ///     let req = HTTPRequest::parse(raw.as_bytes()).unwrap();
+    ///     (req.host(), req.host_with_port())
+    /// };
+    ///
+    /// assert_eq!(host("example.com"), (Some("example.com".into()), Some("example.com".into())));
+    /// assert_eq!(host("Example.com:8080"), (Some("example.com".into()), Some("Example.com:8080".into())));
+    /// assert_eq!(host("[::1]:8080"), (Some("[::1]".into()), Some("[::1]:8080".into())));
+    /// ```
     pub fn host(&self) -> Option<String> {
+        self.host_with_port().map(|host| {
+            let name = match host.strip_prefix('[') {
+                Some(rest) => rest
+                    .split_once(']')
+                    .map_or(host.as_str(), |(addr, _)| &host[..addr.len() + 2]),
+                None => host.split(':').next().unwrap_or(&host),
+            };
+            name.to_lowercase()
+        })
+    }
+
+    /// The raw `Host` header value, including any `:port`.
+    pub fn host_with_port(&self) -> Option<String> {
         self.get_header("Host")
     }
 
+    /// Address of the client that sent the request.
+    ///
+    /// `None` for transports without a socket address, such as Unix sockets.
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        self.peer_addr
+    }
+
+    /// Protocol negotiated with the client via TLS ALPN.
+    ///
+    /// `None` for plain HTTP connections, or when the client offered none of the
+    /// protocols configured with [`ServerConfig::set_alpn_protocols`](crate::webserver::ServerConfig::set_alpn_protocols).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Read, Write};
+    /// use std::net::{TcpListener, TcpStream};
+    /// use std::sync::Arc;
+    /// use std::thread;
+    /// use rustls::pki_types::ServerName;
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    /// let dir = tempfile::tempdir().unwrap();
+    /// let key_path = dir.path().join("key.pem");
+    /// let cert_path = dir.path().join("cert.pem");
+    /// std::fs::write(&key_path, cert.signing_key.serialize_pem()).unwrap();
+    /// std::fs::write(&cert_path, cert.cert.pem()).unwrap();
+    ///
+    /// let config = ServerConfig::new([127, 0, 0, 1], 0)
+    ///     .add_cert(key_path.display().to_string(), cert_path.display().to_string())
+    ///     .set_alpn_protocols(&["http/1.1"]);
+    /// let mut server = WebServer::new(config);
+    /// server.add_custom_route("/", HTTPMethod::GET, |request, _domain| {
+    ///     HTTPResponse::with_body(StatusCode::Ok, request.alpn_protocol().unwrap_or_default())
+    /// }, StatusCode::Ok, None);
+    ///
+    /// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    /// let addr = listener.local_addr().unwrap();
+    /// thread::spawn(move || {
+    ///     let (stream, _) = listener.accept().unwrap();
+    ///     server.serve_connection(stream);
+    /// });
+    ///
+    /// let mut roots = rustls::RootCertStore::empty();
+    /// roots.add(cert.cert.der().clone()).unwrap();
+    /// let mut client_config = rustls::ClientConfig::builder()
+    ///     .with_root_certificates(roots)
+    ///     .with_no_client_auth();
+    /// client_config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    ///
+    /// let conn = rustls::ClientConnection::new(
+    ///     Arc::new(client_config),
+    ///     ServerName::try_from("localhost").unwrap(),
+    /// ).unwrap();
+    /// let mut tls = rustls::StreamOwned::new(conn, TcpStream::connect(addr).unwrap());
+    /// tls.write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n").unwrap();
+    /// assert_eq!(tls.conn.alpn_protocol(), Some(&b"http/1.1"[..]));
+    ///
+    /// let mut response = Vec::new();
+    /// let _ = tls.read_to_end(&mut response);
+    /// assert!(String::from_utf8_lossy(&response).ends_with("http/1.1"));
+    /// ```
+    pub fn alpn_protocol(&self) -> Option<&str> {
+        self.alpn_protocol.as_deref()
+    }
+
+    /// Certificate presented by the client during a mutual-TLS handshake.
+    ///
+    /// `None` for plain HTTP connections, when client authentication is not
+    /// configured, or when [`ServerConfig::allow_client_cert`](crate::webserver::ServerConfig::allow_client_cert)
+    /// is used and the client sent no certificate.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io::{Read, Write};
+    /// use std::net::{TcpListener, TcpStream};
+    /// use std::sync::Arc;
+    /// use std::thread;
+    /// use rcgen::{BasicConstraints, CertificateParams, CertifiedIssuer, DnType, IsCa, KeyPair};
+    /// use rustls::pki_types::{PrivateKeyDer, ServerName};
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let mut ca_params = CertificateParams::new(Vec::new()).unwrap();
+    /// ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    /// let ca = CertifiedIssuer::self_signed(ca_params, KeyPair::generate().unwrap()).unwrap();
+    ///
+    /// let mut client_params = CertificateParams::new(vec!["alice.example".to_string()]).unwrap();
+    /// client_params.distinguished_name.push(DnType::CommonName, "alice");
+    /// let client_key = KeyPair::generate().unwrap();
+    /// let client_cert = client_params.signed_by(&client_key, &ca).unwrap();
+    ///
+    /// let server_cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    /// let dir = tempfile::tempdir().unwrap();
+    /// let path = |name: &str| dir.path().join(name).display().to_string();
+    /// std::fs::write(path("key.pem"), server_cert.signing_key.serialize_pem()).unwrap();
+    /// std::fs::write(path("cert.pem"), server_cert.cert.pem()).unwrap();
+    /// std::fs::write(path("ca.pem"), ca.pem()).unwrap();
+    ///
+    /// let config = ServerConfig::new([127, 0, 0, 1], 0)
+    ///     .add_cert(path("key.pem"), path("cert.pem"))
+    ///     .require_client_cert(path("ca.pem"));
+    /// let mut server = WebServer::new(config);
+    /// server.add_custom_route("/", HTTPMethod::GET, |request, _domain| {
+    ///     let cert = request.client_certificate().unwrap();
+    ///     let body = format!("{} {}", cert.subject(), cert.subject_alt_names().join(","));
+    ///     HTTPResponse::with_body(StatusCode::Ok, body)
+    /// }, StatusCode::Ok, None);
+    ///
+    /// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    /// let addr = listener.local_addr().unwrap();
+    /// thread::spawn(move || {
+    ///     let (stream, _) = listener.accept().unwrap();
+    ///     server.serve_connection(stream);
+    /// });
+    ///
+    /// let mut roots = rustls::RootCertStore::empty();
+    /// roots.add(server_cert.cert.der().clone()).unwrap();
+    /// let client_config = rustls::ClientConfig::builder()
+    ///     .with_root_certificates(roots)
+    ///     .with_client_auth_cert(
+    ///         vec![client_cert.der().clone()],
+    ///         PrivateKeyDer::try_from(client_key.serialize_der()).unwrap(),
+    ///     )
+    ///     .unwrap();
+    ///
+    /// let conn = rustls::ClientConnection::new(
+    ///     Arc::new(client_config),
+    ///     ServerName::try_from("localhost").unwrap(),
+    /// ).unwrap();
+    /// let mut tls = rustls::StreamOwned::new(conn, TcpStream::connect(addr).unwrap());
+    /// tls.write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n").unwrap();
+    ///
+    /// let mut response = Vec::new();
+    /// let _ = tls.read_to_end(&mut response);
+    /// assert!(String::from_utf8_lossy(&response).ends_with("CN=alice alice.example"));
+    /// ```
+    pub fn client_certificate(&self) -> Option<&ClientCertificate> {
+        self.client_certificate.as_ref()
+    }
+
     /// Shorthand for `Authorization` header.
     pub fn authorization(&self) -> Option<String> {
         self.get_header("Authorization")
@@ -187,6 +1073,108 @@ impl HTTPRequest {
     // ===== Body Operations =====
 
     /// View into the raw body, if any.
+    ///
+    /// A body sent with `Content-Encoding: gzip` or `deflate` is decompressed
+    /// while parsing; the request then carries the decoded body, a matching
+    /// `Content-Length` and no `Content-Encoding` header. Other encodings are
+    /// rejected with `415 Unsupported Media Type`. The server answers bodies
+    /// that decompress past the limit set with
+    /// [`ServerConfig::set_max_body_size`](crate::webserver::ServerConfig::set_max_body_size)
+    /// with `413 Content Too Large`; `parse` applies the default limit of 10 MiB.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use flate2::Compression;
+    /// use flate2::write::GzEncoder;
+    /// use std::io::Write;
+    /// use sunweb::webserver::requests::HTTPRequest;
+    /// use sunweb::webserver::requests::parse_error::RequestParseError;
+    ///
+    /// let json = br#"{"name":"Bob"}"#;
+    /// let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    /// encoder.write_all(json).unwrap();
+    /// let gzipped = encoder.finish().unwrap();
+    ///
+    /// let mut raw = format!(
+    ///     "POST /people HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\n\
+    ///      Content-Encoding: gzip\r\nContent-Length: {}\r\n\r\n",
+    ///     gzipped.len()
+    /// )
+    /// .into_bytes();
+    /// raw.extend_from_slice(&gzipped);
+    ///
+    /// let req = HTTPRequest::parse(&raw).unwrap();
+    /// assert_eq!(req.body(), Some(&json[..]));
+    /// assert_eq!(req.content_length(), Some(json.len()));
+    /// assert_eq!(req.get_header("Content-Encoding"), None);
+    ///
+    /// let raw = b"POST / HTTP/1.1\r\nHost: localhost\r\nContent-Encoding: br\r\nContent-Length: 3\r\n\r\nabc";
+    /// let error = HTTPRequest::parse(raw).unwrap_err();
+    /// assert_eq!(error, RequestParseError::UnsupportedContentEncoding("br".into()));
+    /// assert_eq!(error.status_code().as_u16(), 415);
+    ///
+    /// // 11 MiB of zeros compress to a few kilobytes but decode past the default limit.
+    /// let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    /// encoder.write_all(&vec![0u8; 11 * 1024 * 1024]).unwrap();
+    /// let bomb = encoder.finish().unwrap();
+    /// let mut raw = format!(
+    ///     "POST / HTTP/1.1\r\nHost: localhost\r\nContent-Encoding: gzip\r\nContent-Length: {}\r\n\r\n",
+    ///     bomb.len()
+    /// )
+    /// .into_bytes();
+    /// raw.extend_from_slice(&bomb);
+    /// assert_eq!(HTTPRequest::parse(&raw).unwrap_err().status_code().as_u16(), 413);
+    /// ```
+    ///
+    /// The compressed bytes are read from the socket as they are, so bodies
+    /// that are not valid UTF-8 arrive intact:
+    ///
+    /// ```
+    /// use flate2::Compression;
+    /// use flate2::write::GzEncoder;
+    /// use std::io::{Read, Write};
+    /// use std::net::{TcpListener, TcpStream};
+    /// use std::thread;
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 0));
+    /// server.add_custom_route("/echo", HTTPMethod::POST, |request, _domain| {
+    ///     HTTPResponse::with_body(StatusCode::Ok, request.body_string().unwrap_or_default())
+    /// }, StatusCode::Ok, None);
+    ///
+    /// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    /// let addr = listener.local_addr().unwrap();
+    /// let serving = thread::spawn(move || {
+    ///     let (stream, _) = listener.accept().unwrap();
+    ///     server.serve_connection(stream);
+    /// });
+    ///
+    /// let text = "hello over a real socket ".repeat(100);
+    /// let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    /// encoder.write_all(text.as_bytes()).unwrap();
+    /// let gzipped = encoder.finish().unwrap();
+    /// assert!(std::str::from_utf8(&gzipped).is_err());
+    ///
+    /// let mut client = TcpStream::connect(addr).unwrap();
+    /// write!(
+    ///     client,
+    ///     "POST /echo HTTP/1.1\r\nHost: localhost\r\nContent-Encoding: gzip\r\n\
+    ///      Content-Length: {}\r\nConnection: close\r\n\r\n",
+    ///     gzipped.len()
+    /// )
+    /// .unwrap();
+    /// client.write_all(&gzipped).unwrap();
+    /// let mut response = String::new();
+    /// client.read_to_string(&mut response).unwrap();
+    /// serving.join().unwrap();
+    ///
+    /// assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    /// assert!(response.ends_with(&text));
+    /// ```
     pub fn body(&self) -> Option<&[u8]> {
         self.message.body.as_deref()
     }
@@ -205,10 +1193,23 @@ impl HTTPRequest {
     }
 
     /// Panic-free version of [`content_type`](Self::content_type) that returns
-    /// [`ContentType::OctetStream`](crate::webserver::http_packet::header::content_types::ContentType::OctetStream)
-    /// when the header is missing.
+    /// [`ContentType::octet_stream`] when the header is missing or malformed.
+    ///
+    /// ```
+    /// use sunweb::webserver::http_packet::header::content_types::ContentType;
+    /// use sunweb::webserver::http_packet::header::content_types::application::ApplicationSubType;
+    /// use sunweb::webserver::requests::HTTPRequest;
+    ///
+    /// let raw = b"POST / HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json; charset=utf-8\r\n\r\n";
+    /// let req = HTTPRequest::parse(raw).unwrap();
+    /// assert_eq!(req.get_content_type(), ContentType::Application(ApplicationSubType::Json));
+    ///
+    /// let req = HTTPRequest::parse(b"POST / HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+    /// assert_eq!(req.get_content_type(), ContentType::octet_stream());
+    /// ```
     pub fn get_content_type(&self) -> ContentType {
-        self.content_type().unwrap()
+        self.content_type()
+            .unwrap_or_else(ContentType::octet_stream)
     }
 
     // ===== Query Parameters =====
@@ -256,6 +1257,43 @@ impl HTTPRequest {
         &self.query_params
     }
 
+    /// Deserializes the query string into `T`.
+    ///
+    /// Values are URL-decoded and parsed into the field types. Returns
+    /// `Err(description)` if a required field is missing or a value cannot be
+    /// parsed; use `Option` fields for optional parameters.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use serde::Deserialize;
+    /// use sunweb::webserver::requests::HTTPRequest;
+    /// use sunweb::webserver::route::HTTPMethod;
+    ///
+    /// #[derive(Deserialize, Debug, PartialEq)]
+    /// struct Page {
+    ///     page: u32,
+    ///     size: u32,
+    ///     sort: Option<String>,
+    /// }
+    ///
+    /// let req = HTTPRequest::new(HTTPMethod::GET, "/items?page=2&size=50", None);
+    /// assert_eq!(req.query::<Page>(), Ok(Page { page: 2, size: 50, sort: None }));
+    ///
+    /// let req = HTTPRequest::new(HTTPMethod::GET, "/items?page=2", None);
+    /// assert!(req.query::<Page>().unwrap_err().contains("missing field `size`"));
+    ///
+    /// let req = HTTPRequest::new(HTTPMethod::GET, "/items?page=two&size=50", None);
+    /// assert!(req.query::<Page>().is_err());
+    /// ```
+    pub fn query<T: DeserializeOwned>(&self) -> Result<T, String> {
+        let query = self
+            .path
+            .split_once('?')
+            .map_or("", |(_, query)| query.split('#').next().unwrap_or_default());
+        serde_urlencoded::from_str(query).map_err(|e| format!("Invalid query string: {}", e))
+    }
+
     // ===== Path Parameters =====
 
     /// Extracts a path segment by name (`/users/:id`).
@@ -280,6 +1318,35 @@ impl HTTPRequest {
         &self.path_params
     }
 
+    /// The route pattern the request was routed by, as it was registered.
+    ///
+    /// For a request to `/users/42` handled by a route added for `/users`
+    /// this is `/users`, which groups requests in logs better than the
+    /// concrete path. `None` until the request has been routed, e.g. in
+    /// request middleware, and for requests no route matched.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_custom_route("/users", HTTPMethod::GET, |request, _domain| {
+    ///     let pattern = request.matched_route_pattern().unwrap_or_default().to_string();
+    ///     HTTPResponse::with_body(StatusCode::Ok, pattern)
+    /// }, StatusCode::Ok, None);
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let response = server.handle_request(b"GET /users/42?tab=posts HTTP/1.1\r\nHost: localhost\r\n\r\n", peer);
+    /// assert!(response.ends_with(b"\r\n\r\n/users"));
+    /// ```
+    pub fn matched_route_pattern(&self) -> Option<&str> {
+        self.matched_route.as_deref()
+    }
+
     // ===== Form Parameters =====
 
     /// Value from `application/x-www-form-urlencoded` **or** JSON body.
@@ -299,27 +1366,103 @@ impl HTTPRequest {
         &self.form_params
     }
 
+    /// Deserializes an `application/x-www-form-urlencoded` body into `T`.
+    ///
+    /// Returns `Err(description)` if the request is not a url-encoded form, the
+    /// body is not valid UTF-8, a required field is missing or a value cannot
+    /// be parsed; use `Option` fields for optional values.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use serde::Deserialize;
+    /// use sunweb::webserver::requests::HTTPRequest;
+    ///
+    /// #[derive(Deserialize, Debug, PartialEq)]
+    /// struct Person {
+    ///     name: String,
+    ///     age: u8,
+    /// }
+    ///
+    /// let raw = b"POST /people HTTP/1.1\r\nHost: localhost\r\n\
+    ///     Content-Type: application/x-www-form-urlencoded\r\nContent-Length: 15\r\n\r\nname=Bob&age=30";
+    /// let req = HTTPRequest::parse(raw).unwrap();
+    /// assert_eq!(req.form::<Person>(), Ok(Person { name: "Bob".into(), age: 30 }));
+    ///
+    /// let raw = b"POST /people HTTP/1.1\r\nHost: localhost\r\n\
+    ///     Content-Type: application/json\r\nContent-Length: 12\r\n\r\n{\"name\":\"B\"}";
+    /// let req = HTTPRequest::parse(raw).unwrap();
+    /// assert!(req.form::<Person>().unwrap_err().contains("application/x-www-form-urlencoded"));
+    /// ```
+    pub fn form<T: DeserializeOwned>(&self) -> Result<T, String> {
+        match self.content_type() {
+            Some(ContentType::Application(ApplicationSubType::XWwwFormUrlEncoded)) => {}
+            other => {
+                return Err(format!(
+                    "Expected Content-Type application/x-www-form-urlencoded, got {}",
+                    other.map_or_else(
+                        || "none".to_string(),
+                        |content_type| content_type.to_string()
+                    )
+                ));
+            }
+        }
+        let body = std::str::from_utf8(self.body().unwrap_or_default())
+            .map_err(|e| format!("Invalid UTF-8 in form body: {}", e))?;
+        serde_urlencoded::from_str(body).map_err(|e| format!("Invalid form body: {}", e))
+    }
+
     // ===== Cookies =====
 
     /// Finds the first cookie whose name matches.
     ///
-    /// **Note:** The current implementation is intentionally simplified and
-    /// returns `Some(true)` when the cookie **exists**; this will be replaced
-    /// with `Option<Cookie>` in the next breaking release.
-    pub fn cookie(&self, name: &str) -> Option<Cookie> {
-        Some(
-            self.cookie_jar
-                .iter()
-                .map(|cookie: &Cookie| cookie.key == name)
-                .collect(),
-        )
+    /// # Example
+    ///
+    /// ```
+    /// use sunweb::webserver::requests::HTTPRequest;
+    ///
+    /// let raw = b"GET / HTTP/1.1\r\nHost: localhost\r\nCookie: session=abc123; theme=dark\r\n\r\n";
+    /// let req = HTTPRequest::parse(raw).unwrap();
+    /// assert_eq!(req.cookie("theme").map(|c| c.value()), Some("dark"));
+    /// assert_eq!(req.all_cookies()[0].name(), "session");
+    /// assert!(req.cookie("missing").is_none());
+    /// ```
+    pub fn cookie(&self, name: &str) -> Option<&RequestCookie> {
+        self.cookie_jar.iter().find(|cookie| cookie.name() == name)
     }
 
-    /// All cookies sent by the client.
-    pub fn all_cookies(&self) -> &Vec<Cookie> {
+    /// All cookies sent by the client, in header order.
+    pub fn all_cookies(&self) -> &[RequestCookie] {
         &self.cookie_jar
     }
 
+    /// All cookies as a name to value map.
+    ///
+    /// If a name was sent more than once, the first value wins, matching
+    /// [`cookie`](Self::cookie).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sunweb::webserver::requests::HTTPRequest;
+    ///
+    /// let raw = b"GET / HTTP/1.1\r\nHost: localhost\r\nCookie: session=abc123; theme=dark; session=stale\r\n\r\n";
+    /// let req = HTTPRequest::parse(raw).unwrap();
+    /// let cookies = req.cookies_map();
+    /// assert_eq!(cookies.len(), 2);
+    /// assert_eq!(cookies["session"], "abc123");
+    /// assert_eq!(cookies["theme"], "dark");
+    /// ```
+    pub fn cookies_map(&self) -> HashMap<String, String> {
+        let mut cookies = HashMap::new();
+        for cookie in &self.cookie_jar {
+            cookies
+                .entry(cookie.name().to_string())
+                .or_insert_with(|| cookie.value().to_string());
+        }
+        cookies
+    }
+
     /// `true` if a cookie with the given name exists.
     pub fn has_cookie(&self, name: &str) -> bool {
         self.cookie(name).is_some()
@@ -332,6 +1475,23 @@ impl HTTPRequest {
 
     // ===== Parsing Private Methods =====
 
+    /// The effective connection directive for a request with the given
+    /// version and `Connection` header.
+    fn parse_connection(http_version: &str, header: Option<&str>) -> ConnectionType {
+        let tokens: Vec<String> = header
+            .unwrap_or_default()
+            .split(',')
+            .map(|token| token.trim().to_ascii_lowercase())
+            .collect();
+        if tokens.iter().any(|token| token == "close") {
+            ConnectionType::Close
+        } else if tokens.iter().any(|token| token == "keep-alive") || http_version != "HTTP/1.0" {
+            ConnectionType::KeepAlive
+        } else {
+            ConnectionType::Close
+        }
+    }
+
     fn parse_query_params(&mut self) {
         if let Some(query_start) = self.path.find('?') {
             let query_string = &self.path[query_start + 1..];
@@ -354,11 +1514,7 @@ impl HTTPRequest {
                 if let Some(eq_pos) = cookie.find('=') {
                     let key = cookie[..eq_pos].trim().to_string();
                     let value = cookie[eq_pos + 1..].trim().to_string();
-                    self.cookie_jar.push(Cookie::new(
-                        &*key,
-                        &*value,
-                        &Domain::new(self.host().unwrap().as_str()),
-                    ));
+                    self.cookie_jar.push(RequestCookie::new(&key, &value));
                 }
             }
         }
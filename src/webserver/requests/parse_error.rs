@@ -0,0 +1,104 @@
+//! Errors returned when raw bytes are not a valid HTTP request.
+
+use crate::webserver::responses::status_code::StatusCode;
+use std::error::Error;
+use std::fmt;
+
+/// Why [`HTTPRequest::parse`](crate::webserver::requests::HTTPRequest::parse)
+/// rejected a request.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RequestParseError {
+    /// The request contained no request line.
+    Empty,
+    /// The request line is not `METHOD target VERSION`.
+    BadRequestLine,
+    /// The method is not one the server knows.
+    UnknownMethod(String),
+    /// The HTTP version is not `HTTP/1.0`, `HTTP/1.1` or `HTTP/2`.
+    UnsupportedVersion(String),
+    /// The request line or headers are not valid UTF-8.
+    InvalidUtf8,
+    /// An HTTP/1.1 request has no `Host` header.
+    MissingHost,
+    /// A header line is malformed: its field name is not a token, its value
+    /// contains control characters, or it does not end in CRLF. Only reported
+    /// by [`HTTPRequest::parse_strict`](crate::webserver::requests::HTTPRequest::parse_strict).
+    InvalidHeader(String),
+    /// Fewer body bytes arrived than `Content-Length` announced.
+    BodyTooShort {
+        /// The declared `Content-Length`.
+        expected: usize,
+        /// The number of body bytes received.
+        received: usize,
+    },
+    /// The body length is ambiguous: `Content-Length` is sent together with
+    /// `Transfer-Encoding`, or several times with different values.
+    ConflictingLength,
+    /// The body uses a `Transfer-Encoding` the server cannot decode. No
+    /// transfer codings are supported, so any such request is rejected.
+    UnsupportedTransferEncoding(String),
+    /// The body uses a `Content-Encoding` the server cannot decode.
+    UnsupportedContentEncoding(String),
+    /// The body could not be decoded with its declared `Content-Encoding`.
+    InvalidEncodedBody(String),
+    /// The body decompresses to more than `limit` bytes.
+    BodyTooLarge {
+        /// The largest accepted decoded body, in bytes.
+        limit: usize,
+    },
+}
+
+impl RequestParseError {
+    /// The status code the server answers this error with.
+    ///
+    /// Unknown methods and transfer codings get `501 Not Implemented`,
+    /// unsupported versions
+    /// `505 HTTP Version Not Supported`, unsupported content encodings
+    /// `415 Unsupported Media Type` and oversized bodies `413 Content Too Large`;
+    /// everything else is `400 Bad Request`.
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Self::UnknownMethod(_) | Self::UnsupportedTransferEncoding(_) => {
+                StatusCode::NotImplemented
+            }
+            Self::UnsupportedContentEncoding(_) => StatusCode::UnsupportedMediaType,
+            Self::BodyTooLarge { .. } => StatusCode::ContentTooLarge,
+            Self::UnsupportedVersion(_) => StatusCode::HTTPVersionNotSupported,
+            _ => StatusCode::BadRequest,
+        }
+    }
+}
+
+impl fmt::Display for RequestParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "Empty request"),
+            Self::BadRequestLine => write!(f, "Invalid request line format"),
+            Self::UnknownMethod(method) => write!(f, "Unknown HTTP method: {}", method),
+            Self::UnsupportedVersion(version) => write!(f, "Unsupported HTTP version: {}", version),
+            Self::InvalidUtf8 => write!(f, "Invalid UTF-8 in request"),
+            Self::MissingHost => write!(f, "Missing Host header"),
+            Self::InvalidHeader(line) => write!(f, "Invalid header line: {:?}", line),
+            Self::BodyTooShort { expected, received } => write!(
+                f,
+                "Request body shorter than Content-Length: received {} of {} bytes",
+                received, expected
+            ),
+            Self::ConflictingLength => write!(f, "Conflicting body length headers"),
+            Self::UnsupportedTransferEncoding(coding) => {
+                write!(f, "Unsupported Transfer-Encoding: {}", coding)
+            }
+            Self::UnsupportedContentEncoding(coding) => {
+                write!(f, "Unsupported Content-Encoding: {}", coding)
+            }
+            Self::InvalidEncodedBody(coding) => {
+                write!(f, "Request body is not valid {} data", coding)
+            }
+            Self::BodyTooLarge { limit } => {
+                write!(f, "Decoded request body exceeds {} bytes", limit)
+            }
+        }
+    }
+}
+
+impl Error for RequestParseError {}
@@ -0,0 +1,33 @@
+//! Cookies received in a request's `Cookie` header.
+
+/// A cookie sent by the client in the `Cookie` request header.
+///
+/// Clients only send a cookie's name and value. Attributes such as `Path`,
+/// `Domain`, `Secure` or `SameSite` exist only in `Set-Cookie` and never reach
+/// the server. To send a cookie back, build a
+/// [`Cookie`](crate::webserver::http_packet::header::headers::cookie::Cookie).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RequestCookie {
+    name: String,
+    value: String,
+}
+
+impl RequestCookie {
+    /// Creates a cookie from a `name=value` pair of the `Cookie` header.
+    pub(crate) fn new(name: &str, value: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    /// The cookie name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The cookie value.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+}
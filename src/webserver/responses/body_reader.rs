@@ -0,0 +1,52 @@
+//! Response bodies produced by a reader instead of being held in memory.
+
+use std::fmt;
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+
+/// A response body that is read and written to the client piece by piece.
+///
+/// The reader is shared between clones of a response and can only be consumed
+/// once; whichever clone is sent first takes it.
+#[derive(Clone)]
+pub(crate) struct BodyReader {
+    reader: Arc<Mutex<Option<Box<dyn Read + Send>>>>,
+    len: Option<u64>,
+}
+
+impl BodyReader {
+    pub(crate) fn new(reader: impl Read + Send + 'static, len: Option<u64>) -> Self {
+        Self {
+            reader: Arc::new(Mutex::new(Some(Box::new(reader)))),
+            len,
+        }
+    }
+
+    /// The number of bytes the reader yields, if it was known up front.
+    pub(crate) fn len(&self) -> Option<u64> {
+        self.len
+    }
+
+    /// Takes the reader out, limited to the announced length.
+    ///
+    /// Returns `None` if the reader was already taken.
+    pub(crate) fn take(&self) -> Option<Box<dyn Read + Send>> {
+        let reader = self
+            .reader
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()?;
+        Some(match self.len {
+            Some(len) => Box::new(reader.take(len)),
+            None => reader,
+        })
+    }
+}
+
+impl fmt::Debug for BodyReader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BodyReader")
+            .field("len", &self.len)
+            .finish_non_exhaustive()
+    }
+}
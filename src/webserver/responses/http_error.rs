@@ -0,0 +1,60 @@
+//! A ready-made error type for handlers that can fail.
+
+use crate::webserver::responses::HTTPResponse;
+use crate::webserver::responses::status_code::StatusCode;
+use std::error::Error;
+use std::fmt;
+
+/// An error that turns into a plain-text response with its status code.
+///
+/// Return it from a handler registered with
+/// [`WebServer::add_custom_route_result`](crate::webserver::WebServer::add_custom_route_result)
+/// to answer with an error status without building the response by hand.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HttpError {
+    /// The status code of the response.
+    pub status: StatusCode,
+    /// The response body.
+    pub message: String,
+}
+
+impl HttpError {
+    /// Creates an error answered with `status` and `message` as the body.
+    pub fn new(status: StatusCode, message: impl Into<String>) -> HttpError {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+
+    /// Shorthand for [`Self::new(StatusCode::BadRequest, message)`].
+    pub fn bad_request(message: impl Into<String>) -> HttpError {
+        Self::new(StatusCode::BadRequest, message)
+    }
+
+    /// Shorthand for [`Self::new(StatusCode::NotFound, message)`].
+    pub fn not_found(message: impl Into<String>) -> HttpError {
+        Self::new(StatusCode::NotFound, message)
+    }
+
+    /// Shorthand for [`Self::new(StatusCode::InternalServerError, message)`].
+    pub fn internal(message: impl Into<String>) -> HttpError {
+        Self::new(StatusCode::InternalServerError, message)
+    }
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.status, self.message)
+    }
+}
+
+impl Error for HttpError {}
+
+impl From<HttpError> for HTTPResponse {
+    fn from(error: HttpError) -> Self {
+        let mut response = HTTPResponse::with_body(error.status, error.message);
+        response.set_text();
+        response
+    }
+}
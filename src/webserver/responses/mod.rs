@@ -11,12 +11,22 @@ use crate::webserver::http_packet::header::content_types::audio::AudioSubType;
 use crate::webserver::http_packet::header::content_types::image::ImageSubType;
 use crate::webserver::http_packet::header::content_types::text::TextSubType;
 use crate::webserver::http_packet::header::content_types::video::VideoSubType;
+use crate::webserver::http_packet::header::headers::cookie::Cookie;
+use crate::webserver::http_packet::header::headers::cors::CorsPolicy;
 use crate::webserver::http_packet::header::headers::frame_option::FrameOption;
 pub(crate) use crate::webserver::responses::status_code::StatusCode;
+use crate::webserver::route::HTTPMethod;
+use chrono::{DateTime, Utc};
 use std::collections::HashMap;
+use std::io::Read;
+use std::str::FromStr;
 
+mod body_reader;
+pub mod http_error;
 pub mod status_code;
 
+pub(crate) use body_reader::BodyReader;
+
 /// A convenient wrapper around an [`HTTPMessage`] that couples it with a
 /// [`StatusCode`].
 ///
@@ -27,6 +37,12 @@ pub struct HTTPResponse {
     /// The three-digit status code that will appear in the first line of the response.
     pub status_code: StatusCode,
     pub(crate) message: HTTPMessage,
+    pub(crate) body_reader: Option<BodyReader>,
+    /// Message of the panic this `500` response replaces.
+    pub(crate) panic_message: Option<String>,
+    /// Whether a streamed body of unknown length is sent as-is, ended by
+    /// closing the connection, instead of chunk-encoded.
+    pub(crate) unframed: bool,
 }
 
 // -------------------- Constructors --------------------
@@ -49,14 +65,102 @@ impl HTTPResponse {
         Self {
             status_code,
             message,
+            body_reader: None,
+            panic_message: None,
+            unframed: false,
         }
     }
 
+    /// Creates a response with the supplied status code and body in one call.
+    ///
+    /// `Content-Length` is set from the body; the content type keeps its
+    /// default (`text/html`).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    ///
+    /// let resp = HTTPResponse::with_body(StatusCode::Created, "Hello API");
+    /// assert_eq!(resp.status_code, StatusCode::Created);
+    /// assert_eq!(resp.body(), Some(&b"Hello API"[..]));
+    /// ```
+    pub fn with_body(status_code: StatusCode, body: impl Into<String>) -> Self {
+        let mut response = Self::new(status_code);
+        response.set_body_string(body.into());
+        response
+    }
+
     /// Shorthand for [`Self::new(StatusCode::Ok)`].
-    pub(crate) fn ok() -> Self {
+    pub fn ok() -> Self {
         Self::new(StatusCode::Ok)
     }
 
+    /// Shorthand for [`Self::new(StatusCode::Created)`].
+    pub fn created() -> Self {
+        Self::new(StatusCode::Created)
+    }
+
+    /// Builds a `204 No Content` response.
+    ///
+    /// The response has no body and sends no `Content-Length` header.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_custom_route("/item", HTTPMethod::DELETE, |_request, _domain| {
+    ///     HTTPResponse::no_content()
+    /// }, StatusCode::NoContent, None);
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let raw = b"DELETE /item HTTP/1.1\r\nHost: localhost\r\n\r\n";
+    /// let response = String::from_utf8(server.handle_request(raw, peer)).unwrap();
+    /// assert!(response.starts_with("HTTP/1.1 204 No Content\r\n"));
+    /// assert!(!response.contains("Content-Length"));
+    /// assert!(response.ends_with("\r\n\r\n"));
+    /// ```
+    pub fn no_content() -> Self {
+        let mut response = Self::new(StatusCode::NoContent);
+        response.message.body = None;
+        response.message.headers.content_length = None;
+        response
+    }
+
+    /// Shorthand for [`Self::new(StatusCode::BadRequest)`].
+    pub fn bad_request() -> Self {
+        Self::new(StatusCode::BadRequest)
+    }
+
+    /// Shorthand for [`Self::new(StatusCode::Unauthorized)`].
+    pub fn unauthorized() -> Self {
+        Self::new(StatusCode::Unauthorized)
+    }
+
+    /// Shorthand for [`Self::new(StatusCode::Forbidden)`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    ///
+    /// assert_eq!(HTTPResponse::created().status_code, StatusCode::Created);
+    /// assert_eq!(HTTPResponse::bad_request().status_code, StatusCode::BadRequest);
+    /// assert_eq!(HTTPResponse::unauthorized().status_code, StatusCode::Unauthorized);
+    /// assert_eq!(HTTPResponse::forbidden().status_code, StatusCode::Forbidden);
+    /// assert_eq!(HTTPResponse::no_content().body(), None);
+    /// ```
+    pub fn forbidden() -> Self {
+        Self::new(StatusCode::Forbidden)
+    }
+
     /// Shorthand for [`Self::new(StatusCode::NotFound)`].
     pub fn not_found() -> Self {
         Self::new(StatusCode::NotFound)
@@ -77,6 +181,57 @@ impl HTTPResponse {
         Self::new(StatusCode::BadGateway)
     }
 
+    /// Shorthand for [`Self::new(StatusCode::RequestTimeout)`].
+    pub(crate) fn request_timeout() -> Self {
+        Self::new(StatusCode::RequestTimeout)
+    }
+
+    /// Shorthand for [`Self::new(StatusCode::ServiceUnavailable)`].
+    pub(crate) fn service_unavailable() -> Self {
+        Self::new(StatusCode::ServiceUnavailable)
+    }
+
+    /// Shorthand for [`Self::new(StatusCode::GatewayTimeout)`].
+    pub(crate) fn gateway_timeout() -> Self {
+        Self::new(StatusCode::GatewayTimeout)
+    }
+
+    /// Builds a `426 Upgrade Required` response asking the client to switch to
+    /// one of `protocols`.
+    ///
+    /// Sets the `Upgrade` header to the listed protocols and `Connection: upgrade`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sunweb::webserver::http_packet::header::connection::ConnectionType;
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let mut response = HTTPResponse::upgrade_required(&["TLS/1.2", "HTTP/1.1"]);
+    /// assert_eq!(response.status_code, StatusCode::UpgradeRequired);
+    /// assert_eq!(response.get_header("Upgrade"), Some("TLS/1.2, HTTP/1.1".into()));
+    /// assert_eq!(response.headers().connection, ConnectionType::Upgrade);
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_custom_route("/", HTTPMethod::GET, |_request, _domain| {
+    ///     HTTPResponse::upgrade_required(&["TLS/1.2", "HTTP/1.1"])
+    /// }, StatusCode::Ok, None);
+    /// let raw = b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n";
+    /// let response = server.handle_request(raw, "127.0.0.1:50000".parse().unwrap());
+    /// let response = String::from_utf8(response).unwrap();
+    /// assert!(response.starts_with("HTTP/1.1 426 Upgrade Required\r\n"));
+    /// assert!(response.contains("\r\nUpgrade: TLS/1.2, HTTP/1.1\r\n"));
+    /// assert!(response.contains("\r\nConnection: upgrade\r\n"));
+    /// ```
+    pub fn upgrade_required(protocols: &[&str]) -> Self {
+        let mut response = Self::new(StatusCode::UpgradeRequired);
+        response.message.headers.set_upgrade(protocols);
+        response
+    }
+
     /// Builds a redirect response with the appropriate 3xx status code and a
     /// `Location` header.
     ///
@@ -89,9 +244,9 @@ impl HTTPResponse {
     /// ```
     pub fn redirect(location: &str, permanent: bool) -> Self {
         let status = if permanent {
-            StatusCode::TemporaryRedirect
-        } else {
             StatusCode::PermanentRedirect
+        } else {
+            StatusCode::TemporaryRedirect
         };
         let mut response = Self::new(status);
         response.set_location(location);
@@ -101,12 +256,66 @@ impl HTTPResponse {
 
 // Functions
 impl HTTPResponse {
+    /// Changes the status code.
+    ///
+    /// Switching to a status whose responses must not carry a body (1xx,
+    /// `204 No Content`, `304 Not Modified`) drops the body and its
+    /// `Content-Length`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    ///
+    /// let mut response = HTTPResponse::with_body(StatusCode::Ok, "stored");
+    /// response.set_status(StatusCode::Created);
+    /// assert_eq!(response.status_code, StatusCode::Created);
+    /// assert_eq!(response.body(), Some(&b"stored"[..]));
+    ///
+    /// response.set_status(StatusCode::NoContent);
+    /// assert_eq!(response.status_code, StatusCode::NoContent);
+    /// assert_eq!(response.body(), None);
+    /// assert_eq!(response.headers().content_length, None);
+    /// ```
+    pub fn set_status(&mut self, status_code: StatusCode) {
+        self.status_code = status_code;
+        if self.forbids_body() {
+            self.message.body = None;
+            self.message.headers.content_length = None;
+            self.body_reader = None;
+        }
+    }
+
     // ===== Header Delegation Methods =====
 
     /// Adds an arbitrary header to the response.
     ///
     /// If the header already exists, the new value is *appended* according to
     /// HTTP rules (comma-separated for most headers).
+    ///
+    /// Line breaks are stripped from the name and value, so echoing user
+    /// input into a header cannot split the response:
+    ///
+    /// ```rust
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_custom_route("/greet", HTTPMethod::GET, |request, _domain| {
+    ///     let mut response = HTTPResponse::with_body(StatusCode::Ok, "hi");
+    ///     response.add_header("X-Name", &request.query_param("name").unwrap_or_default());
+    ///     response
+    /// }, StatusCode::Ok, None);
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let raw = b"GET /greet?name=sun%0D%0ASet-Cookie:%20evil=1 HTTP/1.1\r\nHost: localhost\r\n\r\n";
+    /// let response = String::from_utf8(server.handle_request(raw, peer)).unwrap();
+    /// assert!(response.contains("\r\nX-Name: sunSet-Cookie: evil=1\r\n"));
+    /// assert!(!response.contains("\r\nSet-Cookie:"));
+    /// ```
     pub fn add_header(&mut self, key: &str, value: &str) {
         self.message.headers.add_header(key, value);
     }
@@ -126,13 +335,109 @@ impl HTTPResponse {
         &mut self.message.headers
     }
 
+    /// Adds a `Set-Cookie` header for the given cookie.
+    ///
+    /// Cookies received with a request are exposed as
+    /// [`RequestCookie`](crate::webserver::requests::request_cookie::RequestCookie)s,
+    /// which carry only a name and value; attributes are set on the [`Cookie`] sent back.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sunweb::webserver::http_packet::header::headers::cookie::Cookie;
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_custom_route("/", HTTPMethod::GET, |request, domain| {
+    ///     let received = request.cookie("session").unwrap();
+    ///     let mut response = HTTPResponse::new(StatusCode::Ok);
+    ///     response.set_cookie(Cookie::new(received.name(), received.value(), domain).http_only());
+    ///     response
+    /// }, StatusCode::Ok, None);
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let raw = b"GET / HTTP/1.1\r\nHost: localhost\r\nCookie: session=abc123\r\n\r\n";
+    /// let response = String::from_utf8(server.handle_request(raw, peer)).unwrap();
+    /// assert!(response.contains("Set-Cookie: session=abc123; Path=/; Domain=localhost; SameSite=Lax; HttpOnly\r\n"));
+    /// ```
+    pub fn set_cookie(&mut self, cookie: Cookie) {
+        self.message.headers.set_cookie(cookie);
+    }
+
+    /// Adds a `Set-Cookie` header for the given cookie and returns the response.
+    ///
+    /// Consuming counterpart of [`set_cookie`](Self::set_cookie) for building a
+    /// response in one expression. Each cookie is sent on its own line.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sunweb::webserver::http_packet::header::headers::cookie::Cookie;
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_custom_route("/login", HTTPMethod::POST, |_request, domain| {
+    ///     HTTPResponse::with_body(StatusCode::Ok, "signed in")
+    ///         .with_cookie(Cookie::new("session", "abc123", domain).http_only())
+    ///         .with_cookie(Cookie::new("theme", "dark", domain))
+    /// }, StatusCode::Ok, None);
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let raw = b"POST /login HTTP/1.1\r\nHost: example.com\r\nContent-Length: 0\r\n\r\n";
+    /// let bytes = String::from_utf8(server.handle_request(raw, peer)).unwrap();
+    /// assert_eq!(bytes.matches("Set-Cookie: ").count(), 2);
+    /// assert!(bytes.contains("Set-Cookie: session=abc123; Path=/; Domain=example.com; SameSite=Lax; HttpOnly\r\n"));
+    /// assert!(bytes.contains("Set-Cookie: theme=dark; Path=/; Domain=example.com; SameSite=Lax\r\n"));
+    /// ```
+    pub fn with_cookie(mut self, cookie: Cookie) -> Self {
+        self.set_cookie(cookie);
+        self
+    }
+
     // ===== Body Methods =====
 
     /// Replaces the response body with the supplied bytes and automatically
     /// sets the `Content-Length` header.
+    ///
+    /// `Content-Length` is always derived from the body that is finally sent;
+    /// a stale value set by hand is ignored.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_custom_route("/draft", HTTPMethod::GET, |_request, _domain| {
+    ///     let mut response = HTTPResponse::ok();
+    ///     response.set_body(b"first draft".to_vec());
+    ///     response.add_header("Content-Length", "11");
+    ///     response.headers().content_length = Some(11);
+    ///     response.set_body(b"final".to_vec());
+    ///     response.headers().content_length = Some(11);
+    ///     response
+    /// }, StatusCode::Ok, None);
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let raw = b"GET /draft HTTP/1.1\r\nHost: localhost\r\n\r\n";
+    /// let response = String::from_utf8(server.handle_request(raw, peer)).unwrap();
+    /// assert_eq!(response.matches("Content-Length").count(), 1);
+    /// assert!(response.contains("Content-Length: 5\r\n"));
+    /// assert!(response.ends_with("\r\n\r\nfinal"));
+    /// ```
     pub fn set_body(&mut self, body: Vec<u8>) {
         self.message.headers.content_length = Some(body.len() as u64);
         self.message.body = Some(body);
+        self.body_reader = None;
     }
 
     /// Convenience wrapper around [`set_body`](Self::set_body) that converts a
@@ -141,11 +446,188 @@ impl HTTPResponse {
         self.set_body(body.into_bytes());
     }
 
+    /// Replaces the body with `value` serialized as JSON and sets
+    /// `Content-Type: application/json`.
+    ///
+    /// Any JSON value can be sent, including arrays and nested objects built
+    /// with `serde_json::json!`, without defining a struct for them.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use serde_json::json;
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_custom_route("/user", HTTPMethod::GET, |_request, _domain| {
+    ///     let mut response = HTTPResponse::ok();
+    ///     response.json_value(&json!({"name": "sun", "roles": {"admin": true}}));
+    ///     response
+    /// }, StatusCode::Ok, None);
+    /// server.add_custom_route("/ids", HTTPMethod::GET, |_request, _domain| {
+    ///     let mut response = HTTPResponse::ok();
+    ///     response.json_value(&json!([1, 2, 3]));
+    ///     response
+    /// }, StatusCode::Ok, None);
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let get = |path: &str| {
+    ///     let raw = format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n");
+    ///     String::from_utf8(server.handle_request(raw.as_bytes(), peer)).unwrap()
+    /// };
+    ///
+    /// let user = get("/user");
+    /// assert!(user.contains("Content-Type: application/json"));
+    /// assert!(user.ends_with(r#"{"name":"sun","roles":{"admin":true}}"#));
+    ///
+    /// let ids = get("/ids");
+    /// assert!(ids.contains("Content-Length: 7\r\n"));
+    /// assert!(ids.ends_with("\r\n\r\n[1,2,3]"));
+    /// ```
+    pub fn json_value(&mut self, value: &serde_json::Value) {
+        self.set_json();
+        self.set_body(value.to_string().into_bytes());
+    }
+
+    /// Streams the body from `reader` instead of holding it in memory.
+    ///
+    /// The reader is copied to the client in chunks once the handler returns.
+    /// If `len` is known it is sent as `Content-Length` and at most that many
+    /// bytes are read; otherwise the body is sent with
+    /// `Transfer-Encoding: chunked` (or as plain DATA frames over HTTP/2).
+    /// HTTP/1.0 clients cannot read chunked bodies, so they get the body
+    /// unframed and the connection is closed after it.
+    /// Replaces any body set previously, so [`body`](Self::body) returns `None`
+    /// afterwards.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    /// use std::io::Cursor;
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_custom_route("/report", HTTPMethod::GET, |_request, _domain| {
+    ///     let mut response = HTTPResponse::new(StatusCode::Ok);
+    ///     response.set_body_reader(Cursor::new(b"hello world".to_vec()), None);
+    ///     response
+    /// }, StatusCode::Ok, None);
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let raw = b"GET /report HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n";
+    /// let response = String::from_utf8(server.handle_request(raw, peer)).unwrap();
+    /// assert!(response.contains("Transfer-Encoding: chunked\r\n"));
+    /// assert!(!response.contains("Content-Length"));
+    /// assert!(response.ends_with("\r\n\r\nB\r\nhello world\r\n0\r\n\r\n"));
+    ///
+    /// let raw = b"GET /report HTTP/1.0\r\nHost: localhost\r\nConnection: keep-alive\r\n\r\n";
+    /// let response = String::from_utf8(server.handle_request(raw, peer)).unwrap();
+    /// assert!(!response.contains("Transfer-Encoding"));
+    /// assert!(response.contains("Connection: close\r\n"));
+    /// assert!(response.ends_with("\r\n\r\nhello world"));
+    /// ```
+    ///
+    /// A large source is sent while it is still being produced:
+    ///
+    /// ```rust
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    /// use std::io::{self, BufRead, BufReader, Read, Write};
+    /// use std::net::{TcpListener, TcpStream};
+    /// use std::sync::Arc;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    ///
+    /// const SIZE: usize = 64 * 1024 * 1024;
+    ///
+    /// /// Yields `remaining` bytes without ever holding them all in memory.
+    /// struct Generator(Arc<AtomicUsize>);
+    ///
+    /// impl Read for Generator {
+    ///     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    ///         let n = buf.len().min(self.0.load(Ordering::SeqCst));
+    ///         buf[..n].fill(b'x');
+    ///         self.0.fetch_sub(n, Ordering::SeqCst);
+    ///         Ok(n)
+    ///     }
+    /// }
+    ///
+    /// let remaining = Arc::new(AtomicUsize::new(SIZE));
+    /// let source = Arc::clone(&remaining);
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_custom_route("/large", HTTPMethod::GET, move |_request, _domain| {
+    ///     let mut response = HTTPResponse::new(StatusCode::Ok);
+    ///     response.set_body_reader(Generator(Arc::clone(&source)), None);
+    ///     response
+    /// }, StatusCode::Ok, None);
+    ///
+    /// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    /// let addr = listener.local_addr().unwrap();
+    /// std::thread::spawn(move || {
+    ///     let (stream, _) = listener.accept().unwrap();
+    ///     server.serve_connection(stream);
+    /// });
+    ///
+    /// let mut client = TcpStream::connect(addr).unwrap();
+    /// client
+    ///     .write_all(b"GET /large HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+    ///     .unwrap();
+    /// let mut reader = BufReader::new(client);
+    ///
+    /// let mut line = String::new();
+    /// let mut head = String::new();
+    /// while line != "\r\n" {
+    ///     line.clear();
+    ///     reader.read_line(&mut line).unwrap();
+    ///     head.push_str(&line);
+    /// }
+    /// assert!(head.starts_with("HTTP/1.1 200 OK\r\n"));
+    /// assert!(head.contains("Transfer-Encoding: chunked\r\n"));
+    /// // The server is blocked on the socket long before the source is drained.
+    /// assert!(remaining.load(Ordering::SeqCst) > 0);
+    ///
+    /// let mut received = 0;
+    /// loop {
+    ///     line.clear();
+    ///     reader.read_line(&mut line).unwrap();
+    ///     let size = usize::from_str_radix(line.trim_end(), 16).unwrap();
+    ///     let mut chunk = vec![0; size + 2];
+    ///     reader.read_exact(&mut chunk).unwrap();
+    ///     assert!(chunk.ends_with(b"\r\n"));
+    ///     if size == 0 {
+    ///         break;
+    ///     }
+    ///     assert!(chunk[..size].iter().all(|&b| b == b'x'));
+    ///     received += size;
+    /// }
+    /// assert_eq!(received, SIZE);
+    /// ```
+    pub fn set_body_reader(&mut self, reader: impl Read + Send + 'static, len: Option<u64>) {
+        self.message.headers.content_length = len;
+        self.message.body = None;
+        self.body_reader = Some(BodyReader::new(reader, len));
+    }
+
     /// Returns a slice into the current body, if one has been set.
     pub fn body(&self) -> Option<&[u8]> {
         self.message.body.as_deref()
     }
 
+    /// The message of the handler panic this `500` response was sent for.
+    ///
+    /// Only set on responses passed to an
+    /// [error reporter](crate::webserver::WebServer::set_error_reporter).
+    pub fn panic_message(&self) -> Option<&str> {
+        self.panic_message.as_deref()
+    }
+
     // ===== Convenience Methods (delegating to HTTPHeader) =====
 
     /// Delegates to [`HTTPHeader::set_date_now`].
@@ -179,10 +661,89 @@ impl HTTPResponse {
     }
 
     /// Delegates to [`HTTPHeader::set_etag`].
+    ///
+    /// # Example
+    ///
+    /// A conditional GET answering a matching `If-None-Match` with `304 Not Modified`.
+    /// The body set on the response is not sent, as 304 responses must not have one.
+    ///
+    /// ```
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_custom_route("/doc", HTTPMethod::GET, |request, _domain| {
+    ///     let fresh = request.get_header("If-None-Match").as_deref() == Some("\"v1\"");
+    ///     let status = if fresh { StatusCode::NotModified } else { StatusCode::Ok };
+    ///     let mut response = HTTPResponse::with_body(status, "document");
+    ///     response.set_etag("v1");
+    ///     response
+    /// }, StatusCode::Ok, None);
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let raw = b"GET /doc HTTP/1.1\r\nHost: localhost\r\nIf-None-Match: \"v1\"\r\n\r\n";
+    /// let response = String::from_utf8(server.handle_request(raw, peer)).unwrap();
+    /// assert!(response.starts_with("HTTP/1.1 304 Not Modified\r\n"));
+    /// assert!(response.contains("ETag: \"v1\"\r\n"));
+    /// assert!(!response.contains("Content-Length"));
+    /// assert!(response.ends_with("\r\n\r\n"));
+    /// ```
     pub fn set_etag(&mut self, etag: &str) {
         self.message.headers.set_etag(etag);
     }
 
+    /// Delegates to [`HTTPHeader::set_expires`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use chrono::{TimeZone, Utc};
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    ///
+    /// let mut resp = HTTPResponse::new(StatusCode::Ok);
+    /// resp.set_expires(Utc.with_ymd_and_hms(2025, 10, 4, 12, 30, 0).unwrap());
+    /// assert_eq!(resp.get_header("Expires"), Some("Sat, 04 Oct 2025 12:30:00 GMT".into()));
+    /// ```
+    pub fn set_expires(&mut self, datetime: DateTime<Utc>) {
+        self.message.headers.set_expires(datetime);
+    }
+
+    /// Delegates to [`HTTPHeader::set_retry_after_secs`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    ///
+    /// let mut resp = HTTPResponse::new(StatusCode::TooManyRequests);
+    /// resp.set_retry_after_secs(120);
+    /// assert_eq!(resp.get_header("Retry-After"), Some("120".into()));
+    /// ```
+    pub fn set_retry_after_secs(&mut self, seconds: u64) {
+        self.message.headers.set_retry_after_secs(seconds);
+    }
+
+    /// Delegates to [`HTTPHeader::set_retry_after_date`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use chrono::{TimeZone, Utc};
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    ///
+    /// let mut resp = HTTPResponse::new(StatusCode::ServiceUnavailable);
+    /// resp.set_retry_after_date(Utc.with_ymd_and_hms(2015, 10, 21, 7, 28, 0).unwrap());
+    /// assert_eq!(resp.get_header("Retry-After"), Some("Wed, 21 Oct 2015 07:28:00 GMT".into()));
+    /// ```
+    pub fn set_retry_after_date(&mut self, datetime: DateTime<Utc>) {
+        self.message.headers.set_retry_after_date(datetime);
+    }
+
     /// Delegates to [`HTTPHeader::set_content_encoding`].
     pub fn set_content_encoding(&mut self, encoding: &str) {
         self.message.headers.set_content_encoding(encoding);
@@ -226,7 +787,11 @@ impl HTTPResponse {
     /// - `X-Frame-Options: DENY`
     /// - `X-XSS-Protection: 1; mode=block`
     /// - `Content-Security-Policy: default-src 'self'`
-    /// - `Strict-Transport-Security: max-age=31536000; includeSubDomains`
+    ///
+    /// `Strict-Transport-Security` is not included because it must only be sent
+    /// over HTTPS. Use [`set_hsts`](Self::set_hsts), or enable
+    /// [`ServerConfig::default_security_headers`](crate::webserver::ServerConfig::default_security_headers)
+    /// to have it added to TLS responses automatically.
     pub fn apply_security_headers(&mut self) {
         self.message.headers.apply_security_headers();
     }
@@ -237,7 +802,22 @@ impl HTTPResponse {
     }
 
     /// Adds `Access-Control-Allow-Methods`.
-    pub fn set_cors_methods(&mut self, methods: &[&str]) {
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    ///
+    /// let mut resp = HTTPResponse::new(StatusCode::NoContent);
+    /// resp.set_cors_methods(&[HTTPMethod::GET, HTTPMethod::POST, HTTPMethod::DELETE]);
+    /// assert_eq!(
+    ///     resp.get_header("Access-Control-Allow-Methods"),
+    ///     Some("GET, POST, DELETE".into())
+    /// );
+    /// ```
+    pub fn set_cors_methods(&mut self, methods: &[HTTPMethod]) {
         self.message.headers.set_cors_methods(methods);
     }
 
@@ -265,6 +845,52 @@ impl HTTPResponse {
         self.message.headers.apply_cors_permissive();
     }
 
+    /// Applies a [`CorsPolicy`] for a request sent from `request_origin`.
+    ///
+    /// The request's `Origin` is echoed only if the policy allows it; for any
+    /// other origin no CORS headers are added, so browsers block the response.
+    /// Either way `Vary: Origin` is sent unless the policy allows any origin.
+    /// `Access-Control-Allow-Credentials` is only sent for allowed origins of a
+    /// policy with credentials enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sunweb::webserver::http_packet::header::headers::cors::CorsPolicy;
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::route::HTTPMethod;
+    ///
+    /// let policy = CorsPolicy::new()
+    ///     .allow_origin("https://app.example.com")
+    ///     .allow_methods(&[HTTPMethod::GET, HTTPMethod::POST])
+    ///     .allow_credentials();
+    ///
+    /// let mut allowed = HTTPResponse::ok();
+    /// allowed.apply_cors(&policy, Some("https://app.example.com"));
+    /// assert_eq!(
+    ///     allowed.get_header("Access-Control-Allow-Origin"),
+    ///     Some("https://app.example.com".into())
+    /// );
+    /// assert_eq!(allowed.get_header("Access-Control-Allow-Credentials"), Some("true".into()));
+    /// assert_eq!(allowed.get_header("Access-Control-Allow-Methods"), Some("GET, POST".into()));
+    /// assert_eq!(allowed.get_header("Vary"), Some("Origin".into()));
+    ///
+    /// let mut disallowed = HTTPResponse::ok();
+    /// disallowed.apply_cors(&policy, Some("https://evil.example"));
+    /// assert_eq!(disallowed.get_header("Access-Control-Allow-Origin"), None);
+    /// assert_eq!(disallowed.get_header("Access-Control-Allow-Credentials"), None);
+    /// assert_eq!(disallowed.get_header("Vary"), Some("Origin".into()));
+    ///
+    /// let mut wildcard = HTTPResponse::ok();
+    /// wildcard.apply_cors(&CorsPolicy::any_origin(), Some("https://anywhere.example"));
+    /// assert_eq!(wildcard.get_header("Access-Control-Allow-Origin"), Some("*".into()));
+    /// assert_eq!(wildcard.get_header("Access-Control-Allow-Credentials"), None);
+    /// assert_eq!(wildcard.get_header("Vary"), None);
+    /// ```
+    pub fn apply_cors(&mut self, policy: &CorsPolicy, request_origin: Option<&str>) {
+        self.message.headers.apply_cors(policy, request_origin);
+    }
+
     // ===== Content-Type Methods =====
 
     /// Overwrites the `Content-Type` header with the supplied value.
@@ -272,6 +898,102 @@ impl HTTPResponse {
         self.message.headers.content_type = content_type;
     }
 
+    /// Parses a raw MIME string and sets it as the `Content-Type`.
+    ///
+    /// The string must be a bare `type/subtype` made of HTTP token characters;
+    /// it is matched case-insensitively. Parameters such as `; charset=utf-8`
+    /// are rejected. Subtypes without a dedicated variant are kept verbatim.
+    ///
+    /// Returns `Err(description)` and leaves the current content type untouched
+    /// if the string is not a valid MIME type.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    ///
+    /// let mut resp = HTTPResponse::new(StatusCode::Ok);
+    /// resp.set_content_type_str("application/vnd.api+json").unwrap();
+    /// assert_eq!(resp.content_type().to_string(), "application/vnd.api+json");
+    ///
+    /// assert!(resp.set_content_type_str("not a mime type").is_err());
+    /// assert!(resp.set_content_type_str("text/").is_err());
+    /// ```
+    pub fn set_content_type_str(&mut self, content_type: &str) -> Result<(), String> {
+        let is_token = |s: &str| {
+            !s.is_empty()
+                && s.chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c))
+        };
+        let content_type = content_type.trim().to_ascii_lowercase();
+        match content_type.split_once('/') {
+            Some((main, sub)) if is_token(main) && is_token(sub) => {}
+            _ => return Err(format!("Invalid MIME type: {content_type}")),
+        }
+
+        self.message.headers.content_type = ContentType::from_str(&content_type)
+            .map_err(|_| format!("Invalid MIME type: {content_type}"))?;
+        Ok(())
+    }
+
+    /// Sets the `charset` parameter sent with textual content types.
+    ///
+    /// Responses default to `utf-8`, which is appended to `text/*`, JSON, XML,
+    /// JavaScript and SVG content types (see [`ContentType::is_textual`]). Pass `None`
+    /// to send the bare media type. Other content types never get a charset.
+    /// CR, LF and NUL characters are removed, as for any header value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sunweb::webserver::http_packet::header::content_types::image::ImageSubType;
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+    /// server.add_custom_route("/text", HTTPMethod::GET, |_request, _domain| {
+    ///     let mut response = HTTPResponse::with_body(StatusCode::Ok, "hello");
+    ///     response.set_text();
+    ///     response
+    /// }, StatusCode::Ok, None);
+    /// server.add_custom_route("/latin", HTTPMethod::GET, |_request, _domain| {
+    ///     let mut response = HTTPResponse::with_body(StatusCode::Ok, "hello");
+    ///     response.set_text();
+    ///     response.set_charset(Some("iso-8859-1"));
+    ///     response
+    /// }, StatusCode::Ok, None);
+    /// server.add_custom_route("/logo", HTTPMethod::GET, |_request, _domain| {
+    ///     let mut response = HTTPResponse::new(StatusCode::Ok);
+    ///     response.set_image(ImageSubType::Png);
+    ///     response
+    /// }, StatusCode::Ok, None);
+    /// server.add_custom_route("/injected", HTTPMethod::GET, |_request, _domain| {
+    ///     let mut response = HTTPResponse::with_body(StatusCode::Ok, "hello");
+    ///     response.set_text();
+    ///     response.set_charset(Some("utf-8\r\nSet-Cookie: session=stolen"));
+    ///     response
+    /// }, StatusCode::Ok, None);
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let get = |path: &str| {
+    ///     let raw = format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n");
+    ///     String::from_utf8(server.handle_request(raw.as_bytes(), peer)).unwrap()
+    /// };
+    /// assert!(get("/text").contains("Content-Type: text/plain; charset=utf-8\r\n"));
+    /// assert!(get("/latin").contains("Content-Type: text/plain; charset=iso-8859-1\r\n"));
+    /// assert!(get("/logo").contains("Content-Type: image/png\r\n"));
+    ///
+    /// let injected = get("/injected");
+    /// assert!(injected.contains("Content-Type: text/plain; charset=utf-8Set-Cookie: session=stolen\r\n"));
+    /// assert!(!injected.contains("\r\nSet-Cookie"));
+    /// ```
+    pub fn set_charset(&mut self, charset: Option<&str>) {
+        self.message.headers.charset = charset.map(str::to_string);
+    }
+
     /// Returns the currently set content type.
     pub fn content_type(&self) -> &ContentType {
         &self.message.headers.content_type
@@ -319,7 +1041,9 @@ impl HTTPResponse {
     ///
     /// The returned buffer contains the status line, all headers (including
     /// those set implicitly such as `Content-Length`) and, if present, the
-    /// body.  It is ready to be written directly to a `TcpStream`.
+    /// body.  It is ready to be written directly to a `TcpStream`. For status
+    /// codes that forbid a body (1xx, 204, 304) both the body and
+    /// `Content-Length` are left out.
     ///
     /// # Example
     ///
@@ -341,13 +1065,25 @@ impl HTTPResponse {
         // Add content-type and content-length
         response.push_str(&format!(
             "Content-Type: {}\r\n",
-            self.message.headers.content_type.to_string()
+            self.message.headers.content_type_value()
         ));
 
-        if let Some(len) = self.message.headers.content_length {
+        if let Some(len) = self.content_length()
+            && !self.forbids_body()
+        {
             response.push_str(&format!("Content-Length: {}\r\n", len));
         }
 
+        if self
+            .body_reader
+            .as_ref()
+            .is_some_and(|body| body.len().is_none())
+            && !self.forbids_body()
+            && !self.unframed
+        {
+            response.push_str("Transfer-Encoding: chunked\r\n");
+        }
+
         response.push_str(&format!(
             "Connection: {}\r\n",
             self.message.headers.connection.to_string()
@@ -362,10 +1098,34 @@ impl HTTPResponse {
         let mut bytes = response.into_bytes();
 
         // Add body if present
-        if let Some(body) = &self.message.body {
+        if let Some(body) = &self.message.body
+            && !self.forbids_body()
+        {
             bytes.extend_from_slice(body);
         }
 
         bytes
     }
+
+    /// The `Content-Length` to send.
+    ///
+    /// Computed from the body at serialization time so it cannot drift from
+    /// the bytes actually sent: a buffered body's real length, a streamed
+    /// body's announced length (`None` for chunked), or the stored value when
+    /// there is no body.
+    pub(crate) fn content_length(&self) -> Option<u64> {
+        if let Some(body_reader) = &self.body_reader {
+            return body_reader.len();
+        }
+        match &self.message.body {
+            Some(body) => Some(body.len() as u64),
+            None => self.message.headers.content_length,
+        }
+    }
+
+    /// `true` for status codes whose responses must not carry a body: 1xx, 204 and 304.
+    pub(crate) fn forbids_body(&self) -> bool {
+        let code = self.status_code.as_u16();
+        code < 200 || code == 204 || code == 304
+    }
 }
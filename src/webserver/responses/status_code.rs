@@ -36,6 +36,7 @@
 
 use std::fmt;
 use std::fmt::Formatter;
+use std::str::FromStr;
 
 /// HTTP response status codes enumeration
 ///
@@ -322,7 +323,119 @@ impl fmt::Display for StatusCode {
     }
 }
 
+/// Every status code, in ascending numeric order.
+const ALL: [StatusCode; 61] = [
+    StatusCode::Continue,
+    StatusCode::SwitchingProtocols,
+    StatusCode::Processing,
+    StatusCode::EarlyHints,
+    StatusCode::Ok,
+    StatusCode::Created,
+    StatusCode::Accepted,
+    StatusCode::NonAuthoritativeInformation,
+    StatusCode::NoContent,
+    StatusCode::ResetContent,
+    StatusCode::PartialContent,
+    StatusCode::MultiStatus,
+    StatusCode::AlreadyReported,
+    StatusCode::ImUsed,
+    StatusCode::MultipleChoices,
+    StatusCode::MovedPermanently,
+    StatusCode::Found,
+    StatusCode::SeeOther,
+    StatusCode::NotModified,
+    StatusCode::TemporaryRedirect,
+    StatusCode::PermanentRedirect,
+    StatusCode::BadRequest,
+    StatusCode::Unauthorized,
+    StatusCode::PaymentRequired,
+    StatusCode::Forbidden,
+    StatusCode::NotFound,
+    StatusCode::MethodNotAllowed,
+    StatusCode::NotAcceptable,
+    StatusCode::ProxyAuthenticationRequired,
+    StatusCode::RequestTimeout,
+    StatusCode::Conflict,
+    StatusCode::Gone,
+    StatusCode::LengthRequired,
+    StatusCode::PreconditionFailed,
+    StatusCode::ContentTooLarge,
+    StatusCode::UriTooLong,
+    StatusCode::UnsupportedMediaType,
+    StatusCode::RangeNotSatisfiable,
+    StatusCode::ExpectationFailed,
+    StatusCode::ImATeapot,
+    StatusCode::MisdirectedRequest,
+    StatusCode::UnprocessableContent,
+    StatusCode::Locked,
+    StatusCode::FailedDependency,
+    StatusCode::TooEarly,
+    StatusCode::UpgradeRequired,
+    StatusCode::PreconditionRequired,
+    StatusCode::TooManyRequests,
+    StatusCode::RequestHeaderFieldsTooLarge,
+    StatusCode::UnavailableForLegalReasons,
+    StatusCode::InternalServerError,
+    StatusCode::NotImplemented,
+    StatusCode::BadGateway,
+    StatusCode::ServiceUnavailable,
+    StatusCode::GatewayTimeout,
+    StatusCode::HTTPVersionNotSupported,
+    StatusCode::VariantAlsoNegotiates,
+    StatusCode::InsufficientStorage,
+    StatusCode::LoopDetected,
+    StatusCode::NotExtended,
+    StatusCode::NetworkAuthenticationRequired,
+];
+
+impl FromStr for StatusCode {
+    type Err = String;
+
+    /// Parses a status code from its number (`"404"`) or its reason phrase
+    /// (`"Not Found"`, matched case-insensitively).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::str::FromStr;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    ///
+    /// assert_eq!(StatusCode::from_str("404"), Ok(StatusCode::NotFound));
+    /// assert_eq!(" not found ".parse::<StatusCode>(), Ok(StatusCode::NotFound));
+    /// assert_eq!("I'M A TEAPOT".parse::<StatusCode>(), Ok(StatusCode::ImATeapot));
+    /// assert!("299".parse::<StatusCode>().is_err());
+    /// assert!("Not Really Found".parse::<StatusCode>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let status_code = match s.parse::<u16>() {
+            Ok(code) => StatusCode::from_u16(code),
+            Err(_) => ALL
+                .into_iter()
+                .find(|status_code| status_code.to_string().eq_ignore_ascii_case(s)),
+        };
+        status_code.ok_or_else(|| format!("Unknown status code: {s}"))
+    }
+}
+
 impl StatusCode {
+    /// Looks up the status code with the given number.
+    ///
+    /// Returns `None` for numbers without a variant.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    ///
+    /// assert_eq!(StatusCode::from_u16(204), Some(StatusCode::NoContent));
+    /// assert_eq!(StatusCode::from_u16(999), None);
+    /// ```
+    pub fn from_u16(code: u16) -> Option<Self> {
+        ALL.into_iter()
+            .find(|status_code| status_code.as_u16() == code)
+    }
+
     /// Compares two status codes for complete equality
     ///
     /// This method checks both the numeric value and string representation of status codes
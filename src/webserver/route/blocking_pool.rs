@@ -0,0 +1,48 @@
+//! A fixed set of threads that runs the handlers of blocking routes.
+
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// A unit of work queued on the pool.
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Runs jobs on a fixed number of worker threads, shared by every blocking
+/// route of a server.
+///
+/// Jobs beyond the number of threads wait in a queue, so at most `threads`
+/// heavy handlers run at once no matter how many connections request them.
+/// The pool only limits concurrency: callers still wait for their job's result.
+/// The workers exit once the pool and every route holding it are dropped.
+pub(crate) struct BlockingPool {
+    sender: Sender<Job>,
+}
+
+impl BlockingPool {
+    /// Starts a pool with `threads` workers; at least one is always started.
+    pub(crate) fn new(threads: usize) -> Self {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for _ in 0..threads.max(1) {
+            let receiver = Arc::clone(&receiver);
+            thread::spawn(move || work(&receiver));
+        }
+        Self { sender }
+    }
+
+    /// Queues `job` to run on the next free worker.
+    pub(crate) fn execute(&self, job: impl FnOnce() + Send + 'static) {
+        let _ = self.sender.send(Box::new(job));
+    }
+}
+
+/// Runs queued jobs until the pool is dropped.
+fn work(receiver: &Mutex<Receiver<Job>>) {
+    loop {
+        let job = receiver.lock().unwrap_or_else(|e| e.into_inner()).recv();
+        match job {
+            Ok(job) => job(),
+            Err(_) => break,
+        }
+    }
+}
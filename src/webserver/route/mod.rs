@@ -1,26 +1,58 @@
+mod blocking_pool;
 mod http_method;
 
 use crate::webserver::Domain;
+use crate::webserver::client_handling::UpgradedStream;
+use crate::webserver::files::StaticOptions;
+use crate::webserver::http_packet::header::content_types::ContentType;
+use crate::webserver::http_packet::header::headers::cache_control::CacheControl;
+use crate::webserver::middleware::Middleware;
+use crate::webserver::proxy::CircuitBreaker;
 use crate::webserver::requests::HTTPRequest;
 use crate::webserver::responses::{HTTPResponse, StatusCode};
+pub(crate) use crate::webserver::route::blocking_pool::BlockingPool;
 pub use crate::webserver::route::http_method::HTTPMethod;
 use std::sync::Arc;
 
+/// A custom route handler, shared between the router and in-flight requests.
+pub(crate) type Handler = Arc<dyn Fn(HTTPRequest, &Domain) -> HTTPResponse + Send + Sync>;
+
+/// The handler of an upgrade route, which takes over the connection.
+pub(crate) type UpgradeHandler = Arc<dyn Fn(HTTPRequest, &mut dyn UpgradedStream) + Send + Sync>;
+
 /// Represents the type of a route.
 ///
-/// This is used internally by the web server to determine how to handle requests.
+/// This is used by the web server to determine how to handle requests.
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) enum RouteType {
+pub enum RouteType {
     /// Serves files from a static folder.
     Static,
     /// Serves a specific file.
     File,
+    /// Serves a fixed in-memory byte buffer.
+    Bytes,
     /// Uses a custom closure to generate a response.
     Custom,
     /// Represents an error page route (e.g., 404).
     Error,
     /// Forwards the request to an external URL.
     Proxy,
+    /// Hands the connection to a closure, e.g. for WebSockets.
+    Upgrade,
+}
+
+/// A read-only summary of a registered route, as returned by
+/// [`WebServer::routes`](crate::webserver::WebServer::routes).
+#[derive(Clone, Debug, PartialEq)]
+pub struct RouteInfo {
+    /// The path or route string (empty for error page routes).
+    pub route: String,
+    /// The HTTP method the route answers to.
+    pub method: HTTPMethod,
+    /// How the route produces its response.
+    pub route_type: RouteType,
+    /// The HTTP response code configured for the route.
+    pub status_code: StatusCode,
 }
 
 /// Represents a route in the web server.
@@ -42,13 +74,41 @@ pub(crate) struct Route {
     pub(crate) external: Option<String>,
     /// Optional content for file or error routes.
     pub(crate) content: Option<Arc<String>>,
+    /// Optional raw content for byte buffer routes.
+    pub(crate) bytes: Option<Arc<Vec<u8>>>,
+    /// Optional content type to respond with.
+    pub(crate) content_type: Option<ContentType>,
+    /// Optional Cache-Control directive for file and static routes.
+    pub(crate) cache_control: Option<CacheControl>,
     /// Optional folder path for static routes.
     pub(crate) folder: Option<String>,
+    /// How static routes serve their folder.
+    pub(crate) static_options: Option<StaticOptions>,
+    /// Breaker guarding the upstream of proxy routes.
+    pub(crate) circuit_breaker: Option<CircuitBreaker>,
+    /// Pool that runs the handler of blocking custom routes.
+    pub(crate) blocking_pool: Option<Arc<BlockingPool>>,
+    /// Middleware wrapped around the handler of custom routes only.
+    pub(crate) middleware: Vec<Middleware>,
     /// Optional custom closure for dynamic routes.
-    pub(crate) f: Option<Arc<dyn Fn(HTTPRequest, &Domain) -> HTTPResponse + Send + Sync>>,
+    pub(crate) f: Option<Handler>,
+    /// Closure taking over the connection for upgrade routes.
+    pub(crate) upgrade: Option<UpgradeHandler>,
+    /// Whether requests to this route are written to the access log.
+    pub(crate) access_log: bool,
 }
 
 impl Route {
+    /// Returns the public summary of this route.
+    pub(crate) fn info(&self) -> RouteInfo {
+        RouteInfo {
+            route: self.route.clone(),
+            method: self.method.clone(),
+            route_type: self.route_type.clone(),
+            status_code: self.status_code,
+        }
+    }
+
     /// Creates a new file-based route.
     ///
     /// # Arguments
@@ -58,6 +118,8 @@ impl Route {
     /// * `response_code` - HTTP status code for responses.
     /// * `domain` - Domain this route belongs to.
     /// * `content` - File content to serve.
+    /// * `content_type` - Content type inferred from the file extension, if known.
+    /// * `cache_control` - Optional Cache-Control directive for responses.
     ///
     /// # Returns
     ///
@@ -68,6 +130,8 @@ impl Route {
         response_code: StatusCode,
         domain: Domain,
         content: Arc<String>,
+        content_type: Option<ContentType>,
+        cache_control: Option<CacheControl>,
     ) -> Route {
         Self {
             route,
@@ -77,8 +141,61 @@ impl Route {
             status_code: response_code,
             external: None,
             content: Some(content),
+            bytes: None,
+            content_type,
+            cache_control,
             folder: None,
+            static_options: None,
+            circuit_breaker: None,
+            blocking_pool: None,
+            middleware: Vec::new(),
             f: None,
+            upgrade: None,
+            access_log: true,
+        }
+    }
+
+    /// Creates a new route serving a fixed byte buffer.
+    ///
+    /// # Arguments
+    ///
+    /// * `route` - Route path.
+    /// * `method` - HTTP method.
+    /// * `response_code` - HTTP status code.
+    /// * `domain` - Domain for this route.
+    /// * `bytes` - The buffer to serve.
+    /// * `content_type` - Content type of the buffer.
+    ///
+    /// # Returns
+    ///
+    /// A `Route` that always responds with the same bytes.
+    pub(crate) fn new_bytes(
+        route: String,
+        method: HTTPMethod,
+        response_code: StatusCode,
+        domain: Domain,
+        bytes: Arc<Vec<u8>>,
+        content_type: ContentType,
+    ) -> Route {
+        Self {
+            route,
+            domain,
+            method,
+            route_type: RouteType::Bytes,
+            status_code: response_code,
+            external: None,
+            content: None,
+            bytes: Some(bytes),
+            content_type: Some(content_type),
+            cache_control: None,
+            folder: None,
+            static_options: None,
+            circuit_breaker: None,
+            blocking_pool: None,
+            middleware: Vec::new(),
+            f: None,
+            upgrade: None,
+            access_log: true,
         }
     }
 
@@ -110,8 +227,17 @@ impl Route {
             status_code: response_code,
             external: None,
             content: None,
+            bytes: None,
+            content_type: None,
+            cache_control: None,
             folder: None,
+            static_options: None,
+            circuit_breaker: None,
+            blocking_pool: None,
+            middleware: Vec::new(),
             f: Some(Arc::new(f)),
+            upgrade: None,
+            access_log: true,
         }
     }
 
@@ -124,6 +250,7 @@ impl Route {
     /// * `response_code` - HTTP status code.
     /// * `domain` - Domain for this route.
     /// * `folder` - Folder path containing static files.
+    /// * `options` - Index file, listing, caching and hidden-file behavior.
     ///
     /// # Returns
     ///
@@ -134,6 +261,7 @@ impl Route {
         response_code: StatusCode,
         domain: Domain,
         folder: String,
+        options: StaticOptions,
     ) -> Route {
         Self {
             route,
@@ -143,8 +271,17 @@ impl Route {
             status_code: response_code,
             external: None,
             content: None,
+            bytes: None,
+            content_type: None,
+            cache_control: None,
             folder: Some(folder),
+            static_options: Some(options),
+            circuit_breaker: None,
+            blocking_pool: None,
+            middleware: Vec::new(),
             f: None,
+            upgrade: None,
+            access_log: true,
         }
     }
 
@@ -174,8 +311,17 @@ impl Route {
             status_code: response_code,
             external: None,
             content: Some(content),
+            bytes: None,
+            content_type: None,
+            cache_control: None,
             folder: None,
+            static_options: None,
+            circuit_breaker: None,
+            blocking_pool: None,
+            middleware: Vec::new(),
             f: None,
+            upgrade: None,
+            access_log: true,
         }
     }
 
@@ -198,6 +344,7 @@ impl Route {
         domain: Domain,
         response_code: StatusCode,
         external: String,
+        circuit_breaker: Option<CircuitBreaker>,
     ) -> Route {
         Self {
             route,
@@ -207,8 +354,57 @@ impl Route {
             status_code: response_code,
             external: Some(external),
             content: None,
+            bytes: None,
+            content_type: None,
+            cache_control: None,
+            folder: None,
+            static_options: None,
+            circuit_breaker,
+            blocking_pool: None,
+            middleware: Vec::new(),
+            f: None,
+            upgrade: None,
+            access_log: true,
+        }
+    }
+
+    /// Creates a new upgrade route.
+    ///
+    /// # Arguments
+    ///
+    /// * `route` - Route path.
+    /// * `method` - HTTP method.
+    /// * `domain` - Domain for this route.
+    /// * `f` - Closure that takes over the connection after the request is parsed.
+    ///
+    /// # Returns
+    ///
+    /// A `Route` that hands matching connections to the closure.
+    pub(crate) fn new_upgrade(
+        route: String,
+        method: HTTPMethod,
+        domain: Domain,
+        f: impl Fn(HTTPRequest, &mut dyn UpgradedStream) + Send + Sync + 'static,
+    ) -> Route {
+        Self {
+            route,
+            domain,
+            method,
+            route_type: RouteType::Upgrade,
+            status_code: StatusCode::SwitchingProtocols,
+            external: None,
+            content: None,
+            bytes: None,
+            content_type: None,
+            cache_control: None,
             folder: None,
+            static_options: None,
+            circuit_breaker: None,
+            blocking_pool: None,
+            middleware: Vec::new(),
             f: None,
+            upgrade: Some(Arc::new(f)),
+            access_log: true,
         }
     }
 }
@@ -0,0 +1,124 @@
+//! Routers built separately from the server and mounted under a path prefix.
+
+use crate::webserver::Domain;
+use crate::webserver::middleware::Middleware;
+use crate::webserver::requests::HTTPRequest;
+use crate::webserver::responses::{HTTPResponse, StatusCode};
+use crate::webserver::route::{HTTPMethod, Route};
+
+/// A group of routes and middleware that can be mounted under a prefix with
+/// [`WebServer::mount`](crate::webserver::WebServer::mount).
+///
+/// Routes are registered relative to the router, so `/` becomes the mount
+/// prefix itself followed by `/`. Middleware added to a router only runs for
+/// requests below its prefix. Routers can be mounted into other routers;
+/// prefixes add up.
+///
+/// # Examples
+///
+/// ```rust
+/// use sunweb::webserver::Router;
+/// use sunweb::webserver::responses::HTTPResponse;
+/// use sunweb::webserver::responses::status_code::StatusCode;
+/// use sunweb::webserver::route::HTTPMethod;
+///
+/// let mut users = Router::new();
+/// users.add_custom_route("/", HTTPMethod::GET, |_request, _domain| {
+///     HTTPResponse::with_body(StatusCode::Ok, "all users")
+/// }, StatusCode::Ok);
+///
+/// let mut api = Router::new();
+/// api.mount("/users", users);
+/// ```
+#[derive(Clone, Default)]
+pub struct Router {
+    /// Routes with paths relative to the router.
+    pub(crate) routes: Vec<Route>,
+    /// Middleware with route patterns relative to the router.
+    pub(crate) middleware: Vec<Middleware>,
+}
+
+impl Router {
+    /// Creates an empty router.
+    pub fn new() -> Router {
+        Self::default()
+    }
+
+    /// Adds a custom route with a handler function.
+    ///
+    /// Works like [`WebServer::add_custom_route`](crate::webserver::WebServer::add_custom_route);
+    /// the domain is chosen when the router is mounted.
+    pub fn add_custom_route(
+        &mut self,
+        route: &str,
+        method: HTTPMethod,
+        f: impl Fn(HTTPRequest, &Domain) -> HTTPResponse + Send + Sync + 'static,
+        response_codes: StatusCode,
+    ) -> &mut Self {
+        self.routes.push(Route::new_custom(
+            route.to_string(),
+            method,
+            response_codes,
+            Domain::new("*"),
+            f,
+        ));
+        self
+    }
+
+    /// Adds a custom route whose handler can fail.
+    ///
+    /// Works like [`WebServer::add_custom_route_result`](crate::webserver::WebServer::add_custom_route_result).
+    pub fn add_custom_route_result<E: Into<HTTPResponse>>(
+        &mut self,
+        route: &str,
+        method: HTTPMethod,
+        f: impl Fn(HTTPRequest, &Domain) -> Result<HTTPResponse, E> + Send + Sync + 'static,
+        response_codes: StatusCode,
+    ) -> &mut Self {
+        self.add_custom_route(
+            route,
+            method,
+            move |request, domain| f(request, domain).unwrap_or_else(Into::into),
+            response_codes,
+        )
+    }
+
+    /// Runs `f` on every request below this router's prefix before it is routed.
+    pub fn add_request_middleware(&mut self, f: fn(&mut HTTPRequest)) -> &mut Self {
+        self.middleware
+            .push(Middleware::new_request(None, Some("/*".to_string()), f));
+        self
+    }
+
+    /// Runs `f` on the response to every request below this router's prefix.
+    pub fn add_response_middleware(&mut self, f: fn(&mut HTTPResponse)) -> &mut Self {
+        self.middleware
+            .push(Middleware::new_response(None, Some("/*".to_string()), f));
+        self
+    }
+
+    /// Mounts `router` under `prefix` within this router.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - Path prefix relative to this router, e.g. `/v1`.
+    /// * `router` - The router whose routes and middleware are moved in.
+    pub fn mount(&mut self, prefix: &str, router: Router) -> &mut Self {
+        let router = router.prefixed(prefix);
+        self.routes.extend(router.routes);
+        self.middleware.extend(router.middleware);
+        self
+    }
+
+    /// Prepends `prefix` to every route and middleware pattern.
+    pub(crate) fn prefixed(mut self, prefix: &str) -> Router {
+        let prefix = prefix.trim_end_matches('/');
+        for route in &mut self.routes {
+            route.route = format!("{prefix}{}", route.route);
+        }
+        for middleware in &mut self.middleware {
+            middleware.route = format!("{prefix}{}", middleware.route);
+        }
+        self
+    }
+}
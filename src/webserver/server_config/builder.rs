@@ -0,0 +1,322 @@
+//! Step-by-step construction of a [`ServerConfig`] that reports errors
+//! instead of panicking.
+
+use crate::webserver::http_packet::header::hsts_value;
+use crate::webserver::requests::HTTPRequest;
+use crate::webserver::responses::HTTPResponse;
+use crate::webserver::server_config::{ConfigError, ContinueHook, ServerConfig};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Builds a [`ServerConfig`], created with [`ServerConfig::builder`].
+///
+/// Every setting defaults to the value [`ServerConfig::new`] uses; only the
+/// address to bind to is required. Certificate files are read when
+/// [`build`](Self::build) is called, which returns a [`ConfigError`] instead
+/// of panicking when they are missing or invalid.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+/// use sunweb::webserver::responses::HTTPResponse;
+/// use sunweb::webserver::responses::status_code::StatusCode;
+/// use sunweb::webserver::route::HTTPMethod;
+/// use sunweb::webserver::{ConfigError, ServerConfig, WebServer};
+///
+/// let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+/// let dir = tempfile::tempdir().unwrap();
+/// let path = |name: &str| dir.path().join(name).display().to_string();
+/// std::fs::write(path("key.pem"), cert.signing_key.serialize_pem()).unwrap();
+/// std::fs::write(path("cert.pem"), cert.cert.pem()).unwrap();
+/// std::fs::write(path("ca.pem"), cert.cert.pem()).unwrap();
+///
+/// let config = ServerConfig::builder()
+///     .bind([127, 0, 0, 1], 8443)
+///     .tls(&path("key.pem"), &path("cert.pem"))
+///     .allow_client_cert(&path("ca.pem"))
+///     .alpn_protocols(&["h2", "http/1.1"])
+///     .base_domain("example.com")
+///     .security_headers(true)
+///     .hsts(86_400, false, false)
+///     .max_keepalive_requests(10)
+///     .read_timeout(Duration::from_secs(2))
+///     .write_timeout(Duration::from_secs(10))
+///     .handler_timeout(Duration::from_secs(30))
+///     .proxy_pool_size(4)
+///     .blocking_threads(2)
+///     .strict_parsing(true)
+///     .max_request_line_length(4 * 1024)
+///     .max_header_size(32 * 1024)
+///     .max_body_size(1024 * 1024)
+///     .tcp_nodelay(true)
+///     .listen_backlog(1024)
+///     .continue_hook(|_request| None)
+///     .build()
+///     .unwrap();
+///
+/// let mut server = WebServer::new(config);
+/// server.add_custom_route("/", HTTPMethod::GET, |_request, _domain| {
+///     HTTPResponse::with_body(StatusCode::Ok, "hello")
+/// }, StatusCode::Ok, None);
+/// let peer = "127.0.0.1:50000".parse().unwrap();
+/// let response = server.handle_request(b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n", peer);
+/// assert!(String::from_utf8_lossy(&response).contains("X-Frame-Options: DENY\r\n"));
+///
+/// assert_eq!(ServerConfig::builder().build().err(), Some(ConfigError::MissingBind));
+///
+/// let missing = ServerConfig::builder()
+///     .bind([127, 0, 0, 1], 8443)
+///     .tls(&path("key.pem"), &path("missing.pem"))
+///     .build();
+/// assert!(matches!(missing, Err(ConfigError::Certificate(_))));
+///
+/// let without_cert = ServerConfig::builder()
+///     .bind([127, 0, 0, 1], 8080)
+///     .alpn_protocols(&["h2"])
+///     .build();
+/// assert!(matches!(without_cert, Err(ConfigError::Tls(_))));
+/// ```
+#[derive(Default)]
+pub struct ServerConfigBuilder {
+    bind: Option<([u8; 4], u16)>,
+    tls: Option<(String, String)>,
+    client_ca: Option<(String, bool)>,
+    alpn_protocols: Vec<String>,
+    base_domain: Option<String>,
+    security_headers: bool,
+    hsts: Option<String>,
+    max_keepalive_requests: Option<u32>,
+    read_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
+    handler_timeout: Option<Duration>,
+    proxy_pool_size: Option<usize>,
+    blocking_threads: Option<usize>,
+    strict_parsing: bool,
+    max_request_line: Option<usize>,
+    max_header_size: Option<usize>,
+    max_body_size: Option<usize>,
+    tcp_nodelay: Option<bool>,
+    listen_backlog: Option<u32>,
+    continue_hook: Option<ContinueHook>,
+}
+
+impl ServerConfigBuilder {
+    /// Creates a builder with every setting at its default.
+    pub fn new() -> ServerConfigBuilder {
+        Self::default()
+    }
+
+    /// Sets the IPv4 address and port to listen on. Required.
+    pub fn bind(mut self, host: [u8; 4], port: u16) -> Self {
+        self.bind = Some((host, port));
+        self
+    }
+
+    /// Serves HTTPS with the PEM-encoded private key and certificate chain at
+    /// the given paths. See [`ServerConfig::add_cert`].
+    pub fn tls(mut self, private_key_pem: &str, cert_pem: &str) -> Self {
+        self.tls = Some((private_key_pem.to_string(), cert_pem.to_string()));
+        self
+    }
+
+    /// Requires client certificates signed by a CA in `ca_pem`.
+    /// See [`ServerConfig::require_client_cert`].
+    pub fn require_client_cert(mut self, ca_pem: &str) -> Self {
+        self.client_ca = Some((ca_pem.to_string(), true));
+        self
+    }
+
+    /// Accepts, but does not require, client certificates signed by a CA in
+    /// `ca_pem`. See [`ServerConfig::allow_client_cert`].
+    pub fn allow_client_cert(mut self, ca_pem: &str) -> Self {
+        self.client_ca = Some((ca_pem.to_string(), false));
+        self
+    }
+
+    /// Sets the ALPN protocols offered during the TLS handshake.
+    /// See [`ServerConfig::set_alpn_protocols`].
+    pub fn alpn_protocols(mut self, protocols: &[&str]) -> Self {
+        self.alpn_protocols = protocols.iter().map(|p| p.to_string()).collect();
+        self
+    }
+
+    /// Sets the base domain. See [`ServerConfig::set_base_domain`].
+    pub fn base_domain(mut self, base_domain: &str) -> Self {
+        self.base_domain = Some(base_domain.to_string());
+        self
+    }
+
+    /// Adds security headers to every response.
+    /// See [`ServerConfig::default_security_headers`].
+    pub fn security_headers(mut self, enabled: bool) -> Self {
+        self.security_headers = enabled;
+        self
+    }
+
+    /// Configures `Strict-Transport-Security`. See [`ServerConfig::set_hsts`].
+    pub fn hsts(mut self, max_age_seconds: u64, include_subdomains: bool, preload: bool) -> Self {
+        self.hsts = Some(hsts_value(max_age_seconds, include_subdomains, preload));
+        self
+    }
+
+    /// Limits the requests per keep-alive connection.
+    /// See [`ServerConfig::set_max_keepalive_requests`].
+    pub fn max_keepalive_requests(mut self, max_requests: u32) -> Self {
+        self.max_keepalive_requests = Some(max_requests);
+        self
+    }
+
+    /// Sets how long the server waits for a request.
+    /// See [`ServerConfig::set_read_timeout`].
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets how long writing a response may block.
+    /// See [`ServerConfig::set_write_timeout`].
+    pub fn write_timeout(mut self, timeout: Duration) -> Self {
+        self.write_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets how long a custom route handler may run.
+    /// See [`ServerConfig::set_handler_timeout`].
+    pub fn handler_timeout(mut self, timeout: Duration) -> Self {
+        self.handler_timeout = Some(timeout);
+        self
+    }
+
+    /// Reuses connections to proxy upstreams.
+    /// See [`ServerConfig::set_proxy_pool_size`].
+    pub fn proxy_pool_size(mut self, max_idle: usize) -> Self {
+        self.proxy_pool_size = Some(max_idle);
+        self
+    }
+
+    /// Sets how many threads run the handlers of blocking routes.
+    /// See [`ServerConfig::set_blocking_threads`].
+    pub fn blocking_threads(mut self, threads: usize) -> Self {
+        self.blocking_threads = Some(threads);
+        self
+    }
+
+    /// Rejects requests with malformed header lines.
+    /// See [`ServerConfig::set_strict_parsing`].
+    pub fn strict_parsing(mut self, enabled: bool) -> Self {
+        self.strict_parsing = enabled;
+        self
+    }
+
+    /// Limits the length of the request line.
+    /// See [`ServerConfig::set_max_request_line_length`].
+    pub fn max_request_line_length(mut self, max_bytes: usize) -> Self {
+        self.max_request_line = Some(max_bytes);
+        self
+    }
+
+    /// Limits the size of the request head.
+    /// See [`ServerConfig::set_max_header_size`].
+    pub fn max_header_size(mut self, max_bytes: usize) -> Self {
+        self.max_header_size = Some(max_bytes);
+        self
+    }
+
+    /// Limits the size a request body may decompress to.
+    /// See [`ServerConfig::set_max_body_size`].
+    pub fn max_body_size(mut self, max_bytes: usize) -> Self {
+        self.max_body_size = Some(max_bytes);
+        self
+    }
+
+    /// Enables or disables `TCP_NODELAY` on accepted connections.
+    /// See [`ServerConfig::set_tcp_nodelay`].
+    pub fn tcp_nodelay(mut self, enabled: bool) -> Self {
+        self.tcp_nodelay = Some(enabled);
+        self
+    }
+
+    /// Sets how many connections may wait to be accepted.
+    /// See [`ServerConfig::set_listen_backlog`].
+    pub fn listen_backlog(mut self, backlog: u32) -> Self {
+        self.listen_backlog = Some(backlog);
+        self
+    }
+
+    /// Sets a hook that may reject an upload before its body is sent.
+    /// See [`ServerConfig::set_continue_hook`].
+    pub fn continue_hook(
+        mut self,
+        hook: impl Fn(&HTTPRequest) -> Option<HTTPResponse> + Send + Sync + 'static,
+    ) -> Self {
+        self.continue_hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// Builds the configuration, reading any certificate files.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ConfigError`] if no address was bound, a certificate, key
+    /// or CA file cannot be loaded, or client certificates or ALPN protocols
+    /// are configured without [`tls`](Self::tls).
+    pub fn build(self) -> Result<ServerConfig, ConfigError> {
+        let (host, port) = self.bind.ok_or(ConfigError::MissingBind)?;
+        let mut config = ServerConfig::new(host, port);
+
+        if let Some(base_domain) = self.base_domain {
+            config.base_domain = base_domain;
+        }
+        config.security_headers = self.security_headers;
+        if let Some(hsts) = self.hsts {
+            config.hsts = hsts;
+        }
+        if let Some(max_requests) = self.max_keepalive_requests {
+            config.max_keepalive_requests = max_requests;
+        }
+        if let Some(timeout) = self.read_timeout {
+            config.read_timeout = timeout;
+        }
+        config.write_timeout = self.write_timeout;
+        config.handler_timeout = self.handler_timeout;
+        config.proxy_pool_size = self.proxy_pool_size;
+        if let Some(threads) = self.blocking_threads {
+            config.blocking_threads = threads;
+        }
+        config.strict_parsing = self.strict_parsing;
+        if let Some(max_bytes) = self.max_request_line {
+            config.max_request_line = max_bytes;
+        }
+        if let Some(max_bytes) = self.max_header_size {
+            config.max_header_size = max_bytes;
+        }
+        if let Some(max_bytes) = self.max_body_size {
+            config.max_body_size = max_bytes;
+        }
+        if let Some(enabled) = self.tcp_nodelay {
+            config.tcp_nodelay = enabled;
+        }
+        config.listen_backlog = self.listen_backlog;
+        config.continue_hook = self.continue_hook;
+
+        config.alpn_protocols = self
+            .alpn_protocols
+            .iter()
+            .map(|p| p.as_bytes().to_vec())
+            .collect();
+        if let Some((ca_pem, required)) = &self.client_ca {
+            config.client_verifier = Some(ServerConfig::client_verifier(ca_pem, *required)?);
+        }
+        if let Some((private_key_pem, cert_pem)) = &self.tls {
+            config.certificate = Some(ServerConfig::load_certificate(private_key_pem, cert_pem)?);
+            config.using_https = true;
+        }
+        config.build_tls_config()?;
+
+        match config.problems().first() {
+            Some(problem) => Err(ConfigError::Tls(problem.clone())),
+            None => Ok(config),
+        }
+    }
+}
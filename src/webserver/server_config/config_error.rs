@@ -0,0 +1,34 @@
+//! Errors returned when a server configuration cannot be built.
+
+use std::error::Error;
+use std::fmt;
+
+/// Why [`ServerConfigBuilder::build`](crate::webserver::ServerConfigBuilder::build)
+/// rejected a configuration.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConfigError {
+    /// No address to listen on was set.
+    MissingBind,
+    /// The certificate file could not be read or contains no certificate.
+    Certificate(String),
+    /// The private key file could not be read or contains no key.
+    PrivateKey(String),
+    /// The client CA file could not be read or contains no valid certificate.
+    ClientCa(String),
+    /// The TLS settings are inconsistent or were rejected by rustls.
+    Tls(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingBind => write!(f, "No address to bind to was configured"),
+            Self::Certificate(reason) => write!(f, "Invalid certificate: {}", reason),
+            Self::PrivateKey(reason) => write!(f, "Invalid private key: {}", reason),
+            Self::ClientCa(reason) => write!(f, "Invalid client CA certificate: {}", reason),
+            Self::Tls(reason) => write!(f, "Invalid TLS configuration: {}", reason),
+        }
+    }
+}
+
+impl Error for ConfigError {}
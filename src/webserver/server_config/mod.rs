@@ -1,6 +1,28 @@
+mod builder;
+mod config_error;
+
+pub use builder::ServerConfigBuilder;
+pub use config_error::ConfigError;
+
+use crate::webserver::client_handling::{
+    DEFAULT_MAX_BODY_SIZE, DEFAULT_MAX_HEADER_SIZE, DEFAULT_MAX_REQUEST_LINE,
+};
+use crate::webserver::http_packet::header::hsts_value;
+use crate::webserver::requests::HTTPRequest;
+use crate::webserver::responses::HTTPResponse;
+use rustls::RootCertStore;
 use rustls::ServerConfig as RustlsConfig;
+use rustls::server::WebPkiClientVerifier;
+use rustls::server::danger::ClientCertVerifier;
 use rustls_pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject};
+use std::num::NonZeroUsize;
 use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Callback deciding whether a request sent with `Expect: 100-continue` may
+/// send its body; `Some(response)` rejects it.
+pub(crate) type ContinueHook = Arc<dyn Fn(&HTTPRequest) -> Option<HTTPResponse> + Send + Sync>;
 
 /// Configuration for the web server.
 ///
@@ -26,6 +48,42 @@ pub struct ServerConfig {
     pub(crate) tls_config: Option<Arc<RustlsConfig>>,
     /// The base domain used for the server. Defaults to localhost.
     pub(crate) base_domain: String,
+    /// ALPN protocol identifiers offered during the TLS handshake, in order of preference.
+    pub(crate) alpn_protocols: Vec<Vec<u8>>,
+    /// Certificate chain and private key added with [`add_cert`](Self::add_cert).
+    certificate: Option<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>)>,
+    /// Whether security headers are added to every response.
+    pub(crate) security_headers: bool,
+    /// `Strict-Transport-Security` value sent over TLS when security headers are enabled.
+    pub(crate) hsts: String,
+    /// Verifier for client certificates; `None` disables client authentication.
+    client_verifier: Option<Arc<dyn ClientCertVerifier>>,
+    /// Number of requests served on one keep-alive connection before it is closed.
+    pub(crate) max_keepalive_requests: u32,
+    /// How long reading from a client may block.
+    pub(crate) read_timeout: Duration,
+    /// How long writing to a client may block; `None` blocks indefinitely.
+    pub(crate) write_timeout: Option<Duration>,
+    /// Idle upstream connections kept per proxy target; `None` disables reuse.
+    pub(crate) proxy_pool_size: Option<usize>,
+    /// How long a custom handler may run before `504` is sent; `None` waits indefinitely.
+    pub(crate) handler_timeout: Option<Duration>,
+    /// Number of threads running the handlers of blocking routes.
+    pub(crate) blocking_threads: usize,
+    /// Whether malformed header lines are rejected with `400`.
+    pub(crate) strict_parsing: bool,
+    /// Longest accepted request line in bytes; longer ones get `414`.
+    pub(crate) max_request_line: usize,
+    /// Largest accepted request head in bytes; larger ones get `431`.
+    pub(crate) max_header_size: usize,
+    /// Largest accepted request body in bytes, before and after decompression; larger ones get `413`.
+    pub(crate) max_body_size: usize,
+    /// Whether `TCP_NODELAY` is set on accepted connections.
+    pub(crate) tcp_nodelay: bool,
+    /// Length of the queue of pending connections; `None` keeps the system default.
+    pub(crate) listen_backlog: Option<u32>,
+    /// Inspects `Expect: 100-continue` requests before their body is read.
+    pub(crate) continue_hook: Option<ContinueHook>,
 }
 
 impl ServerConfig {
@@ -53,6 +111,24 @@ impl ServerConfig {
             using_https: false,
             tls_config: None,
             base_domain: String::from("localhost"),
+            alpn_protocols: Vec::new(),
+            certificate: None,
+            security_headers: false,
+            hsts: hsts_value(31_536_000, true, false),
+            client_verifier: None,
+            max_keepalive_requests: 100,
+            read_timeout: Duration::from_millis(500),
+            write_timeout: None,
+            proxy_pool_size: None,
+            handler_timeout: None,
+            blocking_threads: thread::available_parallelism().map_or(4, NonZeroUsize::get),
+            strict_parsing: false,
+            max_request_line: DEFAULT_MAX_REQUEST_LINE,
+            max_header_size: DEFAULT_MAX_HEADER_SIZE,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            tcp_nodelay: true,
+            listen_backlog: None,
+            continue_hook: None,
         }
     }
 
@@ -85,24 +161,238 @@ impl ServerConfig {
     ///     .expect("Failed to add certificate");
     /// ```
     pub fn add_cert(mut self, private_key_pem: String, cert_pem: String) -> Self {
-        let certs: Result<Vec<_>, _> = CertificateDer::pem_file_iter(cert_pem)
-            .unwrap()
-            .collect::<Result<Vec<_>, _>>();
-        let certs = certs.map_err(|e| format!("Failed to parse certificates: {}", e));
-        let key: PrivateKeyDer = PrivateKeyDer::from_pem_file(private_key_pem).unwrap();
-
-        if certs.clone().unwrap().is_empty() {
-            panic!("Failed to parse certificates");
+        self.certificate = Some(
+            Self::load_certificate(&private_key_pem, &cert_pem).unwrap_or_else(|e| panic!("{}", e)),
+        );
+        self.using_https = true;
+        self.build_tls_config().unwrap_or_else(|e| panic!("{}", e));
+        self
+    }
+
+    /// Requires every TLS client to present a certificate signed by one of the given CAs.
+    ///
+    /// Handshakes without a valid client certificate are rejected. The presented
+    /// certificate is available to handlers through
+    /// [`HTTPRequest::client_certificate`](crate::webserver::requests::HTTPRequest::client_certificate).
+    /// May be called before or after [`add_cert`](Self::add_cert).
+    ///
+    /// # Arguments
+    ///
+    /// * `ca_pem` - Path to the PEM file containing the trusted CA certificate(s).
+    ///
+    /// # Returns
+    ///
+    /// The updated `ServerConfig`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the CA file cannot be read or contains no valid certificates.
+    pub fn require_client_cert(mut self, ca_pem: String) -> Self {
+        self.client_verifier =
+            Some(Self::client_verifier(&ca_pem, true).unwrap_or_else(|e| panic!("{}", e)));
+        self.build_tls_config().unwrap_or_else(|e| panic!("{}", e));
+        self
+    }
+
+    /// Accepts, but does not require, TLS client certificates signed by one of the given CAs.
+    ///
+    /// Clients without a certificate are still served; an invalid certificate
+    /// fails the handshake. Handlers decide what to allow based on
+    /// [`HTTPRequest::client_certificate`](crate::webserver::requests::HTTPRequest::client_certificate).
+    ///
+    /// # Arguments
+    ///
+    /// * `ca_pem` - Path to the PEM file containing the trusted CA certificate(s).
+    ///
+    /// # Returns
+    ///
+    /// The updated `ServerConfig`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the CA file cannot be read or contains no valid certificates.
+    pub fn allow_client_cert(mut self, ca_pem: String) -> Self {
+        self.client_verifier =
+            Some(Self::client_verifier(&ca_pem, false).unwrap_or_else(|e| panic!("{}", e)));
+        self.build_tls_config().unwrap_or_else(|e| panic!("{}", e));
+        self
+    }
+
+    /// Creates a builder for a configuration.
+    ///
+    /// See [`ServerConfigBuilder`] for the available settings.
+    pub fn builder() -> ServerConfigBuilder {
+        ServerConfigBuilder::new()
+    }
+
+    /// Reads the certificate chain and private key from PEM files.
+    fn load_certificate(
+        private_key_pem: &str,
+        cert_pem: &str,
+    ) -> Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>), ConfigError> {
+        let certs = CertificateDer::pem_file_iter(cert_pem)
+            .map_err(|e| ConfigError::Certificate(format!("{}: {}", cert_pem, e)))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| ConfigError::Certificate(format!("{}: {}", cert_pem, e)))?;
+        if certs.is_empty() {
+            return Err(ConfigError::Certificate(format!(
+                "{}: no certificates found",
+                cert_pem
+            )));
         }
+        let key = PrivateKeyDer::from_pem_file(private_key_pem)
+            .map_err(|e| ConfigError::PrivateKey(format!("{}: {}", private_key_pem, e)))?;
+        Ok((certs, key))
+    }
 
-        let tls_config = RustlsConfig::builder()
-            .with_no_client_auth()
-            .with_single_cert(certs.unwrap(), key)
-            .map_err(|e| format!("Failed to create TLS config: {}", e));
+    /// Builds a verifier for client certificates signed by the CAs in `ca_pem`.
+    fn client_verifier(
+        ca_pem: &str,
+        required: bool,
+    ) -> Result<Arc<dyn ClientCertVerifier>, ConfigError> {
+        let error = |reason: String| ConfigError::ClientCa(format!("{}: {}", ca_pem, reason));
+        let mut roots = RootCertStore::empty();
+        for cert in CertificateDer::pem_file_iter(ca_pem).map_err(|e| error(e.to_string()))? {
+            let cert = cert.map_err(|e| error(e.to_string()))?;
+            roots.add(cert).map_err(|e| error(e.to_string()))?;
+        }
+        if roots.is_empty() {
+            return Err(error("no certificates found".to_string()));
+        }
 
-        self.tls_config = Some(Arc::new(tls_config.unwrap()));
-        self.using_https = true;
+        let builder = WebPkiClientVerifier::builder(Arc::new(roots));
+        let builder = match required {
+            true => builder,
+            false => builder.allow_unauthenticated(),
+        };
+        builder.build().map_err(|e| error(e.to_string()))
+    }
+
+    /// Rebuilds the rustls configuration from the certificate, client
+    /// verifier and ALPN settings. Does nothing until a certificate is added.
+    fn build_tls_config(&mut self) -> Result<(), ConfigError> {
+        let Some((certs, key)) = &self.certificate else {
+            return Ok(());
+        };
+
+        let builder = RustlsConfig::builder();
+        let builder = match &self.client_verifier {
+            Some(verifier) => builder.with_client_cert_verifier(Arc::clone(verifier)),
+            None => builder.with_no_client_auth(),
+        };
+        let mut tls_config = builder
+            .with_single_cert(certs.clone(), key.clone_key())
+            .map_err(|e| ConfigError::Tls(e.to_string()))?;
+        tls_config.alpn_protocols = self.alpn_protocols.clone();
+
+        self.tls_config = Some(Arc::new(tls_config));
+        Ok(())
+    }
 
+    /// Sets the ALPN protocol identifiers offered during the TLS handshake.
+    ///
+    /// Protocols are listed in order of preference (e.g. `"h2"`, `"http/1.1"`). The
+    /// protocol negotiated with a client is available to handlers through
+    /// [`HTTPRequest::alpn_protocol`](crate::webserver::requests::HTTPRequest::alpn_protocol).
+    /// Connections that negotiate `"h2"` are served over HTTP/2; all others fall
+    /// back to HTTP/1.1. May be called before or after [`add_cert`](Self::add_cert).
+    /// Has no effect on plain HTTP connections.
+    ///
+    /// # Arguments
+    ///
+    /// * `protocols` - The protocol identifiers to offer.
+    ///
+    /// # Returns
+    ///
+    /// The updated `ServerConfig`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use sunweb::webserver::ServerConfig;
+    ///
+    /// let config = ServerConfig::new([127, 0, 0, 1], 8443)
+    ///     .set_alpn_protocols(&["http/1.1"]);
+    /// ```
+    ///
+    /// Serving a GET request over HTTP/2:
+    ///
+    /// ```rust
+    /// use std::io::{Read, Write};
+    /// use std::net::{TcpListener, TcpStream};
+    /// use std::sync::Arc;
+    /// use std::thread;
+    /// use rustls::pki_types::ServerName;
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    /// let dir = tempfile::tempdir().unwrap();
+    /// let key_path = dir.path().join("key.pem");
+    /// let cert_path = dir.path().join("cert.pem");
+    /// std::fs::write(&key_path, cert.signing_key.serialize_pem()).unwrap();
+    /// std::fs::write(&cert_path, cert.cert.pem()).unwrap();
+    ///
+    /// let config = ServerConfig::new([127, 0, 0, 1], 0)
+    ///     .add_cert(key_path.display().to_string(), cert_path.display().to_string())
+    ///     .set_alpn_protocols(&["h2", "http/1.1"]);
+    /// let mut server = WebServer::new(config);
+    /// server.add_custom_route("/", HTTPMethod::GET, |request, _domain| {
+    ///     HTTPResponse::with_body(StatusCode::Ok, request.alpn_protocol().unwrap_or_default())
+    /// }, StatusCode::Ok, None);
+    ///
+    /// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    /// let addr = listener.local_addr().unwrap();
+    /// thread::spawn(move || {
+    ///     let (stream, _) = listener.accept().unwrap();
+    ///     server.serve_connection(stream);
+    /// });
+    ///
+    /// let mut roots = rustls::RootCertStore::empty();
+    /// roots.add(cert.cert.der().clone()).unwrap();
+    /// let mut client_config = rustls::ClientConfig::builder()
+    ///     .with_root_certificates(roots)
+    ///     .with_no_client_auth();
+    /// client_config.alpn_protocols = vec![b"h2".to_vec()];
+    ///
+    /// let conn = rustls::ClientConnection::new(
+    ///     Arc::new(client_config),
+    ///     ServerName::try_from("localhost").unwrap(),
+    /// ).unwrap();
+    /// let mut tls = rustls::StreamOwned::new(conn, TcpStream::connect(addr).unwrap());
+    ///
+    /// // Connection preface, an empty SETTINGS frame, and a HEADERS frame for
+    /// // `GET https://localhost/` on stream 1 (END_STREAM | END_HEADERS).
+    /// let mut request = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n".to_vec();
+    /// request.extend_from_slice(&[0, 0, 0, 0x4, 0, 0, 0, 0, 0]);
+    /// let block = [&[0x82, 0x84, 0x87, 0x41, 9][..], b"localhost"].concat();
+    /// request.extend_from_slice(&[0, 0, block.len() as u8, 0x1, 0x5, 0, 0, 0, 1]);
+    /// request.extend_from_slice(&block);
+    /// tls.write_all(&request).unwrap();
+    ///
+    /// let (mut headers, mut body) = (Vec::new(), Vec::new());
+    /// loop {
+    ///     let mut frame = [0u8; 9];
+    ///     tls.read_exact(&mut frame).unwrap();
+    ///     let mut payload = vec![0; u32::from_be_bytes([0, frame[0], frame[1], frame[2]]) as usize];
+    ///     tls.read_exact(&mut payload).unwrap();
+    ///     match frame[3] {
+    ///         0x1 => headers = payload,
+    ///         0x0 => body.extend_from_slice(&payload),
+    ///         _ => continue,
+    ///     }
+    ///     if frame[4] & 0x1 != 0 {
+    ///         break;
+    ///     }
+    /// }
+    /// assert!(headers.windows(3).any(|w| w == b"200"));
+    /// assert_eq!(body, b"h2");
+    /// ```
+    pub fn set_alpn_protocols(mut self, protocols: &[&str]) -> Self {
+        self.alpn_protocols = protocols.iter().map(|p| p.as_bytes().to_vec()).collect();
+        self.build_tls_config().unwrap_or_else(|e| panic!("{}", e));
         self
     }
     /// Sets the base domain for the server.
@@ -131,6 +421,818 @@ impl ServerConfig {
         self
     }
 
+    /// Adds a conservative set of security headers to every response.
+    ///
+    /// The headers are the ones set by
+    /// [`HTTPResponse::apply_security_headers`](crate::webserver::responses::HTTPResponse::apply_security_headers).
+    /// Responses sent over TLS also get `Strict-Transport-Security`, configured with
+    /// [`set_hsts`](Self::set_hsts). Any of them a handler sets explicitly is left
+    /// as-is. Disabled by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether to add the headers.
+    ///
+    /// # Returns
+    ///
+    /// The updated `ServerConfig`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let config = ServerConfig::new([127, 0, 0, 1], 8080).default_security_headers(true);
+    /// let mut server = WebServer::new(config);
+    /// server.add_custom_route("/", HTTPMethod::GET, |_request, _domain| {
+    ///     HTTPResponse::with_body(StatusCode::Ok, "hello")
+    /// }, StatusCode::Ok, None);
+    /// server.add_custom_route("/embed", HTTPMethod::GET, |_request, _domain| {
+    ///     let mut response = HTTPResponse::with_body(StatusCode::Ok, "embeddable");
+    ///     response.set_csp("frame-ancestors *");
+    ///     response
+    /// }, StatusCode::Ok, None);
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let response = server.handle_request(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n", peer);
+    /// let response = String::from_utf8_lossy(&response);
+    /// assert!(response.contains("X-Content-Type-Options: nosniff\r\n"));
+    /// assert!(response.contains("X-Frame-Options: DENY\r\n"));
+    /// assert!(response.contains("Content-Security-Policy: default-src 'self'\r\n"));
+    ///
+    /// let response = server.handle_request(b"GET /embed HTTP/1.1\r\nHost: localhost\r\n\r\n", peer);
+    /// let response = String::from_utf8_lossy(&response);
+    /// assert!(response.contains("Content-Security-Policy: frame-ancestors *\r\n"));
+    /// assert!(response.contains("X-Content-Type-Options: nosniff\r\n"));
+    /// ```
+    pub fn default_security_headers(mut self, enabled: bool) -> Self {
+        self.security_headers = enabled;
+        self
+    }
+
+    /// Configures the `Strict-Transport-Security` header sent over TLS.
+    ///
+    /// Only takes effect together with
+    /// [`default_security_headers`](Self::default_security_headers). HSTS is never
+    /// sent on plain HTTP connections. Defaults to one year with `includeSubDomains`.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_age_seconds` - How long browsers should remember to use HTTPS.
+    /// * `include_subdomains` - Whether the policy also covers all subdomains.
+    /// * `preload` - Whether to mark the domain as eligible for browser preload lists.
+    ///
+    /// # Returns
+    ///
+    /// The updated `ServerConfig`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::{Read, Write};
+    /// use std::net::{TcpListener, TcpStream};
+    /// use std::sync::{Arc, Mutex};
+    /// use std::thread;
+    /// use rustls::pki_types::ServerName;
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    /// let dir = tempfile::tempdir().unwrap();
+    /// let key_path = dir.path().join("key.pem");
+    /// let cert_path = dir.path().join("cert.pem");
+    /// std::fs::write(&key_path, cert.signing_key.serialize_pem()).unwrap();
+    /// std::fs::write(&cert_path, cert.cert.pem()).unwrap();
+    ///
+    /// let config = ServerConfig::new([127, 0, 0, 1], 0)
+    ///     .add_cert(key_path.display().to_string(), cert_path.display().to_string())
+    ///     .default_security_headers(true)
+    ///     .set_hsts(86400, true, true);
+    /// let mut server = WebServer::new(config);
+    /// server.add_custom_route("/", HTTPMethod::GET, |_request, _domain| {
+    ///     HTTPResponse::with_body(StatusCode::Ok, "hello")
+    /// }, StatusCode::Ok, None);
+    ///
+    /// // Plain HTTP: no HSTS.
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let plain = server.handle_request(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n", peer);
+    /// assert!(!String::from_utf8_lossy(&plain).contains("Strict-Transport-Security"));
+    ///
+    /// // TLS: HSTS is added.
+    /// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    /// let addr = listener.local_addr().unwrap();
+    /// thread::spawn(move || {
+    ///     let (stream, _) = listener.accept().unwrap();
+    ///     server.serve_connection(stream);
+    /// });
+    ///
+    /// let mut roots = rustls::RootCertStore::empty();
+    /// roots.add(cert.cert.der().clone()).unwrap();
+    /// let client_config = rustls::ClientConfig::builder()
+    ///     .with_root_certificates(roots)
+    ///     .with_no_client_auth();
+    /// let conn = rustls::ClientConnection::new(
+    ///     Arc::new(client_config),
+    ///     ServerName::try_from("localhost").unwrap(),
+    /// ).unwrap();
+    /// let mut tls = rustls::StreamOwned::new(conn, TcpStream::connect(addr).unwrap());
+    /// tls.write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n").unwrap();
+    ///
+    /// let mut response = Vec::new();
+    /// let _ = tls.read_to_end(&mut response);
+    /// let response = String::from_utf8_lossy(&response);
+    /// assert!(response.contains("Strict-Transport-Security: max-age=86400; includeSubDomains; preload\r\n"));
+    /// ```
+    pub fn set_hsts(
+        mut self,
+        max_age_seconds: u64,
+        include_subdomains: bool,
+        preload: bool,
+    ) -> Self {
+        self.hsts = hsts_value(max_age_seconds, include_subdomains, preload);
+        self
+    }
+
+    /// Limits how many requests a single keep-alive connection may send.
+    ///
+    /// The response to the last allowed request carries `Connection: close` and the
+    /// connection is closed afterwards, so one client cannot hold on to a worker
+    /// indefinitely. At least one request is always served. Defaults to 100.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_requests` - The number of requests served per connection.
+    ///
+    /// # Returns
+    ///
+    /// The updated `ServerConfig`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::{Read, Write};
+    /// use std::net::{TcpListener, TcpStream};
+    /// use std::sync::Arc;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::thread;
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let config = ServerConfig::new([127, 0, 0, 1], 0).set_max_keepalive_requests(2);
+    /// let mut server = WebServer::new(config);
+    /// let served = Arc::new(AtomicUsize::new(0));
+    /// let counter = Arc::clone(&served);
+    /// server.add_custom_route("/", HTTPMethod::GET, move |_request, _domain| {
+    ///     counter.fetch_add(1, Ordering::SeqCst);
+    ///     HTTPResponse::with_body(StatusCode::Ok, "ok")
+    /// }, StatusCode::Ok, None);
+    ///
+    /// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    /// let addr = listener.local_addr().unwrap();
+    /// thread::spawn(move || {
+    ///     let (stream, _) = listener.accept().unwrap();
+    ///     server.serve_connection(stream);
+    /// });
+    ///
+    /// let mut client = TcpStream::connect(addr).unwrap();
+    /// client.write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+    /// let mut first = Vec::new();
+    /// while !first.ends_with(b"ok") {
+    ///     let mut chunk = [0u8; 1024];
+    ///     let n = client.read(&mut chunk).unwrap();
+    ///     first.extend_from_slice(&chunk[..n]);
+    /// }
+    /// assert!(String::from_utf8_lossy(&first).contains("Connection: keep-alive\r\n"));
+    ///
+    /// // The second request reaches the limit: the server answers and hangs up.
+    /// client.write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+    /// let mut second = String::new();
+    /// client.read_to_string(&mut second).unwrap();
+    /// assert!(second.contains("Connection: close\r\n"));
+    /// assert_eq!(served.load(Ordering::SeqCst), 2);
+    /// ```
+    pub fn set_max_keepalive_requests(mut self, max_requests: u32) -> Self {
+        self.max_keepalive_requests = max_requests;
+        self
+    }
+
+    /// Sets how long reading from a client may block.
+    ///
+    /// A request whose headers do not arrive within this time is answered with
+    /// `408 Request Timeout`, and one whose body does not arrive is handled with
+    /// what has been received so far. An idle keep-alive connection is closed
+    /// without a response. Raise it for slow clients such as mobile networks.
+    /// Defaults to 500 ms.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The maximum time a single read may block.
+    ///
+    /// # Returns
+    ///
+    /// The updated `ServerConfig`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::{Read, Write};
+    /// use std::net::{TcpListener, TcpStream};
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let config = ServerConfig::new([127, 0, 0, 1], 0).set_read_timeout(Duration::from_secs(3));
+    /// let mut server = WebServer::new(config);
+    /// server.add_custom_route("/upload", HTTPMethod::POST, |request, _domain| {
+    ///     let body = String::from_utf8_lossy(request.body().unwrap_or_default()).into_owned();
+    ///     HTTPResponse::with_body(StatusCode::Ok, body)
+    /// }, StatusCode::Ok, None);
+    ///
+    /// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    /// let addr = listener.local_addr().unwrap();
+    /// thread::spawn(move || {
+    ///     let (stream, _) = listener.accept().unwrap();
+    ///     server.serve_connection(stream);
+    /// });
+    ///
+    /// // The body arrives well after the default 500 ms, but within the configured timeout.
+    /// let mut client = TcpStream::connect(addr).unwrap();
+    /// client.write_all(b"POST /upload HTTP/1.1\r\nHost: localhost\r\nContent-Length: 4\r\n\
+    ///     Connection: close\r\n\r\n").unwrap();
+    /// thread::sleep(Duration::from_millis(900));
+    /// client.write_all(b"slow").unwrap();
+    ///
+    /// let mut response = String::new();
+    /// client.read_to_string(&mut response).unwrap();
+    /// assert!(response.starts_with("HTTP/1.1 200 OK"));
+    /// assert!(response.ends_with("slow"));
+    /// ```
+    ///
+    /// A client that closes its connection cleanly gets no response, while one
+    /// that stops halfway through the headers is told it took too long:
+    ///
+    /// ```rust
+    /// use std::io::{Read, Write};
+    /// use std::net::{Shutdown, TcpListener, TcpStream};
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let config = ServerConfig::new([127, 0, 0, 1], 0).set_read_timeout(Duration::from_millis(200));
+    /// let server = WebServer::new(config);
+    ///
+    /// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    /// let addr = listener.local_addr().unwrap();
+    /// thread::spawn(move || {
+    ///     for stream in listener.incoming().take(2) {
+    ///         server.serve_connection(stream.unwrap());
+    ///     }
+    /// });
+    ///
+    /// let mut client = TcpStream::connect(addr).unwrap();
+    /// client.shutdown(Shutdown::Write).unwrap();
+    /// let mut response = Vec::new();
+    /// client.read_to_end(&mut response).unwrap();
+    /// assert!(response.is_empty());
+    ///
+    /// let mut client = TcpStream::connect(addr).unwrap();
+    /// client.write_all(b"GET / HTTP/1.1\r\nHost: local").unwrap();
+    /// let mut response = String::new();
+    /// client.read_to_string(&mut response).unwrap();
+    /// assert!(response.starts_with("HTTP/1.1 408 Request Timeout\r\n"));
+    /// assert!(response.contains("Connection: close\r\n"));
+    /// ```
+    pub fn set_read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = timeout;
+        self
+    }
+
+    /// Sets how long writing a response to a client may block.
+    ///
+    /// Protects workers from clients that stop reading. By default writes block
+    /// indefinitely.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The maximum time a single write may block.
+    ///
+    /// # Returns
+    ///
+    /// The updated `ServerConfig`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use sunweb::webserver::ServerConfig;
+    ///
+    /// let config = ServerConfig::new([127, 0, 0, 1], 8080).set_write_timeout(Duration::from_secs(10));
+    /// ```
+    pub fn set_write_timeout(mut self, timeout: Duration) -> Self {
+        self.write_timeout = Some(timeout);
+        self
+    }
+
+    /// Keeps connections to proxy upstreams open and reuses them.
+    ///
+    /// By default every proxied request opens a new TCP (and TLS) connection.
+    /// With a pool, idle connections are kept per `(host, port, scheme)` and
+    /// reused by later requests; a stale connection is replaced transparently.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_idle` - The maximum number of idle connections kept per upstream.
+    ///
+    /// # Returns
+    ///
+    /// The updated `ServerConfig`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::{BufRead, BufReader, Write};
+    /// use std::net::TcpListener;
+    /// use std::sync::Arc;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::thread;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// // An upstream that counts connections and answers every request on them.
+    /// let upstream = TcpListener::bind("127.0.0.1:0").unwrap();
+    /// let upstream_addr = upstream.local_addr().unwrap();
+    /// let connections = Arc::new(AtomicUsize::new(0));
+    /// let accepted = Arc::clone(&connections);
+    /// thread::spawn(move || {
+    ///     for stream in upstream.incoming() {
+    ///         accepted.fetch_add(1, Ordering::SeqCst);
+    ///         let mut stream = stream.unwrap();
+    ///         thread::spawn(move || {
+    ///             let mut reader = BufReader::new(stream.try_clone().unwrap());
+    ///             let mut line = String::new();
+    ///             loop {
+    ///                 line.clear();
+    ///                 if reader.read_line(&mut line).unwrap_or(0) == 0 {
+    ///                     break;
+    ///                 }
+    ///                 if line == "\r\n" {
+    ///                     stream
+    ///                         .write_all(b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 2\r\n\r\nhi")
+    ///                         .unwrap();
+    ///                 }
+    ///             }
+    ///         });
+    ///     }
+    /// });
+    ///
+    /// let config = ServerConfig::new([127, 0, 0, 1], 8080).set_proxy_pool_size(4);
+    /// let mut server = WebServer::new(config);
+    /// server.add_proxy_route("/api", &format!("http://{upstream_addr}"), StatusCode::Ok, None);
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// for _ in 0..2 {
+    ///     let response = server.handle_request(b"GET /api HTTP/1.1\r\nHost: localhost\r\n\r\n", peer);
+    ///     assert!(response.ends_with(b"\r\n\r\nhi"));
+    /// }
+    /// // Both requests went over the same upstream connection.
+    /// assert_eq!(connections.load(Ordering::SeqCst), 1);
+    /// ```
+    pub fn set_proxy_pool_size(mut self, max_idle: usize) -> Self {
+        self.proxy_pool_size = Some(max_idle);
+        self
+    }
+
+    /// Sets how long a custom route handler may run.
+    ///
+    /// When a handler exceeds the timeout the client receives
+    /// `504 Gateway Timeout` and the worker moves on to the next request. The
+    /// handler keeps running on its own thread until it returns, and its
+    /// response is discarded. By default handlers run on the worker thread
+    /// without a limit.
+    ///
+    /// Handlers cannot be interrupted, so a handler that never returns holds
+    /// its thread forever. At most 256 such threads run at once; past that,
+    /// timed handlers are not started and the client receives
+    /// `503 Service Unavailable`. Handlers on a blocking pool run on the
+    /// pool's threads instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The maximum time a handler may take to produce a response.
+    ///
+    /// # Returns
+    ///
+    /// The updated `ServerConfig`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::thread;
+    /// use std::time::{Duration, Instant};
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let config = ServerConfig::new([127, 0, 0, 1], 8080).set_handler_timeout(Duration::from_millis(100));
+    /// let mut server = WebServer::new(config);
+    /// server.add_custom_route("/slow", HTTPMethod::GET, |_request, _domain| {
+    ///     thread::sleep(Duration::from_secs(5));
+    ///     HTTPResponse::with_body(StatusCode::Ok, "too late")
+    /// }, StatusCode::Ok, None);
+    /// server.add_custom_route("/fast", HTTPMethod::GET, |_request, _domain| {
+    ///     HTTPResponse::with_body(StatusCode::Ok, "done")
+    /// }, StatusCode::Ok, None);
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let started = Instant::now();
+    /// let response = server.handle_request(b"GET /slow HTTP/1.1\r\nHost: localhost\r\n\r\n", peer);
+    /// assert!(response.starts_with(b"HTTP/1.1 504 Gateway Timeout"));
+    /// assert!(started.elapsed() < Duration::from_secs(5));
+    ///
+    /// let response = server.handle_request(b"GET /fast HTTP/1.1\r\nHost: localhost\r\n\r\n", peer);
+    /// assert!(response.ends_with(b"done"));
+    /// ```
+    pub fn set_handler_timeout(mut self, timeout: Duration) -> Self {
+        self.handler_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets how many threads run the handlers of blocking routes.
+    ///
+    /// Routes added with [`WebServer::add_blocking_route`](crate::webserver::WebServer::add_blocking_route)
+    /// share these threads, so at most this many of their handlers run at
+    /// once; further requests wait for a free thread. Defaults to the number
+    /// of available CPU cores.
+    ///
+    /// # Arguments
+    ///
+    /// * `threads` - The number of pool threads; `0` is treated as `1`.
+    ///
+    /// # Returns
+    ///
+    /// The updated `ServerConfig`.
+    pub fn set_blocking_threads(mut self, threads: usize) -> Self {
+        self.blocking_threads = threads;
+        self
+    }
+
+    /// Rejects requests with malformed header lines.
+    ///
+    /// By default header lines are split at the first colon and anything is
+    /// accepted. In strict mode requests are parsed with
+    /// [`HTTPRequest::parse_strict`]: field names must be tokens and values
+    /// may not contain control characters such as a bare CR, LF or NUL, so
+    /// headers reflected into a response cannot inject further lines.
+    /// Offending requests are answered with `400 Bad Request` and the
+    /// connection is closed.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - `true` to validate header lines.
+    ///
+    /// # Returns
+    ///
+    /// The updated `ServerConfig`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let config = ServerConfig::new([127, 0, 0, 1], 8080).set_strict_parsing(true);
+    /// let mut server = WebServer::new(config);
+    /// server.add_custom_route("/", HTTPMethod::GET, |request, _domain| {
+    ///     HTTPResponse::with_body(StatusCode::Ok, request.get_header("X-Name").unwrap_or_default())
+    /// }, StatusCode::Ok, None);
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let send = |raw: &[u8]| String::from_utf8(server.handle_request(raw, peer)).unwrap();
+    ///
+    /// let ok = send(b"GET / HTTP/1.1\r\nHost: localhost\r\nX-Name: sun\r\n\r\n");
+    /// assert!(ok.ends_with("\r\n\r\nsun"));
+    ///
+    /// let injected = send(b"GET / HTTP/1.1\r\nHost: localhost\r\nX-Name: sun\rSet-Cookie: evil\r\n\r\n");
+    /// assert!(injected.starts_with("HTTP/1.1 400 Bad Request"));
+    /// assert!(injected.contains("Connection: close\r\n"));
+    ///
+    /// let bad_name = send(b"GET / HTTP/1.1\r\nHost: localhost\r\nX Name: sun\r\n\r\n");
+    /// assert!(bad_name.starts_with("HTTP/1.1 400 Bad Request"));
+    /// ```
+    pub fn set_strict_parsing(mut self, enabled: bool) -> Self {
+        self.strict_parsing = enabled;
+        self
+    }
+
+    /// Limits the length of the request line, e.g. `GET /path?query HTTP/1.1`.
+    ///
+    /// Requests whose line exceeds `max_bytes` are answered with
+    /// `414 URI Too Long` and the connection is closed, before the rest of
+    /// the request is read. Defaults to 8 KiB.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_bytes` - The longest accepted request line, without its line break.
+    ///
+    /// # Returns
+    ///
+    /// The updated `ServerConfig`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let config = ServerConfig::new([127, 0, 0, 1], 8080).set_max_request_line_length(64);
+    /// let mut server = WebServer::new(config);
+    /// server.add_custom_route("/", HTTPMethod::GET, |_request, _domain| {
+    ///     HTTPResponse::with_body(StatusCode::Ok, "found")
+    /// }, StatusCode::Ok, None);
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    ///
+    /// let short = server.handle_request(b"GET /?q=short HTTP/1.1\r\nHost: localhost\r\n\r\n", peer);
+    /// assert!(short.starts_with(b"HTTP/1.1 200 OK"));
+    ///
+    /// let raw = format!("GET /?q={} HTTP/1.1\r\nHost: localhost\r\n\r\n", "a".repeat(100));
+    /// let response = String::from_utf8(server.handle_request(raw.as_bytes(), peer)).unwrap();
+    /// assert!(response.starts_with("HTTP/1.1 414 URI Too Long"));
+    /// assert!(response.contains("Connection: close\r\n"));
+    /// ```
+    pub fn set_max_request_line_length(mut self, max_bytes: usize) -> Self {
+        self.max_request_line = max_bytes;
+        self
+    }
+
+    /// Limits the size of the request head: the request line and all headers.
+    ///
+    /// Requests whose head exceeds `max_bytes` are answered with
+    /// `431 Request Header Fields Too Large` and the connection is closed, so
+    /// an endless header block cannot exhaust memory. Defaults to 64 KiB.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_bytes` - The largest accepted head, including the blank line ending it.
+    ///
+    /// # Returns
+    ///
+    /// The updated `ServerConfig`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let config = ServerConfig::new([127, 0, 0, 1], 8080).set_max_header_size(4 * 1024);
+    /// let server = WebServer::new(config);
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    ///
+    /// // The headers never end; the server answers once the limit is passed.
+    /// let mut raw = String::from("GET / HTTP/1.1\r\nHost: localhost\r\n");
+    /// for i in 0..100 {
+    ///     raw.push_str(&format!("X-Filler-{i}: {}\r\n", "x".repeat(100)));
+    /// }
+    /// let response = String::from_utf8(server.handle_request(raw.as_bytes(), peer)).unwrap();
+    /// assert!(response.starts_with("HTTP/1.1 431 Request Header Fields Too Large"));
+    /// assert!(response.contains("Connection: close\r\n"));
+    /// ```
+    pub fn set_max_header_size(mut self, max_bytes: usize) -> Self {
+        self.max_header_size = max_bytes;
+        self
+    }
+
+    /// Limits the size a request body may decompress to.
+    ///
+    /// A body sent with `Content-Encoding: gzip` or `deflate` stops being
+    /// decoded once its output passes the limit, so a small compressed body
+    /// cannot expand into gigabytes. Such requests are answered with
+    /// `413 Content Too Large` and the connection is closed. Bodies without a
+    /// `Content-Encoding` are not affected, except over HTTP/2, where each
+    /// stream's body is buffered in full and is held to the same limit.
+    /// Defaults to 10 MiB.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_bytes` - The largest accepted decompressed body.
+    ///
+    /// # Returns
+    ///
+    /// The updated `ServerConfig`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use flate2::Compression;
+    /// use flate2::write::GzEncoder;
+    /// use std::io::Write;
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let config = ServerConfig::new([127, 0, 0, 1], 8080).set_max_body_size(64 * 1024);
+    /// let mut server = WebServer::new(config);
+    /// server.add_custom_route("/upload", HTTPMethod::POST, |request, _domain| {
+    ///     HTTPResponse::with_body(StatusCode::Ok, request.body().unwrap_or_default().len().to_string())
+    /// }, StatusCode::Ok, None);
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    ///
+    /// let upload = |body: &[u8]| {
+    ///     let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    ///     encoder.write_all(body).unwrap();
+    ///     let gzipped = encoder.finish().unwrap();
+    ///     let mut raw = format!(
+    ///         "POST /upload HTTP/1.1\r\nHost: localhost\r\n\
+    ///          Content-Encoding: gzip\r\nContent-Length: {}\r\n\r\n",
+    ///         gzipped.len()
+    ///     )
+    ///     .into_bytes();
+    ///     raw.extend_from_slice(&gzipped);
+    ///     String::from_utf8_lossy(&server.handle_request(&raw, peer)).into_owned()
+    /// };
+    ///
+    /// let response = upload(&[b'a'; 1024]);
+    /// assert!(response.starts_with("HTTP/1.1 200 OK"));
+    /// assert!(response.ends_with("\r\n\r\n1024"));
+    ///
+    /// // 8 MiB of zeros compress to a few kilobytes but expand past the limit.
+    /// let bomb = vec![0u8; 8 * 1024 * 1024];
+    /// let response = upload(&bomb);
+    /// assert!(response.starts_with("HTTP/1.1 413 Content Too Large"));
+    /// assert!(response.contains("Connection: close\r\n"));
+    ///
+    /// let mut raw = b"POST /upload HTTP/1.1\r\nHost: localhost\r\nContent-Length: 100000\r\n\r\n".to_vec();
+    /// raw.extend_from_slice(&[b'a'; 100_000]);
+    /// let response = server.handle_request(&raw, peer);
+    /// assert!(response.ends_with(b"\r\n\r\n100000"));
+    /// ```
+    pub fn set_max_body_size(mut self, max_bytes: usize) -> Self {
+        self.max_body_size = max_bytes;
+        self
+    }
+
+    /// Enables or disables `TCP_NODELAY` on accepted connections.
+    ///
+    /// With it enabled, small responses are sent at once instead of waiting
+    /// for Nagle's algorithm to coalesce them with later writes. Applies to
+    /// every TCP connection the server serves, including ones handed to
+    /// [`serve_connection`](crate::webserver::WebServer::serve_connection).
+    /// Defaults to `true`.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether to disable Nagle's algorithm.
+    ///
+    /// # Returns
+    ///
+    /// The updated `ServerConfig`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::{Read, Write};
+    /// use std::net::{TcpListener, TcpStream};
+    /// use std::thread;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let accepted = |config: ServerConfig| {
+    ///     let server = WebServer::new(config);
+    ///     let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    ///     let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    ///     let (stream, _) = listener.accept().unwrap();
+    ///     let observed = stream.try_clone().unwrap();
+    ///     let serving = thread::spawn(move || server.serve_connection(stream));
+    ///
+    ///     client.write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n").unwrap();
+    ///     serving.join().unwrap();
+    ///     let nodelay = observed.nodelay().unwrap();
+    ///
+    ///     drop(observed);
+    ///     let mut response = String::new();
+    ///     client.read_to_string(&mut response).unwrap();
+    ///     assert!(response.starts_with("HTTP/1.1 404 Not Found"));
+    ///     nodelay
+    /// };
+    ///
+    /// assert!(accepted(ServerConfig::new([127, 0, 0, 1], 0)));
+    /// assert!(!accepted(ServerConfig::new([127, 0, 0, 1], 0).set_tcp_nodelay(false)));
+    /// ```
+    pub fn set_tcp_nodelay(mut self, enabled: bool) -> Self {
+        self.tcp_nodelay = enabled;
+        self
+    }
+
+    /// Sets how many connections may wait to be accepted.
+    ///
+    /// Connections arriving while the queue is full are refused or retried
+    /// by the client, depending on the system. Without this setting the
+    /// standard library's default is used. The value is capped by the
+    /// system, e.g. `net.core.somaxconn` on Linux.
+    ///
+    /// # Arguments
+    ///
+    /// * `backlog` - The length of the queue of pending connections.
+    ///
+    /// # Returns
+    ///
+    /// The updated `ServerConfig`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::{Read, Write};
+    /// use std::net::{TcpListener, TcpStream};
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    /// let config = ServerConfig::new([127, 0, 0, 1], port).set_listen_backlog(1024);
+    /// thread::spawn(move || WebServer::new(config).start_blocking().unwrap());
+    ///
+    /// let mut client = loop {
+    ///     match TcpStream::connect(("127.0.0.1", port)) {
+    ///         Ok(stream) => break stream,
+    ///         Err(_) => thread::sleep(Duration::from_millis(10)),
+    ///     }
+    /// };
+    /// client.write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n").unwrap();
+    /// let mut response = String::new();
+    /// client.read_to_string(&mut response).unwrap();
+    /// assert!(response.starts_with("HTTP/1.1 404 Not Found"));
+    /// ```
+    pub fn set_listen_backlog(mut self, backlog: u32) -> Self {
+        self.listen_backlog = Some(backlog);
+        self
+    }
+
+    /// Sets a hook that may reject an upload before its body is sent.
+    ///
+    /// When a client sends `Expect: 100-continue`, the hook is called with the
+    /// request line and headers (the request has no body yet). Returning `None`
+    /// lets the server answer `100 Continue` and read the body as usual;
+    /// returning a response, such as `413 Content Too Large` or
+    /// `401 Unauthorized`, sends it instead and closes the connection.
+    ///
+    /// # Arguments
+    ///
+    /// * `hook` - Decides whether the body may be sent.
+    ///
+    /// # Returns
+    ///
+    /// The updated `ServerConfig`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use sunweb::webserver::responses::HTTPResponse;
+    /// use sunweb::webserver::responses::status_code::StatusCode;
+    /// use sunweb::webserver::route::HTTPMethod;
+    /// use sunweb::webserver::{ServerConfig, WebServer};
+    ///
+    /// let config = ServerConfig::new([127, 0, 0, 1], 8080).set_continue_hook(|request| {
+    ///     (request.content_length()? > 1024).then(|| HTTPResponse::new(StatusCode::ContentTooLarge))
+    /// });
+    /// let mut server = WebServer::new(config);
+    /// server.add_custom_route("/upload", HTTPMethod::POST, |_request, _domain| {
+    ///     HTTPResponse::with_body(StatusCode::Ok, "stored")
+    /// }, StatusCode::Ok, None);
+    ///
+    /// let peer = "127.0.0.1:50000".parse().unwrap();
+    /// let raw = b"POST /upload HTTP/1.1\r\nHost: localhost\r\nContent-Length: 1048576\r\n\
+    ///     Expect: 100-continue\r\n\r\n";
+    /// let response = String::from_utf8(server.handle_request(raw, peer)).unwrap();
+    /// assert!(response.starts_with("HTTP/1.1 413 Content Too Large\r\n"));
+    /// assert!(response.contains("Connection: close\r\n"));
+    ///
+    /// let raw = b"POST /upload HTTP/1.1\r\nHost: localhost\r\nContent-Length: 5\r\n\
+    ///     Expect: 100-continue\r\n\r\n";
+    /// let response = String::from_utf8(server.handle_request(raw, peer)).unwrap();
+    /// assert!(response.starts_with("HTTP/1.1 100 Continue\r\n\r\n"));
+    /// ```
+    pub fn set_continue_hook(
+        mut self,
+        hook: impl Fn(&HTTPRequest) -> Option<HTTPResponse> + Send + Sync + 'static,
+    ) -> Self {
+        self.continue_hook = Some(Arc::new(hook));
+        self
+    }
+
     /// Converts the server configuration to a string representation.
     ///
     /// This method returns a formatted string containing the IP address and port,
@@ -154,4 +1256,26 @@ impl ServerConfig {
             self.host[0], self.host[1], self.host[2], self.host[3], self.port
         )
     }
+
+    /// Lists inconsistencies in the TLS settings, as human-readable messages.
+    pub(crate) fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.using_https && self.tls_config.is_none() {
+            problems.push("HTTPS is enabled but no TLS configuration was built".to_string());
+        }
+        if self.certificate.is_none() {
+            if self.client_verifier.is_some() {
+                problems.push(
+                    "Client certificates are configured but no server certificate was added"
+                        .to_string(),
+                );
+            }
+            if !self.alpn_protocols.is_empty() {
+                problems.push(
+                    "ALPN protocols are configured but no server certificate was added".to_string(),
+                );
+            }
+        }
+        problems
+    }
 }
@@ -0,0 +1,200 @@
+//! Server-sent events pushed to every client connected to a route.
+
+use std::fmt;
+use std::io::{self, Read};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How long a stream waits for an event before sending a keep-alive comment,
+/// which is also how quickly a disconnected client is noticed.
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// A single server-sent event.
+///
+/// Plain strings convert into events with only a `data` field.
+///
+/// # Example
+///
+/// ```rust
+/// use sunweb::webserver::SseEvent;
+///
+/// let event = SseEvent::new("line one\nline two").event("update").id("7");
+/// assert_eq!(
+///     event.to_string(),
+///     "event: update\nid: 7\ndata: line one\ndata: line two\n\n"
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SseEvent {
+    data: String,
+    event: Option<String>,
+    id: Option<String>,
+}
+
+impl SseEvent {
+    /// Creates an event carrying `data`; multi-line data is sent as several
+    /// `data:` lines.
+    pub fn new(data: &str) -> SseEvent {
+        Self {
+            data: data.to_string(),
+            event: None,
+            id: None,
+        }
+    }
+
+    /// Sets the event type clients listen for with `addEventListener`.
+    pub fn event(mut self, event: &str) -> SseEvent {
+        self.event = Some(single_line(event));
+        self
+    }
+
+    /// Sets the event ID clients send back as `Last-Event-ID` when reconnecting.
+    pub fn id(mut self, id: &str) -> SseEvent {
+        self.id = Some(single_line(id));
+        self
+    }
+}
+
+impl fmt::Display for SseEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(event) = &self.event {
+            writeln!(f, "event: {}", event)?;
+        }
+        if let Some(id) = &self.id {
+            writeln!(f, "id: {}", id)?;
+        }
+        for line in self.data.lines() {
+            writeln!(f, "data: {}", line)?;
+        }
+        if self.data.is_empty() {
+            writeln!(f, "data: ")?;
+        }
+        writeln!(f)
+    }
+}
+
+impl From<&str> for SseEvent {
+    fn from(data: &str) -> Self {
+        SseEvent::new(data)
+    }
+}
+
+impl From<String> for SseEvent {
+    fn from(data: String) -> Self {
+        SseEvent::new(&data)
+    }
+}
+
+/// Strips line breaks, which would end a field early.
+fn single_line(value: &str) -> String {
+    value.replace(['\r', '\n'], "")
+}
+
+/// Publishes events to every client connected to an SSE route.
+///
+/// Returned by [`WebServer::add_sse_route`](crate::webserver::WebServer::add_sse_route).
+/// Clones share their subscribers, so the broadcaster can be handed to other
+/// threads or route handlers. Clients are removed once their connection is
+/// closed.
+#[derive(Clone, Default)]
+pub struct SseBroadcaster {
+    subscribers: Arc<Mutex<Subscribers>>,
+}
+
+#[derive(Default)]
+struct Subscribers {
+    next_id: u64,
+    senders: Vec<(u64, Sender<Arc<[u8]>>)>,
+}
+
+impl SseBroadcaster {
+    /// Creates a broadcaster without subscribers.
+    pub fn new() -> SseBroadcaster {
+        Self::default()
+    }
+
+    /// Sends `event` to every connected client.
+    ///
+    /// Returns the number of clients it was queued for.
+    pub fn publish(&self, event: impl Into<SseEvent>) -> usize {
+        let bytes: Arc<[u8]> = event.into().to_string().into_bytes().into();
+        let mut subscribers = self.subscribers.lock().unwrap_or_else(|e| e.into_inner());
+        subscribers
+            .senders
+            .retain(|(_, sender)| sender.send(Arc::clone(&bytes)).is_ok());
+        subscribers.senders.len()
+    }
+
+    /// The number of connected clients.
+    pub fn subscriber_count(&self) -> usize {
+        self.subscribers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .senders
+            .len()
+    }
+
+    /// Registers a new client and returns the body it is streamed.
+    pub(crate) fn subscribe(&self) -> SseStream {
+        let (sender, receiver) = mpsc::channel();
+        let mut subscribers = self.subscribers.lock().unwrap_or_else(|e| e.into_inner());
+        let id = subscribers.next_id;
+        subscribers.next_id += 1;
+        subscribers.senders.push((id, sender));
+        SseStream {
+            id,
+            receiver,
+            subscribers: Arc::clone(&self.subscribers),
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl fmt::Debug for SseBroadcaster {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SseBroadcaster")
+            .field("subscribers", &self.subscriber_count())
+            .finish()
+    }
+}
+
+/// The response body of one SSE client.
+///
+/// Blocks until an event is published and sends a comment line when none
+/// arrived for a while, so writes to a closed connection fail and the stream
+/// is dropped. Dropping it unsubscribes the client.
+pub(crate) struct SseStream {
+    id: u64,
+    receiver: Receiver<Arc<[u8]>>,
+    subscribers: Arc<Mutex<Subscribers>>,
+    pending: Vec<u8>,
+}
+
+impl Read for SseStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pending.is_empty() {
+            match self.receiver.recv_timeout(KEEP_ALIVE_INTERVAL) {
+                Ok(bytes) => self.pending.extend_from_slice(&bytes),
+                Err(RecvTimeoutError::Timeout) => {
+                    self.pending.extend_from_slice(b": keep-alive\n\n")
+                }
+                Err(RecvTimeoutError::Disconnected) => return Ok(0),
+            }
+        }
+        let n = buf.len().min(self.pending.len());
+        buf[..n].copy_from_slice(&self.pending[..n]);
+        self.pending.drain(..n);
+        Ok(n)
+    }
+}
+
+impl Drop for SseStream {
+    fn drop(&mut self) {
+        self.subscribers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .senders
+            .retain(|(id, _)| *id != self.id);
+    }
+}
@@ -0,0 +1,103 @@
+//! Small helpers for writing handlers.
+
+use serde_json::Value;
+
+/// Applies a JSON Merge Patch (RFC 7386) to `target`.
+///
+/// Objects in `patch` are merged into `target` member by member, recursing
+/// into nested objects; a `null` member removes that member from `target`.
+/// Any other patch value, including arrays, replaces the target as a whole.
+///
+/// # Arguments
+///
+/// * `target` - The document to update in place.
+/// * `patch` - The merge patch, usually the body of a `PATCH` request sent
+///   as `application/merge-patch+json`.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde_json::json;
+/// use sunweb::webserver::util::merge_json;
+///
+/// let mut user = json!({
+///     "name": "Bob",
+///     "address": { "city": "Berlin", "zip": "10115" },
+///     "tags": ["admin", "staff"],
+///     "nickname": "bobby",
+/// });
+/// merge_json(&mut user, &json!({
+///     "address": { "zip": "10117", "street": { "name": "Unter den Linden" } },
+///     "tags": ["staff"],
+///     "nickname": null,
+///     "phone": { "home": null },
+/// }));
+///
+/// assert_eq!(user, json!({
+///     "name": "Bob",
+///     "address": { "city": "Berlin", "zip": "10117", "street": { "name": "Unter den Linden" } },
+///     "tags": ["staff"],
+///     "phone": {},
+/// }));
+///
+/// // A patch that is not an object replaces the document.
+/// merge_json(&mut user, &json!([1, 2]));
+/// assert_eq!(user, json!([1, 2]));
+///
+/// // An object patch turns a non-object target into an object.
+/// let mut value = json!("text");
+/// merge_json(&mut value, &json!({ "a": { "b": null }, "c": null }));
+/// assert_eq!(value, json!({ "a": {} }));
+/// ```
+///
+/// Used from a `PATCH` handler:
+///
+/// ```rust
+/// use serde_json::{Value, json};
+/// use std::sync::{Arc, Mutex};
+/// use sunweb::webserver::responses::HTTPResponse;
+/// use sunweb::webserver::responses::status_code::StatusCode;
+/// use sunweb::webserver::route::HTTPMethod;
+/// use sunweb::webserver::util::merge_json;
+/// use sunweb::webserver::{ServerConfig, WebServer};
+///
+/// let stored = Arc::new(Mutex::new(json!({ "title": "Draft", "done": false })));
+/// let document = Arc::clone(&stored);
+///
+/// let mut server = WebServer::new(ServerConfig::new([127, 0, 0, 1], 8080));
+/// server.add_custom_route("/todo", HTTPMethod::PATCH, move |request, _domain| {
+///     let Some(patch) = request.body().and_then(|body| serde_json::from_slice::<Value>(body).ok()) else {
+///         return HTTPResponse::bad_request();
+///     };
+///     let mut document = document.lock().unwrap();
+///     merge_json(&mut document, &patch);
+///     let mut response = HTTPResponse::ok();
+///     response.json_value(&document);
+///     response
+/// }, StatusCode::Ok, None);
+///
+/// let raw = b"PATCH /todo HTTP/1.1\r\nHost: localhost\r\nContent-Length: 14\r\n\r\n{\"done\": true}";
+/// let response = server.handle_request(raw, "127.0.0.1:50000".parse().unwrap());
+/// assert!(response.starts_with(b"HTTP/1.1 200 OK"));
+/// assert_eq!(*stored.lock().unwrap(), json!({ "title": "Draft", "done": true }));
+/// ```
+pub fn merge_json(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Default::default());
+    }
+    let Value::Object(target) = target else {
+        return;
+    };
+
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(key);
+        } else {
+            merge_json(target.entry(key.as_str()).or_insert(Value::Null), value);
+        }
+    }
+}